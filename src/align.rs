@@ -1,10 +1,15 @@
 use std::cmp::max;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::iter;
 
 const SUBSTITUTION_COST: usize = 1;
 const DELETION_COST: usize = 1;
 const INSERTION_COST: usize = 1;
 
+// Under --intra-line-diff-algorithm histogram, tokens occurring more often than this in the old
+// line are not used as anchors.
+const HISTOGRAM_MAX_OCCURRENCES: usize = 64;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Operation {
     NoOp,
@@ -15,6 +20,22 @@ pub enum Operation {
 
 use Operation::*;
 
+/// The algorithm used to align the tokens of a pair of homologous lines (see
+/// --intra-line-diff-algorithm).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntraLineDiffAlgorithm {
+    // A minimal edit distance alignment of all the tokens.
+    Myers,
+    // Tokens occurring exactly once in each line are matched first, keeping the longest sequence
+    // of them that is in the same order in both lines. The tokens between those matches are
+    // aligned as under Myers.
+    Patience,
+    // The longest run of common tokens that starts with one of the tokens occurring least often is
+    // matched, and the tokens on either side of it are aligned in the same way, falling back to
+    // Myers when no token is common to both.
+    Histogram,
+}
+
 /// Needleman-Wunsch / Wagner-Fischer table for computation of edit distance and associated
 /// alignment.
 #[derive(Clone)]
@@ -27,13 +48,78 @@ struct Cell {
 pub struct Alignment<'a> {
     pub x: Vec<&'a str>,
     pub y: Vec<&'a str>,
+    operations: Vec<Operation>,
+}
+
+impl<'a> Alignment<'a> {
+    /// Compute the alignment of y with x using the given algorithm.
+    pub fn new_with_algorithm(
+        x: Vec<&'a str>,
+        y: Vec<&'a str>,
+        algorithm: IntraLineDiffAlgorithm,
+    ) -> Self {
+        let operations = match algorithm {
+            IntraLineDiffAlgorithm::Myers => EditDistanceTable::new(&x, &y).operations(),
+            IntraLineDiffAlgorithm::Patience | IntraLineDiffAlgorithm::Histogram => {
+                let mut operations = Vec::with_capacity(max(x.len(), y.len()));
+                push_anchored_operations(&x, &y, algorithm, &mut operations);
+                operations
+            }
+        };
+        Self { x, y, operations }
+    }
+
+    /// The edit operations transforming x into y.
+    pub fn operations(&self) -> Vec<Operation> {
+        self.operations.clone()
+    }
+
+    pub fn coalesced_operations(&self) -> Vec<(Operation, usize)> {
+        run_length_encode(self.operations())
+    }
+
+    /// Compute custom distance metric from the alignment. The distance metric is
+    ///
+    /// (total length of edits) / (total length of longer string)
+    ///
+    /// where length is measured in number of unicode grapheme clusters.
+    #[allow(dead_code)]
+    pub fn distance(&self) -> f64 {
+        let (numer, denom) = self.distance_parts();
+        (numer as f64) / (denom as f64)
+    }
+
+    #[allow(dead_code)]
+    pub fn distance_parts(&self) -> (usize, usize) {
+        let (mut numer, mut denom) = (0, 0);
+        for op in self.operations() {
+            if op != NoOp {
+                numer += 1;
+            }
+            denom += 1;
+        }
+        (numer, denom)
+    }
+
+    /// Compute levenshtein distance from the alignment. Every edit operation has unit cost, so
+    /// this is the number of edit operations (for Myers; the other algorithms do not minimize
+    /// it).
+    #[allow(dead_code)]
+    pub fn levenshtein_distance(&self) -> usize {
+        self.operations.iter().filter(|op| **op != NoOp).count()
+    }
+}
+
+struct EditDistanceTable<'a, 'b> {
+    x: &'b [&'a str],
+    y: &'b [&'a str],
     table: Vec<Cell>,
     dim: [usize; 2],
 }
 
-impl<'a> Alignment<'a> {
+impl<'a, 'b> EditDistanceTable<'a, 'b> {
     /// Fill table for Levenshtein distance / alignment computation
-    pub fn new(x: Vec<&'a str>, y: Vec<&'a str>) -> Self {
+    fn new(x: &'b [&'a str], y: &'b [&'a str]) -> Self {
         let dim = [y.len() + 1, x.len() + 1];
         let table = vec![
             Cell {
@@ -43,27 +129,27 @@ impl<'a> Alignment<'a> {
             };
             dim[0] * dim[1]
         ];
-        let mut alignment = Self { x, y, table, dim };
-        alignment.fill();
-        alignment
+        let mut table = Self { x, y, table, dim };
+        table.fill();
+        table
     }
 
     /// Fill table for Levenshtein distance / alignment computation
-    pub fn fill(&mut self) {
+    fn fill(&mut self) {
         // x is written along the top of the table; y is written down the left side of the
         // table. Also, we insert a 0 in cell (0, 0) of the table, so x and y are shifted by one
         // position. Therefore, the element corresponding to (x[i], y[j]) is in column (i + 1) and
         // row (j + 1); the index of this element is given by index(i, j).
         for i in 1..self.dim[1] {
             self.table[i] = Cell {
-                parent: 0,
+                parent: i - 1,
                 operation: Deletion,
                 cost: i,
             };
         }
         for j in 1..self.dim[0] {
             self.table[j * self.dim[1]] = Cell {
-                parent: 0,
+                parent: (j - 1) * self.dim[1],
                 operation: Insertion,
                 cost: j,
             };
@@ -102,7 +188,7 @@ impl<'a> Alignment<'a> {
     }
 
     /// Read edit operations from the table.
-    pub fn operations(&self) -> Vec<Operation> {
+    fn operations(&self) -> Vec<Operation> {
         let mut ops = VecDeque::with_capacity(max(self.x.len(), self.y.len()));
         let mut cell = &self.table[self.index(self.x.len(), self.y.len())];
         loop {
@@ -115,39 +201,6 @@ impl<'a> Alignment<'a> {
         Vec::from(ops)
     }
 
-    pub fn coalesced_operations(&self) -> Vec<(Operation, usize)> {
-        run_length_encode(self.operations())
-    }
-
-    /// Compute custom distance metric from the filled table. The distance metric is
-    ///
-    /// (total length of edits) / (total length of longer string)
-    ///
-    /// where length is measured in number of unicode grapheme clusters.
-    #[allow(dead_code)]
-    pub fn distance(&self) -> f64 {
-        let (numer, denom) = self.distance_parts();
-        (numer as f64) / (denom as f64)
-    }
-
-    #[allow(dead_code)]
-    pub fn distance_parts(&self) -> (usize, usize) {
-        let (mut numer, mut denom) = (0, 0);
-        for op in self.operations() {
-            if op != NoOp {
-                numer += 1;
-            }
-            denom += 1;
-        }
-        (numer, denom)
-    }
-
-    /// Compute levenshtein distance from the filled table.
-    #[allow(dead_code)]
-    pub fn levenshtein_distance(&self) -> usize {
-        self.table[self.index(self.x.len(), self.y.len())].cost
-    }
-
     // Row-major storage of 2D array.
     fn index(&self, i: usize, j: usize) -> usize {
         j * self.dim[1] + i
@@ -190,6 +243,139 @@ impl<'a> Alignment<'a> {
     }
 }
 
+/// Append the operations aligning y with x under the patience or histogram algorithm: the common
+/// prefix and suffix are matched, then the anchors chosen by the algorithm, and the tokens between
+/// the anchors are aligned recursively.
+fn push_anchored_operations(
+    x: &[&str],
+    y: &[&str],
+    algorithm: IntraLineDiffAlgorithm,
+    operations: &mut Vec<Operation>,
+) {
+    let prefix = x.iter().zip(y).take_while(|(x_i, y_j)| x_i == y_j).count();
+    let suffix = x[prefix..]
+        .iter()
+        .rev()
+        .zip(y[prefix..].iter().rev())
+        .take_while(|(x_i, y_j)| x_i == y_j)
+        .count();
+    let (x, y) = (&x[prefix..x.len() - suffix], &y[prefix..y.len() - suffix]);
+    operations.extend(iter::repeat(NoOp).take(prefix));
+    let anchors = match algorithm {
+        IntraLineDiffAlgorithm::Patience => get_patience_anchors(x, y),
+        _ => get_histogram_anchor(x, y).into_iter().collect(),
+    };
+    if anchors.is_empty() {
+        push_edit_distance_operations(x, y, operations);
+    } else {
+        let (mut i, mut j) = (0, 0);
+        for (anchor_i, anchor_j, length) in anchors {
+            push_anchored_operations(&x[i..anchor_i], &y[j..anchor_j], algorithm, operations);
+            operations.extend(iter::repeat(NoOp).take(length));
+            i = anchor_i + length;
+            j = anchor_j + length;
+        }
+        push_anchored_operations(&x[i..], &y[j..], algorithm, operations);
+    }
+    operations.extend(iter::repeat(NoOp).take(suffix));
+}
+
+/// Append the operations of a minimal edit distance alignment of y with x.
+fn push_edit_distance_operations(x: &[&str], y: &[&str], operations: &mut Vec<Operation>) {
+    if x.is_empty() || y.is_empty() {
+        operations.extend(iter::repeat(Deletion).take(x.len()));
+        operations.extend(iter::repeat(Insertion).take(y.len()));
+    } else {
+        operations.extend(EditDistanceTable::new(x, y).operations());
+    }
+}
+
+/// Return the matches, as (x index, y index, length), of the tokens occurring exactly once in
+/// each of x and y that form the longest sequence in the same order in both.
+fn get_patience_anchors(x: &[&str], y: &[&str]) -> Vec<(usize, usize, usize)> {
+    let mut occurrences = HashMap::<&str, (usize, usize, usize, usize)>::new();
+    for (i, token) in x.iter().enumerate() {
+        let entry = occurrences.entry(token).or_insert((0, 0, 0, 0));
+        entry.0 += 1;
+        entry.2 = i;
+    }
+    for (j, token) in y.iter().enumerate() {
+        if let Some(entry) = occurrences.get_mut(token) {
+            entry.1 += 1;
+            entry.3 = j;
+        }
+    }
+    let mut unique_matches: Vec<(usize, usize)> = occurrences
+        .values()
+        .filter(|(x_count, y_count, _, _)| *x_count == 1 && *y_count == 1)
+        .map(|(_, _, i, j)| (*i, *j))
+        .collect();
+    unique_matches.sort_unstable();
+
+    // Patience sorting: the longest subsequence of the matches (in x order) that is increasing in
+    // y. piles[k] is the index of the match ending the best such subsequence of length k + 1 found
+    // so far, and predecessors[m] the match preceding match m in it.
+    let mut piles: Vec<usize> = Vec::new();
+    let mut predecessors = vec![None; unique_matches.len()];
+    for (m, (_, j)) in unique_matches.iter().enumerate() {
+        let k = piles.partition_point(|&pile| unique_matches[pile].1 < *j);
+        if k > 0 {
+            predecessors[m] = Some(piles[k - 1]);
+        }
+        if k == piles.len() {
+            piles.push(m);
+        } else {
+            piles[k] = m;
+        }
+    }
+    let mut anchors = VecDeque::new();
+    let mut m = piles.last().copied();
+    while let Some(match_index) = m {
+        let (i, j) = unique_matches[match_index];
+        anchors.push_front((i, j, 1));
+        m = predecessors[match_index];
+    }
+    Vec::from(anchors)
+}
+
+/// Return the longest run of tokens common to x and y, as (x index, y index, length), among those
+/// starting with a token that occurs least often in x.
+fn get_histogram_anchor(x: &[&str], y: &[&str]) -> Option<(usize, usize, usize)> {
+    let mut positions = HashMap::<&str, Vec<usize>>::new();
+    for (i, token) in x.iter().enumerate() {
+        positions.entry(token).or_default().push(i);
+    }
+    // The best anchor so far, with the number of occurrences of its first token.
+    let mut best: Option<(usize, (usize, usize, usize))> = None;
+    for (j, token) in y.iter().enumerate() {
+        let token_positions = match positions.get(token) {
+            Some(token_positions) if token_positions.len() <= HISTOGRAM_MAX_OCCURRENCES => {
+                token_positions
+            }
+            _ => continue,
+        };
+        let n_occurrences = token_positions.len();
+        for &i in token_positions {
+            let length = x[i..]
+                .iter()
+                .zip(&y[j..])
+                .take_while(|(x_i, y_j)| x_i == y_j)
+                .count();
+            let is_better = match best {
+                Some((best_n_occurrences, (_, _, best_length))) => {
+                    n_occurrences < best_n_occurrences
+                        || n_occurrences == best_n_occurrences && length > best_length
+                }
+                None => true,
+            };
+            if is_better {
+                best = Some((n_occurrences, (i, j, length)));
+            }
+        }
+    }
+    best.map(|(_, anchor)| anchor)
+}
+
 fn run_length_encode<T>(sequence: Vec<T>) -> Vec<(T, usize)>
 where
     T: Copy,
@@ -289,6 +475,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_operations_reaching_table_edge() {
+        assert_eq!(operations("abc", "c"), vec![Deletion, Deletion, NoOp]);
+        assert_eq!(operations("c", "abc"), vec![Insertion, Insertion, NoOp]);
+    }
+
+    #[test]
+    fn test_intra_line_diff_algorithms() {
+        // A variable is renamed and two arguments are swapped: patience keeps "price" unchanged
+        // and moves "tax", whereas the others substitute one argument for the other.
+        let (before, after) = ("let total = add(price, tax);", "let sum = add(tax, price);");
+        assert_eq!(
+            word_coalesced_operations(before, after, IntraLineDiffAlgorithm::Myers),
+            vec![
+                (NoOp, 2),
+                (Substitution, 1), // total sum
+                (NoOp, 5),
+                (Substitution, 1), // price tax
+                (NoOp, 2),
+                (Substitution, 1), // tax price
+                (NoOp, 2)
+            ]
+        );
+        assert_eq!(
+            word_coalesced_operations(before, after, IntraLineDiffAlgorithm::Patience),
+            vec![
+                (NoOp, 2),
+                (Substitution, 1), // total sum
+                (NoOp, 5),
+                (Deletion, 3),  // price, -
+                (NoOp, 1),      // tax tax
+                (Insertion, 3), // - , price
+                (NoOp, 2)
+            ]
+        );
+        assert_eq!(
+            word_coalesced_operations(before, after, IntraLineDiffAlgorithm::Histogram),
+            word_coalesced_operations(before, after, IntraLineDiffAlgorithm::Myers),
+        );
+
+        // Every algorithm accounts for all the tokens of both lines.
+        for (before, after) in [
+            (before, after),
+            ("a a b c d", "b c d a a"),
+            (
+                "fn f(x: u8) -> u8 { x }",
+                "fn g(y: u8, x: u8) -> u8 { x + y }",
+            ),
+            ("", "x"),
+        ] {
+            for algorithm in [
+                IntraLineDiffAlgorithm::Myers,
+                IntraLineDiffAlgorithm::Patience,
+                IntraLineDiffAlgorithm::Histogram,
+            ] {
+                let alignment = word_alignment(before, after, algorithm);
+                let operations = alignment.operations();
+                let n_x = operations.iter().filter(|op| **op != Insertion).count();
+                let n_y = operations.iter().filter(|op| **op != Deletion).count();
+                assert_eq!((n_x, n_y), (alignment.x.len(), alignment.y.len()));
+            }
+        }
+    }
+
+    fn word_alignment<'a>(
+        x: &'a str,
+        y: &'a str,
+        algorithm: IntraLineDiffAlgorithm,
+    ) -> Alignment<'a> {
+        let regex = regex::Regex::new(r"\w+").unwrap();
+        Alignment::new_with_algorithm(
+            crate::edits::tokenize(x, &regex),
+            crate::edits::tokenize(y, &regex),
+            algorithm,
+        )
+    }
+
+    fn word_coalesced_operations(
+        x: &str,
+        y: &str,
+        algorithm: IntraLineDiffAlgorithm,
+    ) -> Vec<(Operation, usize)> {
+        word_alignment(x, y, algorithm).coalesced_operations()
+    }
+
     fn assert_string_distance_parts(s1: &str, s2: &str, parts: (usize, usize)) {
         let (numer, _) = parts;
         assert_string_levenshtein_distance(s1, s2, numer);
@@ -306,7 +577,7 @@ mod tests {
             x.graphemes(true).collect::<Vec<&str>>(),
             y.graphemes(true).collect::<Vec<&str>>(),
         );
-        Alignment::new(x, y).distance_parts()
+        Alignment::new_with_algorithm(x, y, IntraLineDiffAlgorithm::Myers).distance_parts()
     }
 
     fn string_levenshtein_distance(x: &str, y: &str) -> usize {
@@ -314,7 +585,7 @@ mod tests {
             x.graphemes(true).collect::<Vec<&str>>(),
             y.graphemes(true).collect::<Vec<&str>>(),
         );
-        Alignment::new(x, y).levenshtein_distance()
+        Alignment::new_with_algorithm(x, y, IntraLineDiffAlgorithm::Myers).levenshtein_distance()
     }
 
     fn operations<'a>(x: &'a str, y: &'a str) -> Vec<Operation> {
@@ -322,6 +593,6 @@ mod tests {
             x.graphemes(true).collect::<Vec<&str>>(),
             y.graphemes(true).collect::<Vec<&str>>(),
         );
-        Alignment::new(x, y).operations()
+        Alignment::new_with_algorithm(x, y, IntraLineDiffAlgorithm::Myers).operations()
     }
 }
@@ -41,6 +41,21 @@ impl OutputType {
         })
     }
 
+    /// Like from_mode, for output of `n_output_lines` lines that has been rendered in advance. The
+    /// output is written to stdout under --pager-quit-if-smaller-than if it has fewer lines than
+    /// the given number.
+    pub fn from_mode_for_output_size(
+        mode: PagingMode,
+        pager: Option<&str>,
+        n_output_lines: usize,
+        config: &config::Config,
+    ) -> Result<Self> {
+        match config.pager_quit_if_smaller_than {
+            Some(n) if n_output_lines < n => Ok(OutputType::stdout()),
+            _ => Self::from_mode(mode, pager, config),
+        }
+    }
+
     /// Try to launch the pager. Fall back to stdout in case of errors.
     fn try_pager(
         quit_if_one_screen: bool,
@@ -89,7 +104,12 @@ impl OutputType {
 
                 let mut process = if is_less {
                     let mut p = Command::new(&pager_path);
-                    if args.is_empty() || replace_arguments_to_less {
+                    if let Some(pager_less_options) = config.pager_less_options.as_ref() {
+                        p.env("LESS", pager_less_options);
+                        if !replace_arguments_to_less {
+                            p.args(args);
+                        }
+                    } else if args.is_empty() || replace_arguments_to_less {
                         p.args(vec!["--RAW-CONTROL-CHARS"]);
 
                         // Passing '--no-init' fixes a bug with '--quit-if-one-screen' in older
@@ -125,8 +145,9 @@ impl OutputType {
                     p
                 };
                 if config.navigate {
-                    process.args(&["--pattern", &config::make_navigate_regexp(&config)]);
+                    process.args(&["--pattern", &config.navigate_regexp]);
                 }
+                process.args(&config.pager_extra_args);
                 Ok(process
                     .env("LESSANSIENDCHARS", "mK")
                     .stdin(Stdio::piped())
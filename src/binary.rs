@@ -0,0 +1,264 @@
+//! Rendering of binary files from the "GIT binary patch" sections emitted by `git diff --binary`.
+//!
+//! A binary patch consists of two hunks: the forward hunk, which produces the new content, and
+//! the reverse hunk, which produces the old content. Each hunk is either a "literal" hunk holding
+//! the complete (zlib-compressed, base85-encoded) content, or a "delta" hunk holding
+//! instructions for transforming the content on the other side. Only literal hunks can be
+//! displayed, because the blobs that delta hunks refer to are not available in the diff.
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::config::Config;
+use crate::style::Style;
+
+const BASE85_ALPHABET: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+const BYTES_PER_ROW: usize = 8;
+
+#[derive(Debug, PartialEq)]
+enum BinaryHunkKind {
+    Literal,
+    Delta,
+}
+
+struct BinaryHunk {
+    kind: BinaryHunkKind,
+    size: usize,
+    data: Vec<u8>,
+}
+
+/// A "GIT binary patch" section, built up one line at a time.
+pub struct BinaryPatch {
+    // The path of the file, if no file header has been written for it.
+    pub file_path: Option<String>,
+    hunks: Vec<BinaryHunk>,
+    in_hunk: bool,
+    is_valid: bool,
+}
+
+impl BinaryPatch {
+    pub fn new(file_path: Option<String>) -> Self {
+        Self {
+            file_path,
+            hunks: Vec::new(),
+            in_hunk: false,
+            is_valid: true,
+        }
+    }
+
+    /// Add a line following the "GIT binary patch" line. Return false if the line is not part of
+    /// the binary patch.
+    pub fn push_line(&mut self, line: &str) -> bool {
+        if self.in_hunk {
+            if line.is_empty() {
+                self.in_hunk = false;
+            } else {
+                match decode_base85_line(line) {
+                    Some(bytes) => self.hunks.last_mut().unwrap().data.extend(bytes),
+                    None => self.is_valid = false,
+                }
+            }
+            return true;
+        }
+        let (kind, size) = if let Some(size) = line.strip_prefix("literal ") {
+            (BinaryHunkKind::Literal, size)
+        } else if let Some(size) = line.strip_prefix("delta ") {
+            (BinaryHunkKind::Delta, size)
+        } else {
+            return false;
+        };
+        let size = size.parse::<usize>().unwrap_or_else(|_| {
+            self.is_valid = false;
+            0
+        });
+        self.hunks.push(BinaryHunk {
+            kind,
+            size,
+            data: Vec::new(),
+        });
+        self.in_hunk = true;
+        true
+    }
+
+    /// Whether both the forward and the reverse hunk have been read.
+    pub fn is_complete(&self) -> bool {
+        self.hunks.len() == 2 && !self.in_hunk
+    }
+
+    /// Return the old and new file contents, if the patch holds both literally and neither is
+    /// larger than `max_bytes`.
+    pub fn get_contents(&self, max_bytes: u64) -> Option<(Vec<u8>, Vec<u8>)> {
+        if !self.is_valid || !self.is_complete() {
+            return None;
+        }
+        let (forward, reverse) = (&self.hunks[0], &self.hunks[1]);
+        for hunk in &[forward, reverse] {
+            if hunk.kind != BinaryHunkKind::Literal || hunk.size as u64 > max_bytes {
+                return None;
+            }
+        }
+        Some((inflate(reverse)?, inflate(forward)?))
+    }
+}
+
+/// Decompress the content of a literal hunk.
+fn inflate(hunk: &BinaryHunk) -> Option<Vec<u8>> {
+    let mut content = Vec::with_capacity(hunk.size);
+    ZlibDecoder::new(&hunk.data[..])
+        .read_to_end(&mut content)
+        .ok()?;
+    if content.len() == hunk.size {
+        Some(content)
+    } else {
+        None
+    }
+}
+
+/// Decode one line of git's base85 encoding. The first character gives the number of decoded
+/// bytes: 'A'-'Z' for 1-26 and 'a'-'z' for 27-52.
+fn decode_base85_line(line: &str) -> Option<Vec<u8>> {
+    let line = line.as_bytes();
+    let len = match line.first()? {
+        c @ b'A'..=b'Z' => (c - b'A') as usize + 1,
+        c @ b'a'..=b'z' => (c - b'a') as usize + 27,
+        _ => return None,
+    };
+    let encoded = &line[1..];
+    if encoded.len() != (len + 3) / 4 * 5 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(len);
+    for group in encoded.chunks(5) {
+        let mut value: u32 = 0;
+        for c in group {
+            let digit = BASE85_ALPHABET.iter().position(|a| a == c)? as u32;
+            value = value.checked_mul(85)?.checked_add(digit)?;
+        }
+        bytes.extend(&value.to_be_bytes());
+    }
+    bytes.truncate(len);
+    Some(bytes)
+}
+
+/// Return the lines of a hex dump showing the old content on the left and the new content on
+/// the right. Bytes that differ from the byte at the same offset on the other side are painted in
+/// the minus and plus emph styles.
+pub fn format_hex_dump(old: &[u8], new: &[u8], config: &Config) -> Vec<String> {
+    let rows = (old.len().max(new.len()) + BYTES_PER_ROW - 1) / BYTES_PER_ROW;
+    (0..rows)
+        .map(|row| {
+            let offset = row * BYTES_PER_ROW;
+            format!(
+                "{:08x}  {}  │  {}",
+                offset,
+                format_hex_dump_row(old, new, offset, config.minus_emph_style),
+                format_hex_dump_row(new, old, offset, config.plus_emph_style),
+            )
+        })
+        .collect()
+}
+
+/// Format the bytes of `content` in the row starting at `offset`, in hex followed by printable
+/// ASCII, painting those that differ from `other_content` in `changed_style`.
+fn format_hex_dump_row(
+    content: &[u8],
+    other_content: &[u8],
+    offset: usize,
+    changed_style: Style,
+) -> String {
+    let mut hex = Vec::new();
+    let mut ascii = String::new();
+    for i in offset..offset + BYTES_PER_ROW {
+        match content.get(i) {
+            Some(byte) => {
+                let (hex_digits, character) = (
+                    format!("{:02x}", byte),
+                    if byte.is_ascii_graphic() || *byte == b' ' {
+                        (*byte as char).to_string()
+                    } else {
+                        ".".to_string()
+                    },
+                );
+                if other_content.get(i) == Some(byte) {
+                    hex.push(hex_digits);
+                    ascii.push_str(&character);
+                } else {
+                    hex.push(changed_style.ansi_term_style.paint(hex_digits).to_string());
+                    ascii.push_str(&changed_style.ansi_term_style.paint(character).to_string());
+                }
+            }
+            None => {
+                hex.push("  ".to_string());
+                ascii.push(' ');
+            }
+        }
+    }
+    format!("{} {}", hex.join(" "), ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils;
+
+    #[test]
+    fn test_decode_base85_line() {
+        assert_eq!(
+            decode_base85_line("WcmeZB&B-ZXU}P@OFUm<#R0IGdo&;n7"),
+            Some(vec![
+                0x78, 0x01, 0xf3, 0x48, 0xcd, 0xc9, 0x29, 0x60, 0x60, 0x64, 0x2e, 0xcf, 0x2f, 0xca,
+                0x49, 0x51, 0x54, 0x04, 0x00, 0x24, 0x9e, 0x04, 0x64
+            ])
+        );
+        assert_eq!(decode_base85_line("W!"), None);
+        assert_eq!(decode_base85_line(""), None);
+    }
+
+    #[test]
+    fn test_binary_patch() {
+        let mut patch = BinaryPatch::new(None);
+        for line in &[
+            "literal 15",
+            "WcmeZB&B-ZXU}P@OFUm<#R0IGdo&;n7",
+            "",
+            "literal 13",
+            "UcmeZB&B@7UU}P%KFUm;)02}B8AOHXW",
+        ] {
+            assert!(patch.push_line(line));
+            assert!(!patch.is_complete());
+        }
+        assert!(patch.push_line(""));
+        assert!(patch.is_complete());
+        assert!(!patch.push_line("diff --git a/y.bin b/y.bin"));
+
+        let (old, new) = patch.get_contents(15).unwrap();
+        assert_eq!(old, b"Hello\x00\x01\x02world");
+        assert_eq!(new, b"Hellp\x00\x01\x03world!!");
+        assert_eq!(patch.get_contents(14), None);
+    }
+
+    #[test]
+    fn test_format_hex_dump() {
+        let config = integration_test_utils::make_config(&[]);
+        let lines = format_hex_dump(
+            b"Hello\x00\x01\x02world",
+            b"Hellp\x00\x01\x03world!!",
+            &config,
+        );
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|l| strip_ansi_codes(l).to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "00000000  48 65 6c 6c 6f 00 01 02 Hello...  │  48 65 6c 6c 70 00 01 03 Hellp...",
+                "00000008  77 6f 72 6c 64          world     │  77 6f 72 6c 64 21 21    world!! ",
+            ]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Validation of a diff file for --check. The file is read line by line, as delta reads its
+//! input, but nothing is displayed: instead, malformed hunk headers, hunks whose length does not
+//! match their header, and unrecognized lines in the file header sections are reported.
+use std::fmt;
+
+use console::strip_ansi_codes;
+
+use crate::parse;
+
+/// A problem found in a diff, at the given (1-based) line.
+#[derive(Debug, PartialEq)]
+pub struct CheckError {
+    pub line_number: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line_number {
+            Some(line_number) => write!(f, "line {}: {}", line_number, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The extended header lines that may follow a "diff" line in git output.
+const FILE_META_PREFIXES: [&str; 16] = [
+    "index ",
+    "mode ",
+    "old mode ",
+    "new mode ",
+    "deleted file mode ",
+    "new file mode ",
+    "copy from ",
+    "copy to ",
+    "rename from ",
+    "rename to ",
+    "rename old ",
+    "rename new ",
+    "similarity index ",
+    "dissimilarity index ",
+    "Binary files ",
+    "GIT binary patch",
+];
+
+enum Section {
+    // Commit metadata, diff stats, and anything else that is not part of a file's diff.
+    Other,
+    // The lines following a "diff" line, up to the "---" line or first hunk.
+    FileMeta,
+    // The base85-encoded data following "GIT binary patch".
+    BinaryPatch,
+    Hunk {
+        header_line_number: usize,
+        // The number of lines in each line number range of the hunk header (the old file(s)
+        // followed by the new file), and the number of lines found so far.
+        expected_counts: Vec<usize>,
+        counts: Vec<usize>,
+    },
+}
+
+struct Checker {
+    section: Section,
+    // The line number of a "---" line that is still to be followed by a "+++" line.
+    minus_file_line_number: Option<usize>,
+    has_file_header: bool,
+    has_diff: bool,
+    errors: Vec<CheckError>,
+}
+
+/// Return the problems found in `input`, a diff in any of the formats accepted by delta.
+pub fn check_diff(input: &str) -> Vec<CheckError> {
+    let mut checker = Checker {
+        section: Section::Other,
+        minus_file_line_number: None,
+        has_file_header: false,
+        has_diff: false,
+        errors: Vec::new(),
+    };
+    for (i, line) in input.lines().enumerate() {
+        checker.check_line(i + 1, &strip_ansi_codes(line));
+    }
+    checker.finish()
+}
+
+impl Checker {
+    fn check_line(&mut self, line_number: usize, line: &str) {
+        let follows_minus_file_line = match self.minus_file_line_number.take() {
+            Some(minus_file_line_number) => {
+                if !line.starts_with("+++ ") {
+                    self.error(
+                        minus_file_line_number,
+                        "\"---\" line is not followed by a \"+++\" line".to_string(),
+                    );
+                }
+                true
+            }
+            None => false,
+        };
+        if self.check_hunk_line(line_number, line) {
+            return;
+        }
+        if line.starts_with("diff ") {
+            self.section = Section::FileMeta;
+            self.has_file_header = false;
+            self.has_diff = true;
+        } else if line.starts_with("--- ") {
+            self.minus_file_line_number = Some(line_number);
+        } else if line.starts_with("+++ ") {
+            if !follows_minus_file_line {
+                self.error(
+                    line_number,
+                    "\"+++\" line is not preceded by a \"---\" line".to_string(),
+                );
+            }
+            self.section = Section::Other;
+            self.has_file_header = true;
+            self.has_diff = true;
+        } else if line.starts_with("@@") {
+            self.start_hunk(line_number, line);
+        } else if matches!(self.section, Section::FileMeta) {
+            if line.starts_with("GIT binary patch") {
+                self.section = Section::BinaryPatch;
+            } else if line.is_empty() || line.starts_with("commit ") {
+                self.section = Section::Other;
+            } else if !FILE_META_PREFIXES
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+            {
+                self.error(
+                    line_number,
+                    format!("unrecognized line in file header: {}", line),
+                );
+            }
+        }
+    }
+
+    /// If the line belongs to the current hunk, check it against the hunk header and return
+    /// true. Otherwise, end the hunk.
+    fn check_hunk_line(&mut self, line_number: usize, line: &str) -> bool {
+        let (header_line_number, expected_counts, counts) = match &mut self.section {
+            Section::Hunk {
+                header_line_number,
+                expected_counts,
+                counts,
+            } => (*header_line_number, expected_counts, counts),
+            _ => return false,
+        };
+        let is_complete = counts == expected_counts;
+        if line.starts_with('\\') {
+            // "\ No newline at end of file"
+            return true;
+        }
+        let ranges = get_hunk_line_ranges(line, counts.len() - 1).filter(|_| {
+            !(is_complete
+                && (line.is_empty()
+                    || line.starts_with("--- ")
+                    || line.starts_with("+++ ")
+                    || line == "-- "))
+        });
+        match ranges {
+            Some(ranges) => {
+                let is_too_long = ranges
+                    .iter()
+                    .zip(counts.iter().zip(expected_counts.iter()))
+                    .any(|(in_range, (count, expected_count))| {
+                        *in_range && count == expected_count
+                    });
+                if is_too_long {
+                    let message = format!(
+                        "hunk at line {} has more lines than its header states ({})",
+                        header_line_number,
+                        format_counts(expected_counts)
+                    );
+                    self.section = Section::Other;
+                    self.error(line_number, message);
+                } else {
+                    for (count, in_range) in counts.iter_mut().zip(ranges) {
+                        if in_range {
+                            *count += 1;
+                        }
+                    }
+                }
+                true
+            }
+            None => {
+                if !is_complete {
+                    let message = format!(
+                        "hunk has fewer lines than its header states (expected {}, found {})",
+                        format_counts(expected_counts),
+                        format_counts(counts)
+                    );
+                    self.error(header_line_number, message);
+                }
+                self.section = Section::Other;
+                false
+            }
+        }
+    }
+
+    fn start_hunk(&mut self, line_number: usize, line: &str) {
+        if !parse::is_valid_hunk_header_line(line) {
+            self.section = Section::Other;
+            self.error(line_number, format!("malformed hunk header: {}", line));
+            return;
+        }
+        if !self.has_file_header {
+            self.error(
+                line_number,
+                "hunk header is not preceded by \"---\" and \"+++\" lines".to_string(),
+            );
+        }
+        let expected_counts: Vec<usize> = parse::parse_hunk_line_number_ranges(line)
+            .iter()
+            .map(|(_, count)| *count)
+            .collect();
+        self.section = Section::Hunk {
+            header_line_number: line_number,
+            counts: vec![0; expected_counts.len()],
+            expected_counts,
+        };
+        self.has_diff = true;
+    }
+
+    fn finish(mut self) -> Vec<CheckError> {
+        if let Some(minus_file_line_number) = self.minus_file_line_number.take() {
+            self.error(
+                minus_file_line_number,
+                "\"---\" line is not followed by a \"+++\" line".to_string(),
+            );
+        }
+        // A line that cannot belong to a hunk ends the hunk that is still open, if any.
+        self.check_hunk_line(0, "diff");
+        if !self.has_diff {
+            self.errors.push(CheckError {
+                line_number: None,
+                message: "no diff found".to_string(),
+            });
+        }
+        self.errors
+    }
+
+    fn error(&mut self, line_number: usize, message: String) {
+        self.errors.push(CheckError {
+            line_number: Some(line_number),
+            message,
+        });
+    }
+}
+
+/// If `line` is a hunk line of a hunk with `n_parents` old files (1, or more for a combined diff
+/// of a merge), return, for each line number range of the hunk header, whether the line belongs
+/// to it. An empty line is taken to be a context line whose trailing space was removed.
+fn get_hunk_line_ranges(line: &str, n_parents: usize) -> Option<Vec<bool>> {
+    let prefix: Vec<char> = if line.is_empty() {
+        vec![' '; n_parents]
+    } else {
+        line.chars().take(n_parents).collect()
+    };
+    if prefix.len() != n_parents || !prefix.iter().all(|c| matches!(c, ' ' | '-' | '+')) {
+        return None;
+    }
+    // A removed line belongs to the old files in whose column it has a '-'; any other line
+    // belongs to the new file and to the old files in whose column it has a ' '.
+    let is_removed = prefix.contains(&'-');
+    let mut ranges: Vec<bool> = prefix
+        .iter()
+        .map(|c| if is_removed { *c == '-' } else { *c == ' ' })
+        .collect();
+    ranges.push(!is_removed);
+    Some(ranges)
+}
+
+/// Format hunk line counts as in a hunk header, without the line numbers, e.g. "-3 +4".
+fn format_counts(counts: &[usize]) -> String {
+    let (new_count, old_counts) = counts.split_last().unwrap();
+    old_counts
+        .iter()
+        .map(|count| format!("-{}", count))
+        .chain(std::iter::once(format!("+{}", new_count)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_DIFF: &str = "\
+commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e
+Author: Dan Davison <dandavison7@gmail.com>
+Date:   Thu Jul 9 12:55:41 2020 -0400
+
+    Remove whitespace
+
+diff --git a/a.py b/a.py
+index 8b13789..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,3 @@
+ def f():
+-    return 1
++    return 2
+
+\\ No newline at end of file
+diff --git a/b.py b/b.py
+new file mode 100644
+index 0000000..ce01362
+--- /dev/null
++++ b/b.py
+@@ -0,0 +1 @@
++x = 1
+diff --cc c.py
+index 1111111,2222222..3333333
+--- a/c.py
++++ b/c.py
+@@@ -1,3 -1,1 +1,3 @@@
+  a
+- b
+ +c
+++d
+";
+
+    fn get_messages(input: &str) -> Vec<String> {
+        check_diff(input)
+            .iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_check_valid_diff() {
+        assert_eq!(get_messages(VALID_DIFF), Vec::<String>::new());
+        let unified_diff = "--- a.txt\n+++ b.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(get_messages(unified_diff), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_check_hunk_line_counts() {
+        let input = VALID_DIFF.replace("@@ -1,3 +1,3 @@", "@@ -1,4 +1,3 @@");
+        assert_eq!(
+            get_messages(&input),
+            vec!["line 11: hunk has fewer lines than its header states (expected -4 +3, found -3 +3)"]
+        );
+        let input = VALID_DIFF.replace("@@ -0,0 +1 @@", "@@ -0,0 +1,0 @@");
+        assert_eq!(
+            get_messages(&input),
+            vec!["line 23: hunk at line 22 has more lines than its header states (-0 +0)"]
+        );
+        let input = VALID_DIFF.replace("@@@ -1,3 -1,1 +1,3 @@@", "@@@ -1,3 -1,1 +1,2 @@@");
+        assert_eq!(
+            get_messages(&input),
+            vec!["line 32: hunk at line 28 has more lines than its header states (-3 -1 +2)"]
+        );
+        // The hunk ends at the end of the input.
+        assert_eq!(
+            get_messages("--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n"),
+            vec![
+                "line 3: hunk has fewer lines than its header states (expected -2 +2, found -1 +1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_malformed_sections() {
+        let input = VALID_DIFF.replace("@@ -1,3 +1,3 @@", "@@ -1,3 @@");
+        assert_eq!(
+            get_messages(&input)[0],
+            "line 11: malformed hunk header: @@ -1,3 @@"
+        );
+        let input = VALID_DIFF.replace("new file mode", "new fiel mode");
+        assert_eq!(
+            get_messages(&input),
+            vec!["line 18: unrecognized line in file header: new fiel mode 100644"]
+        );
+        let input = VALID_DIFF.replace("+++ b/b.py\n", "");
+        assert_eq!(
+            get_messages(&input),
+            vec![
+                "line 20: \"---\" line is not followed by a \"+++\" line",
+                "line 21: hunk header is not preceded by \"---\" and \"+++\" lines",
+            ]
+        );
+        assert_eq!(get_messages("Some text\n"), vec!["no diff found"]);
+    }
+}
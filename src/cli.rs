@@ -24,6 +24,18 @@ is
     number = true
     zero-style = dim syntax
 
+CONFIG FILE
+-----------
+
+Settings can also be kept in a separate TOML file given by --config (or DELTA_CONFIG_PATH), using
+the same option names as in git config. Presets are defined as tables. An example is
+
+number = true
+zero-style = \"dim syntax\"
+
+[my-delta-preset]
+syntax-theme = \"Dracula\"
+
 PRESETS
 -------
 A preset is a named collection of delta options in ~/.gitconfig. An example is:
@@ -49,6 +61,11 @@ A single preset name may not contain whitespace. You can activate multiple prese
 
 If more than one preset sets the same option, the last one wins.
 
+Presets can also be activated by --features (or the DELTA_FEATURES environment variable), together
+with delta's built-in features:
+
+DELTA_FEATURES='navigate my-delta-preset' git diff
+
 There are two built-in presets:
 
 --presets diff-highlight (https://github.com/git/git/tree/master/contrib/diff-highlight)
@@ -164,6 +181,11 @@ you would use
 --number-minus-format '%ln ⋮'
 --number-plus-format '%ln │'
 
+Alternatively, --line-numbers-format specifies both columns in a single string, using the
+placeholders {nm} and {np}: the example above could also be written as
+
+--line-numbers-format '{nm} ⋮{np} │'
+
 If something isn't working correctly, or you have a feature request, please open an issue at
 https://github.com/dandavison/delta/issues.
 "
@@ -176,6 +198,35 @@ pub struct Opt {
     /// 'diff-so-fancy'. See PRESETS section.
     pub presets: Option<String>,
 
+    #[structopt(long = "git-config-section")]
+    /// Read the main delta settings from the git config section [delta "NAME"] instead of
+    /// [delta], e.g. to select one of several profiles kept in one .gitconfig. Settings in [delta]
+    /// are then not used. Presets are still read from their own sections. This option cannot be
+    /// set in git config.
+    pub git_config_section: Option<String>,
+
+    #[structopt(long = "features", env = "DELTA_FEATURES")]
+    /// Names of features to activate (space-separated). A feature is either one of the built-in
+    /// features listed below, which set other delta options, or the name of a preset, which is
+    /// activated with lower priority than those given by --presets. The feature names can also be
+    /// set using an environment variable named DELTA_FEATURES. The built-in features are
+    /// 'color-only', 'diff-color-words', 'line-numbers' (--number), 'navigate', and
+    /// 'no-decorations' (no commit, file, or hunk header decoration).
+    pub features: Option<String>,
+
+    #[structopt(long = "per-file-git-attributes")]
+    /// Look up the delta-style git attribute of each file in the diff (using `git check-attr`),
+    /// and display a file whose attribute names a preset, e.g. `*.md delta-style=markdown` in
+    /// .gitattributes, with that preset activated, with higher priority than those given by
+    /// --presets.
+    pub per_file_git_attributes: bool,
+
+    #[structopt(long = "theme-set-dir")]
+    /// A directory containing additional syntax-highlighting themes, as .tmTheme files. Each theme
+    /// is named after its file (without the extension) and can be selected with --syntax-theme. A
+    /// theme with the same name as a built-in theme replaces it.
+    pub theme_set_dir: Option<String>,
+
     #[structopt(long = "syntax-theme", env = "BAT_THEME")]
     /// The code syntax-highlighting theme to use. Use --list-syntax-themes to demo available
     /// themes. If the syntax-highlighting theme is not set using this option, it will be taken
@@ -193,6 +244,12 @@ pub struct Opt {
     #[structopt(long = "dark")]
     pub dark: bool,
 
+    /// Ask the terminal for its background color and use --light or --dark accordingly. If the
+    /// terminal does not answer within 100ms, --dark is assumed. Ignored if --light or --dark is
+    /// given.
+    #[structopt(long = "auto-dark-light")]
+    pub auto_dark_light: bool,
+
     #[structopt(long = "minus-style", default_value = "normal auto")]
     /// Style (foreground, background, attributes) for removed lines. See STYLES section.
     pub minus_style: String,
@@ -201,6 +258,28 @@ pub struct Opt {
     /// Style (foreground, background, attributes) for unchanged lines. See STYLES section.
     pub zero_style: String,
 
+    #[structopt(long = "context-style")]
+    /// Style (foreground, background, attributes) for unchanged context lines within hunks.
+    /// Defaults to --zero-style. See STYLES section.
+    pub context_style: Option<String>,
+
+    #[structopt(long = "context-line-regex", number_of_values = 1)]
+    /// Style unchanged lines matching a regular expression differently from --context-style. The
+    /// value has the form "<PATTERN>:<STYLE>", e.g. --context-line-regex='assert:yellow'. This
+    /// option may be given more than once; the first pattern that matches the line is used.
+    pub context_line_regex: Vec<String>,
+
+    #[structopt(long = "empty-line-context")]
+    /// Display a run of more than this many consecutive blank unchanged lines within a hunk as a
+    /// single line, "⋮ (K blank lines)", in the --ellipsis-style. By default blank lines are
+    /// displayed like any other unchanged line.
+    pub empty_line_context: Option<usize>,
+
+    #[structopt(long = "ellipsis-style", default_value = "dim")]
+    /// Style (foreground, background, attributes) for the line standing in for a run of blank
+    /// lines under --empty-line-context. See STYLES section.
+    pub ellipsis_style: String,
+
     #[structopt(long = "plus-style", default_value = "syntax auto")]
     /// Style (foreground, background, attributes) for added lines. See STYLES section.
     pub plus_style: String,
@@ -225,18 +304,216 @@ pub struct Opt {
     /// have an emphasized section. Defaults to --plus-style. See STYLES section.
     pub plus_non_emph_style: String,
 
+    #[structopt(long = "minus-empty-line-marker", default_value = "·")]
+    /// Text displayed, in the --minus-empty-line-marker-style, in place of the content of a
+    /// removed line that is empty, so that removed blank lines are visible. Use an empty string
+    /// to display such lines as they are.
+    pub minus_empty_line_marker: String,
+
+    #[structopt(long = "plus-empty-line-marker", default_value = "·")]
+    /// Text displayed, in the --plus-empty-line-marker-style, in place of the content of an added
+    /// line that is empty, so that added blank lines are visible. Use an empty string to display
+    /// such lines as they are.
+    pub plus_empty_line_marker: String,
+
+    #[structopt(long = "minus-empty-line-marker-style", default_value = "red auto")]
+    /// Style (foreground, background, attributes) for the --minus-empty-line-marker. The
+    /// background defaults to that of --minus-style. See STYLES section.
+    pub minus_empty_line_marker_style: String,
+
+    #[structopt(long = "plus-empty-line-marker-style", default_value = "green auto")]
+    /// Style (foreground, background, attributes) for the --plus-empty-line-marker. The
+    /// background defaults to that of --plus-style. See STYLES section.
+    pub plus_empty_line_marker_style: String,
+
+    #[structopt(long = "plus-emphasis-regexp")]
+    /// Additionally emphasize, in the --plus-emph-style, every match of this regular expression in
+    /// added lines, whether or not the matched text differs from the removed lines, e.g.
+    /// --plus-emphasis-regexp='TODO|FIXME'. The emphasis computed by comparing the removed and
+    /// added lines is kept.
+    pub plus_emphasis_regexp: Option<String>,
+
+    #[structopt(long = "plus-ruler")]
+    /// Draw a horizontal rule, in the --plus-emph-style, above each group of added lines in a hunk
+    /// other than the first, to separate independent insertions.
+    pub plus_ruler: bool,
+
+    #[structopt(long = "minus-ruler")]
+    /// Draw a horizontal rule, in the --minus-emph-style, above each group of removed lines in a
+    /// hunk other than the first, to separate independent deletions.
+    pub minus_ruler: bool,
+
+    #[structopt(long = "show-whitespace-errors")]
+    /// Highlight whitespace errors in added lines, in the --whitespace-error-style: trailing
+    /// spaces and tabs, and indentation containing both spaces and tabs. As with git's
+    /// color.diff.whitespace, whitespace errors in removed and unchanged lines are not
+    /// highlighted, since they were not introduced by the change.
+    pub show_whitespace_errors: bool,
+
+    #[structopt(long = "whitespace-error-style", default_value = "normal bright-red")]
+    /// Style (foreground, background, attributes) for whitespace errors under
+    /// --show-whitespace-errors. See STYLES section.
+    pub whitespace_error_style: String,
+
+    #[structopt(long = "ignore-whitespace-change")]
+    /// Display removed and added lines that differ from each other only in whitespace, as with
+    /// `git diff -w`, in the --whitespace-change-style rather than as removed and added lines.
+    pub ignore_whitespace_change: bool,
+
+    #[structopt(long = "whitespace-change-style")]
+    /// Style (foreground, background, attributes) for lines that differ only in whitespace under
+    /// --ignore-whitespace-change. Defaults to --zero-style. See STYLES section.
+    pub whitespace_change_style: Option<String>,
+
+    #[structopt(long = "highlight-copied-lines")]
+    /// Display lines that were moved within a hunk, i.e. that occur verbatim both as removed and
+    /// as added lines of the hunk, in the --moved-style rather than as removed and added lines.
+    /// Blank lines are not considered moved. Each hunk is read in full before it is displayed.
+    pub highlight_copied_lines: bool,
+
+    #[structopt(long = "moved-style", default_value = "bold purple")]
+    /// Style (foreground, background, attributes) for moved lines under
+    /// --highlight-copied-lines. See STYLES section.
+    pub moved_style: String,
+
+    #[structopt(long = "color-moved")]
+    /// Display the lines that git marks as moved, under git's --color-moved option (or the
+    /// diff.colorMoved setting), in --color-moved-style, or --color-moved-alternate-style for the
+    /// alternate blocks of the zebra modes, rather than as removed and added lines. Moved lines are
+    /// recognized by the colors that git gives them, so git must be coloring its output (as it
+    /// does when delta is its pager). Those colors are read from git config (color.diff.oldMoved,
+    /// color.diff.newMovedAlternative, etc.), with git's defaults.
+    pub color_moved: bool,
+
+    #[structopt(long = "color-moved-style", default_value = "bold purple")]
+    /// Style (foreground, background, attributes) for moved lines under --color-moved. See STYLES
+    /// section.
+    pub color_moved_style: String,
+
+    #[structopt(long = "color-moved-alternate-style", default_value = "bold blue")]
+    /// Style (foreground, background, attributes) for moved lines in the alternate blocks of
+    /// git's zebra and dimmed-zebra --color-moved modes, under --color-moved. See STYLES section.
+    pub color_moved_alternate_style: String,
+
+    #[structopt(
+        long = "syntax-highlight-removed",
+        overrides_with = "no-syntax-highlight-removed"
+    )]
+    /// Apply syntax highlighting to removed lines. If --minus-style and --minus-emph-style are at
+    /// their default values, then this sets their foreground color to 'syntax'. Overrides
+    /// --no-syntax-highlight-removed.
+    pub syntax_highlight_removed: bool,
+
+    #[structopt(
+        long = "no-syntax-highlight-removed",
+        overrides_with = "syntax-highlight-removed"
+    )]
+    /// Do not apply syntax highlighting to removed lines, even if --minus-style or
+    /// --minus-emph-style request it. Removed lines are displayed in the --minus-style colors.
+    pub no_syntax_highlight_removed: bool,
+
+    #[structopt(
+        long = "syntax-highlight-added",
+        overrides_with = "no-syntax-highlight-added"
+    )]
+    /// Apply syntax highlighting to added lines (this is the default behavior). Overrides
+    /// --no-syntax-highlight-added.
+    pub syntax_highlight_added: bool,
+
+    #[structopt(
+        long = "no-syntax-highlight-added",
+        overrides_with = "syntax-highlight-added"
+    )]
+    /// Do not apply syntax highlighting to added lines, even if --plus-style or --plus-emph-style
+    /// request it. Added lines are displayed in the --plus-style colors.
+    pub no_syntax_highlight_added: bool,
+
     #[structopt(long = "commit-style", default_value = "raw")]
-    /// Style (foreground, background, attributes) for the commit hash line. See STYLES section.
+    /// Style (foreground, background, attributes) for the commit hash line. See STYLES section. The
+    /// value 'none' omits the commit header lines (hash, author, date and message), and their
+    /// decoration, from the output.
     pub commit_style: String,
 
+    #[structopt(long = "commit-style-by-author-regexp")]
+    /// Style the commit hash line of commits by particular authors differently from
+    /// --commit-style. The value is a comma-separated list of "<REGEX>:<STYLE>" entries, e.g.
+    /// "alice:bold yellow,bob:bold cyan". Each regex is matched against the "Author:" field of
+    /// the commit (name and email) and the style of the first matching entry is used. The
+    /// decoration is taken from --commit-decoration-style. An entry ends at the comma that
+    /// follows its style, so the regexes may contain commas and colons.
+    pub commit_style_by_author_regexp: Option<String>,
+
+    #[structopt(long = "commit-format")]
+    /// Template for displaying the commit header block, instead of the format used by git. The
+    /// template may contain the placeholders {sha}, {author}, {date}, {subject} and {body}; a
+    /// maximum length can be given as in {sha:.7}. Use \n for a line break, e.g. --commit-format
+    /// "{sha:.7} {author} {date}\n  {subject}". The first line is displayed with --commit-style and
+    /// --commit-decoration-style.
+    pub commit_format: Option<String>,
+
     #[structopt(long = "commit-decoration-style", default_value = "")]
     /// Style (foreground, background, attributes) for the commit hash decoration. See STYLES
     /// section. One of the special attributes 'box', 'ul', 'overline', or 'underoverline' must be
     /// given.
     pub commit_decoration_style: String,
 
+    #[structopt(long = "commit-limit")]
+    /// Stop after displaying this many commits, e.g. of `git log -p` output, ignoring the rest of
+    /// the input. This option can only be given on the command line.
+    pub commit_limit: Option<usize>,
+
+    #[structopt(long = "ignore-rev", number_of_values = 1)]
+    /// Omit from the output, e.g. of `git log -p`, the commit whose SHA starts with this value,
+    /// together with its diff. This is useful for hiding commits that only reformat code. This
+    /// option may be given more than once.
+    pub ignore_rev: Vec<String>,
+
+    #[structopt(long = "stats")]
+    /// Display a summary of the diff at the end of the output, as `git diff --stat` does: the
+    /// number of files changed and the numbers of lines added and removed. The summary is
+    /// displayed in the --commit-style.
+    pub show_stats: bool,
+
+    #[structopt(long = "merged-diff")]
+    /// Read a file containing merge conflict markers, instead of a diff, and display each
+    /// conflict as a diff: the lines between "<<<<<<<" and "=======" (our side) as removed lines
+    /// and the lines between "=======" and ">>>>>>>" (their side) as added lines, e.g. `delta
+    /// --merged-diff conflicted_file.rs`. The file can also be given on standard input, in which
+    /// case the language for syntax highlighting is inferred from its first line. The conflict
+    /// markers are displayed in the --hunk-header-style. This option can only be given on the
+    /// command line.
+    pub merged_diff: bool,
+
+    #[structopt(long = "conflict-marker-size", default_value = "7")]
+    /// The length of the merge conflict markers recognized by --merged-diff, for files written with
+    /// a non-standard marker length (see the conflict-marker-size attribute in `git help
+    /// gitattributes`).
+    pub conflict_marker_size: usize,
+
+    #[structopt(long = "rebase-mode")]
+    /// Read an interactive rebase TODO list, as opened in an editor by `git rebase -i`, instead of
+    /// a diff, and display it with each action word (pick, reword, edit, squash, fixup, drop, or
+    /// their one-letter abbreviations) in its --rebase-action-style, e.g. `delta --rebase-mode
+    /// .git/rebase-merge/git-rebase-todo`. The file can also be given on standard input. Comment
+    /// lines are displayed in the --zero-style. This option can only be given on the command line.
+    pub rebase_mode: bool,
+
+    #[structopt(long = "rebase-action-style", number_of_values = 1)]
+    /// Style an action word of a rebase TODO list under --rebase-mode. The value has the form
+    /// "<ACTION>:<STYLE>", e.g. --rebase-action-style='fixup:bold purple'. This option may be
+    /// given more than once. The default styles are green for pick, blue for reword, yellow for
+    /// edit, cyan for squash, purple for fixup and red for drop. See STYLES section.
+    pub rebase_action_style: Vec<String>,
+
+    #[structopt(long = "stash-style")]
+    /// Style (foreground, background, attributes) for the stash entry header line emitted by e.g.
+    /// `git stash list -p`. The decoration is taken from --commit-decoration-style. Defaults to
+    /// --commit-style. See STYLES section.
+    pub stash_style: Option<String>,
+
     #[structopt(long = "file-style", default_value = "blue")]
-    /// Style (foreground, background, attributes) for the file section. See STYLES section.
+    /// Style (foreground, background, attributes) for the file section. See STYLES section. The
+    /// value 'none' omits the file header lines, and their decoration, from the output.
     pub file_style: String,
 
     #[structopt(long = "file-decoration-style", default_value = "blue ul")]
@@ -250,32 +527,166 @@ pub struct Opt {
     /// --file-renamed-label.
     pub navigate: bool,
 
+    #[structopt(long = "navigate-regex")]
+    /// The regular expression used by --navigate to find the lines to jump between. By default
+    /// these are commit lines and file header lines (identified by the --file-*-label options).
+    /// For example, use --navigate-regex='^@@' to jump between hunks instead. The regular
+    /// expression is passed to the pager (less) as its initial search pattern.
+    pub navigate_regex: Option<String>,
+
+    #[structopt(long = "navigate-include-decorations")]
+    /// Under --navigate, also stop at the decoration line drawn above each commit line, when
+    /// --commit-decoration-style includes 'box' or 'overline', so that the whole commit header
+    /// is visible after jumping. Has no effect if --navigate-regex is given.
+    pub navigate_include_decorations: bool,
+
+    #[structopt(long = "diff-header-only")]
+    /// Display only the commit and file header lines: all hunks (hunk headers and the changed
+    /// and unchanged lines) are omitted. This can be used to produce a summary of the files
+    /// changed by each commit.
+    pub diff_header_only: bool,
+
+    #[structopt(long = "diff-filter")]
+    /// A comma-separated list of glob patterns selecting the files to display, e.g.
+    /// --diff-filter='!*.lock,!*.min.js'. Files whose path matches a pattern prefixed with '!'
+    /// are omitted from the output entirely, including their file header. If any patterns without
+    /// a '!' are given, only files matching one of them are displayed. In a pattern, '*' matches
+    /// any sequence of characters (including '/'), '?' matches any single character, and [...]
+    /// matches a character class.
+    pub diff_filter: Option<String>,
+
+    /// Mark the lines of added and removed files, i.e. files for which one side of the diff is
+    /// /dev/null, with the character given by --null-file-indicator instead of the usual -/+
+    /// marker column. The indicator is displayed in the null style.
+    #[structopt(long = "emit-null-for-missing-files")]
+    pub emit_null_for_missing_files: bool,
+
+    /// The character used to mark the lines of added and removed files under
+    /// --emit-null-for-missing-files.
+    #[structopt(long = "null-file-indicator", default_value = "∅")]
+    pub null_file_indicator: String,
+
+    /// Display each file that was renamed without changes to its content as a single line,
+    /// e.g. "renamed: old.rs → new.rs", instead of as a full file header.
+    #[structopt(long = "collapse-renames")]
+    pub collapse_renames: bool,
+
+    /// Display the similarity percentage reported by git for a renamed file, e.g. "renamed:
+    /// (93%) old.rs ⟶   new.rs".
+    #[structopt(long = "show-rename-score")]
+    pub show_rename_score: bool,
+
+    /// Style the submodule change lines written by `git diff --submodule=log`, e.g. "Submodule
+    /// vendor/lib 1a2b3c4..5d6e7f8:", as file headers with the commit range in --commit-style.
+    /// The commit summary lines that follow are displayed in --hunk-header-style.
+    #[structopt(long = "submodule-diff-output")]
+    pub submodule_diff_output: bool,
+
+    /// How renamed files are shown in diff stat output (e.g. git show --stat). Options are "git",
+    /// which leaves git's own notation unchanged, "short", which shows only the new name, "full",
+    /// which shows "old_name → new_name", and "arrow", which uses the compact notation
+    /// "{old,new}/path".
+    #[structopt(long = "diff-stat-file-rename-format", default_value = "git")]
+    pub diff_stat_file_rename_format: String,
+
+    /// Display binary files in the diff that are no larger than this many bytes as a hex dump of
+    /// their old and new contents, with the changed bytes highlighted. This requires the binary
+    /// content to be present in the diff, i.e. `git diff --binary`. Larger binary files are
+    /// displayed as "Binary files differ".
+    #[structopt(long = "binary-hex-dump-max-bytes")]
+    pub binary_hex_dump_max_bytes: Option<u64>,
+
+    #[structopt(long = "diff-split-output")]
+    /// In addition to the normal output, write the removed and unchanged lines of each hunk to
+    /// the file PREFIX.old, and the added and unchanged lines to the file PREFIX.new. This makes
+    /// it possible to compare the old and new versions of the changed regions independently.
+    pub diff_split_output: Option<String>,
+
     #[structopt(long = "file-modified-label", default_value = "")]
-    /// Text to display in front of a modified file path.
+    /// Text to display in front of a modified file path. A literal '\n' in the label starts a new
+    /// line; the file decoration is applied to the last line.
     pub file_modified_label: String,
 
+    #[structopt(long = "file-path-truncation-prefix")]
+    /// Shorten file paths that would make a file header wider than the --width, by removing
+    /// characters from the start of the path and displaying this text in their place, e.g.
+    /// --file-path-truncation-prefix='…'. Paths are not shortened if --width is "variable".
+    pub file_path_truncation_prefix: Option<String>,
+
     #[structopt(long = "file-removed-label", default_value = "removed:")]
-    /// Text to display in front of a removed file path.
+    /// Text to display in front of a removed file path. A literal '\n' in the label starts a new
+    /// line; the file decoration is applied to the last line.
     pub file_removed_label: String,
 
     #[structopt(long = "file-added-label", default_value = "added:")]
-    /// Text to display in front of a added file path.
+    /// Text to display in front of a added file path. A literal '\n' in the label starts a new
+    /// line; the file decoration is applied to the last line.
     pub file_added_label: String,
 
     #[structopt(long = "file-renamed-label", default_value = "renamed:")]
-    /// Text to display in front of a renamed file path.
+    /// Text to display in front of a renamed file path. A literal '\n' in the label starts a new
+    /// line; the file decoration is applied to the last line.
     pub file_renamed_label: String,
 
+    #[structopt(long = "file-copied-label", default_value = "copied:")]
+    /// Text to display in front of the path of a file copied from another (see `git diff -C`). A
+    /// literal '\n' in the label starts a new line; the file decoration is applied to the last
+    /// line.
+    pub file_copied_label: String,
+
+    #[structopt(long = "unicode-symbols")]
+    /// Use symbols rather than words for the file labels: '✚' for an added file, '✕' for a
+    /// removed file, '→' for a renamed file, and '⊕' for a copied file. Any of --file-added-label,
+    /// --file-removed-label, --file-renamed-label, and --file-copied-label that are supplied
+    /// explicitly take precedence.
+    pub unicode_symbols: bool,
+
+    #[structopt(long = "file-renamed-old-style")]
+    /// Style (foreground, background, attributes) for the old path of a renamed file in the file
+    /// header, e.g. "red strike". Defaults to --file-style. See STYLES section.
+    pub file_renamed_old_style: Option<String>,
+
+    #[structopt(long = "file-renamed-new-style")]
+    /// Style (foreground, background, attributes) for the new path of a renamed file in the file
+    /// header. Defaults to --file-style. See STYLES section.
+    pub file_renamed_new_style: Option<String>,
+
     #[structopt(long = "hunk-header-style", default_value = "syntax")]
-    /// Style (foreground, background, attributes) for the hunk-header. See STYLES section.
+    /// Style (foreground, background, attributes) for the hunk-header. See STYLES section. The
+    /// decoration attributes 'box', 'underline' and 'overline' select the kind of hunk-header
+    /// decoration, drawn in the color of --hunk-header-decoration-style, unless that option is
+    /// given on the command line, in which case they are ignored.
     pub hunk_header_style: String,
 
     #[structopt(long = "hunk-header-decoration-style", default_value = "blue box")]
     /// Style (foreground, background, attributes) for the hunk-header decoration. See STYLES
     /// section. One of the special attributes 'box', 'ul', 'overline', or 'underoverline' must be
-    /// given.
+    /// given. When given on the command line, this takes full precedence over decoration
+    /// attributes in --hunk-header-style, so that e.g. --file-style can use 'box' while the hunk
+    /// header is only underlined.
     pub hunk_header_decoration_style: String,
 
+    #[structopt(long = "box-style", default_value = "unicode")]
+    /// The characters with which box and line decorations are drawn: "ascii" (+---+), "unicode"
+    /// (┌───┐, or ┏━━━┓ if the decoration style is bold), "heavy" (┏━━━┓) or "double" (╔═══╗).
+    pub box_style: String,
+
+    #[structopt(long = "hunk-header-include-file-path")]
+    /// Display the path of the file, in the --file-style, at the start of each hunk header. This
+    /// makes it easier to see which file a hunk belongs to when scrolling through a long diff.
+    pub hunk_header_include_file_path: bool,
+
+    #[structopt(long = "hunk-header-function-hint-max-length", value_name = "N")]
+    /// Shorten the function hint of each hunk header (the code following the closing "@@", as
+    /// given by git) to at most N columns, followed by "…". By default the hint is not shortened.
+    pub hunk_header_function_hint_max_length: Option<usize>,
+
+    #[structopt(long = "hunk-header-always")]
+    /// Display a hunk header even when the file header is followed directly by hunk lines, as in
+    /// some hand-made or tool-generated diffs. The header is reconstructed from the hunk lines,
+    /// assuming that the hunk starts at the first line of the file, e.g. "@@ -1,3 +1,4 @@".
+    pub hunk_header_always: bool,
+
     /// Display line numbers next to the diff. The first column contains line
     /// numbers in the previous version of the file, and the second column contains
     /// line number in the new version of the file. A blank cell in the first or
@@ -284,6 +695,29 @@ pub struct Opt {
     #[structopt(short = "n", long = "number")]
     pub show_line_numbers: bool,
 
+    #[structopt(
+        long = "show-line-numbers-for-context",
+        overrides_with = "no-show-line-numbers-for-context"
+    )]
+    /// Display line numbers (--number) next to unchanged context lines (this is the default
+    /// behavior). Overrides --no-show-line-numbers-for-context.
+    pub show_line_numbers_for_context: bool,
+
+    #[structopt(
+        long = "no-show-line-numbers-for-context",
+        overrides_with = "show-line-numbers-for-context"
+    )]
+    /// Display line numbers (--number) only next to removed and added lines: the line number
+    /// columns of unchanged context lines are left blank.
+    pub no_show_line_numbers_for_context: bool,
+
+    /// Instead of displaying line numbers next to every line, display the line number ranges of
+    /// each hunk below its hunk header. The ranges are formatted using --number-minus-format and
+    /// --number-plus-format, in which %ln is replaced by the range as "start,count". The
+    /// placeholders {start} and {count} can also be used individually.
+    #[structopt(long = "line-numbers-for-hunks-only")]
+    pub line_numbers_for_hunks_only: bool,
+
     /// Style (foreground, background, attributes) for the left (minus) column of line numbers
     /// (--number), if --number is set. See STYLES section. Defaults to
     /// --hunk-header-decoration-style.
@@ -296,6 +730,19 @@ pub struct Opt {
     #[structopt(long = "number-plus-style", default_value = "auto")]
     pub number_plus_style: String,
 
+    /// Whether the line number columns (--number) are displayed to the "left" of the line
+    /// content, or to the "right", in which case they are aligned at the right edge of the
+    /// decoration width (see --width).
+    #[structopt(long = "number-column-placement", default_value = "left")]
+    pub number_column_placement: String,
+
+    /// Right-align the line numbers (--number) in a field as wide as the largest line number in
+    /// the hunk, instead of centering them in a field of width 4. This applies to %ln in
+    /// --number-minus-format and --number-plus-format, and to the {nm} and {np} placeholders of
+    /// --line-numbers-format that do not specify an alignment and width.
+    #[structopt(long = "number-right-align")]
+    pub number_right_align: bool,
+
     /// Format string for the left (minus) column of line numbers (--number), if --number is set.
     /// Should include the placeholder %ln to indicate the position of the line number.
     /// See the LINE NUMBERS section.
@@ -308,6 +755,19 @@ pub struct Opt {
     #[structopt(long = "number-plus-format", default_value = "%ln│ ")]
     pub number_plus_format: String,
 
+    /// String to display between the left (minus) and right (plus) columns of line numbers
+    /// (--number), e.g. "│". This allows the separator to be styled independently using
+    /// --number-separator-style. It is not used with --line-numbers-format.
+    #[structopt(long = "number-separator", default_value = "")]
+    pub number_separator: String,
+
+    /// Format string for both columns of line numbers (--number), if --number is set. Overrides
+    /// --number-minus-format and --number-plus-format. The placeholders {nm} and {np} indicate
+    /// the positions of the minus and plus line numbers, and may specify an alignment and width,
+    /// e.g. --line-numbers-format '{nm:>3}│{np:>3}│'. See the LINE NUMBERS section.
+    #[structopt(long = "line-numbers-format")]
+    pub line_numbers_format: Option<String>,
+
     /// Style (foreground, background, attributes) for the left (minus) line number format string
     /// (--number), if --number is set. See STYLES section. Defaults to
     /// --hunk-header-decoration-style.
@@ -320,16 +780,68 @@ pub struct Opt {
     #[structopt(long = "number-plus-format-style", default_value = "auto")]
     pub number_plus_format_style: String,
 
+    /// Style (foreground, background, attributes) for --number-separator, if --number is set.
+    /// See STYLES section. Defaults to --hunk-header-decoration-style.
+    #[structopt(long = "number-separator-style", default_value = "auto")]
+    pub number_separator_style: String,
+
+    /// Give the line number columns (--number) a background color of their own, set by
+    /// --number-gutter-background-color, so that they stand out from the diff content.
+    #[structopt(long = "highlight-line-numbers")]
+    pub highlight_line_numbers: bool,
+
+    /// The background color of the line number columns under --highlight-line-numbers. It
+    /// applies to the parts of the columns whose style has no background color of its own. The
+    /// default, "auto", is a gray suited to the light or dark background of the syntax theme. See
+    /// COLORS section.
+    #[structopt(long = "number-gutter-background-color", default_value = "auto")]
+    pub number_gutter_background_color: String,
+
     #[structopt(long = "color-only")]
     /// Do not alter the input in any way other than applying colors. Equivalent to
     /// `--keep-plus-minus-markers --width variable --tabs 0 --commit-decoration ''
     /// --file-decoration '' --hunk-decoration ''`.
     pub color_only: bool,
 
+    #[structopt(long = "diff-color-words")]
+    /// Display diffs in a style similar to `git diff --color-words`: tokenize lines on whitespace,
+    /// color removed and added words in bold red and bold green, and do not use background
+    /// colors. Equivalent to `--word-diff-regex '[^[:space:]]+' --minus-style red
+    /// --minus-emph-style 'red bold' --plus-style green --plus-emph-style 'green bold'`. Any of
+    /// those options that are supplied explicitly take precedence.
+    pub diff_color_words: bool,
+
+    #[structopt(long = "diff-highlight-compat")]
+    /// Use the color scheme of git's contrib/diff-highlight script: removed lines in red on a
+    /// dark red background and added lines in green on a dark green background, with the changed
+    /// sections in bold. Equivalent to `--minus-style 'red 52' --minus-emph-style 'red bold 52'
+    /// --plus-style 'green 22' --plus-emph-style 'green bold 22'`. Any of those options that are
+    /// supplied explicitly take precedence.
+    pub diff_highlight_compat: bool,
+
+    #[structopt(long = "diff-so-fancy-compat")]
+    /// Use a style that closely matches diff-so-fancy: bold cyan file headers between full-width
+    /// rules, bold cyan hunk headers, and diff-so-fancy's red and green palette for removed and
+    /// added lines. Equivalent to `--file-style 'bold cyan' --file-decoration-style 'bold cyan ul
+    /// ol' --hunk-header-style 'bold cyan' --hunk-header-decoration-style none --minus-style
+    /// 'bold red' --minus-emph-style 'bold red 52' --zero-style normal --plus-style 'bold green'
+    /// --plus-emph-style 'bold green 22'`. Any of those options that are supplied explicitly take
+    /// precedence.
+    pub diff_so_fancy_compat: bool,
+
     #[structopt(long = "no-gitconfig")]
     /// Do not take settings from git config files. See GIT CONFIG section.
     pub no_gitconfig: bool,
 
+    #[structopt(long = "config", env = "DELTA_CONFIG_PATH", parse(from_os_str))]
+    /// Take settings from this delta config file, in addition to git config. The path can also
+    /// be set using an environment variable named DELTA_CONFIG_PATH. Settings in the file take
+    /// precedence over git config, and command line options take precedence over both. See
+    /// CONFIG FILE section. When delta is reading a diff from standard input, sending it SIGHUP
+    /// makes it reread the config file and git config; the new settings apply from the next
+    /// commit or file in the input.
+    pub config_file: Option<PathBuf>,
+
     #[structopt(long = "keep-plus-minus-markers")]
     /// Prefix added/removed lines with a +/- character, respectively, exactly as git does. The
     /// default behavior is to output a space character in place of these markers.
@@ -341,6 +853,39 @@ pub struct Opt {
     #[structopt(short = "w", long = "width")]
     pub width: Option<String>,
 
+    /// The number of terminal columns that are not available to delta, for example because they
+    /// are occupied by a terminal multiplexer's status or border area. This is subtracted from the
+    /// terminal width when computing the width of decorations and background colors. It is an
+    /// error for it to leave no columns available when --width is not given.
+    #[structopt(long = "width-minus-left-margin", default_value = "0")]
+    pub width_minus_left_margin: usize,
+
+    /// The number of columns subtracted, in addition to --width-minus-left-margin, from the width
+    /// detected from the terminal, for terminals whose usable width is narrower than reported (e.g.
+    /// because of a scrollbar). It does not apply when --width is given. It is an error for it to
+    /// leave no columns available.
+    #[structopt(long = "width-minus-padding", default_value = "0")]
+    pub width_minus_padding: usize,
+
+    /// Decoration style (e.g. 'overline' or 'ul') used instead of the commit, file, and hunk header
+    /// decoration styles when fewer than --decoration-fallback-width terminal columns are
+    /// available, for example because box decorations do not fit well in a narrow terminal. Only
+    /// elements that are decorated are affected. See STYLES section.
+    #[structopt(long = "decoration-style-fallback")]
+    pub decoration_style_fallback: Option<String>,
+
+    /// The number of available terminal columns below which --decoration-style-fallback is used.
+    #[structopt(long = "decoration-fallback-width", default_value = "60")]
+    pub decoration_fallback_width: usize,
+
+    /// How background colors are extended to the full width (see --width). Options are "ansi",
+    /// which uses an ANSI escape sequence to fill the rest of the terminal line, "spaces", which
+    /// pads lines with spaces, and "none". "spaces" can be used with terminals or terminal
+    /// multiplexers that do not handle the ANSI sequence correctly. --width=variable implies
+    /// "none".
+    #[structopt(long = "line-fill-method", default_value = "ansi")]
+    pub line_fill_method: String,
+
     /// The number of spaces to replace tab characters with. Use --tabs=0 to pass tab characters
     /// through directly, but note that in that case delta will calculate line widths assuming tabs
     /// occupy one character's width on the screen: if your terminal renders tabs as more than than
@@ -348,6 +893,26 @@ pub struct Opt {
     #[structopt(long = "tabs", default_value = "4")]
     pub tab_width: usize,
 
+    /// Replace each tab character with enough spaces to reach the next tab stop, i.e. the next
+    /// column that is a multiple of --tabs, instead of with a fixed number of spaces. This keeps
+    /// text that is aligned using tabs aligned in delta's output.
+    #[structopt(long = "tabs-to-spaces")]
+    pub tabs_to_spaces: bool,
+
+    /// Remove the carriage return (\r) at the end of each line of the input, as in diffs of files
+    /// with Windows-style line endings. A carriage return immediately followed by the newline is
+    /// always removed; this option also removes one that is followed only by ANSI escape
+    /// sequences, as in the added lines of `git diff --color` output, where it would otherwise be
+    /// displayed as ^M.
+    #[structopt(long = "strip-trailing-cr")]
+    pub strip_trailing_cr: bool,
+
+    /// Remove up to this many leading spaces from the content of each line, to reduce the
+    /// indentation of deeply nested code when viewing diffs in a narrow terminal. Line numbers are
+    /// not affected.
+    #[structopt(long = "decrement-leading-spaces", default_value = "0")]
+    pub decrement_leading_spaces: usize,
+
     /// Show the command-line arguments (RGB hex codes) for the background colors that are in
     /// effect. The hex codes are displayed with their associated background color. This option can
     /// be combined with --light and --dark to view the background colors for those modes. It can
@@ -356,6 +921,13 @@ pub struct Opt {
     #[structopt(long = "show-background-colors")]
     pub show_background_colors: bool,
 
+    /// Show the styles that are in effect: each style option is displayed in its own style,
+    /// followed by swatches of its foreground and background colors. Like
+    /// --show-background-colors, this can be combined with --light, --dark, --24-bit-color and
+    /// the style options.
+    #[structopt(long = "show-colors")]
+    pub show_colors: bool,
+
     /// List supported languages and associated file extensions.
     #[structopt(long = "list-languages")]
     pub list_languages: bool,
@@ -370,18 +942,105 @@ pub struct Opt {
     #[structopt(long = "list-syntax-themes")]
     pub list_syntax_themes: bool,
 
+    /// Use the diff in this file, instead of the built-in example or standard input, for the demo
+    /// of each theme given by --list-syntax-themes, e.g. to see the themes applied to a language
+    /// that you use. The file must contain a well-formed diff (see --check).
+    #[structopt(
+        long = "theme-preview-diff",
+        value_name = "diff-file",
+        parse(from_os_str)
+    )]
+    pub theme_preview_diff: Option<PathBuf>,
+
     /// The regular expression used to decide what a word is for the within-line highlight
     /// algorithm. For less fine-grained matching than the default try --word-diff-regex="\S+"
     /// --max-line-distance=1.0 (this is more similar to `git --word-diff`).
     #[structopt(long = "word-diff-regex", default_value = r"\w+")]
     pub tokenization_regex: String,
 
+    /// Per-language values of --word-diff-regex, as a comma-separated list of
+    /// "<LANGUAGE>:<REGEX>" entries, e.g. "Rust:\w+|::,LaTeX:\\[a-zA-Z]+|\w+". The language
+    /// names are those shown by --list-languages. Files in other languages use --word-diff-regex.
+    #[structopt(long = "tokenization-regex-by-language")]
+    pub tokenization_regex_by_language: Option<String>,
+
     /// The maximum distance between two lines for them to be inferred to be homologous. Homologous
     /// line pairs are highlighted according to the deletion and insertion operations transforming
     /// one into the other.
     #[structopt(long = "max-line-distance", default_value = "0.6")]
     pub max_line_distance: f64,
 
+    /// Activate an experimental option, given as <NAME> or <NAME>=<VALUE>. Experimental options
+    /// are unstable and may change or be removed. This option may be given more than once. Use
+    /// --feature-flags experimental-features-list to see the experimental options.
+    #[structopt(
+        long = "enable-experimental",
+        number_of_values = 1,
+        value_name = "NAME"
+    )]
+    #[cfg_attr(not(feature = "experimental"), structopt(hidden = true))]
+    pub enable_experimental: Vec<String>,
+
+    /// Display information about delta's feature flags, and exit. The only value is
+    /// experimental-features-list, which lists the options that may be given to
+    /// --enable-experimental, with the environment variables that also activate them.
+    #[structopt(long = "feature-flags", value_name = "COMMAND")]
+    #[cfg_attr(not(feature = "experimental"), structopt(hidden = true))]
+    pub feature_flags: Option<String>,
+
+    /// The algorithm used to align the words of a removed line with those of the added line that
+    /// replaces it, for within-line highlighting. Options are: myers (the default), which finds an
+    /// alignment with the fewest changed words; patience, which first matches the words occurring
+    /// once in each line, often better for code with many distinct identifiers; and histogram,
+    /// which first matches the longest run of common words starting with the least frequent one,
+    /// often better for refactored code.
+    #[structopt(long = "intra-line-diff-algorithm", default_value = "myers")]
+    pub intra_line_diff_algorithm: String,
+
+    /// Once the hunk lines of a file in the diff exceed this many bytes, display the rest of that
+    /// file in the plain --minus-style, --zero-style and --plus-style colors, without syntax
+    /// highlighting or within-line edit highlighting. This keeps delta fast on very large
+    /// generated files such as lockfiles.
+    #[structopt(long = "max-file-size")]
+    pub max_file_size: Option<u64>,
+
+    /// The size, in KiB, of the buffer with which the diff is read from standard input, a file or
+    /// a diff command. A larger buffer can make reading very large diffs faster.
+    #[structopt(long = "io-buffer-size", default_value = "8")]
+    pub io_buffer_size: usize,
+
+    /// Debugging aid for --word-diff-regex: instead of styling the within-line edits, display
+    /// each token produced by the tokenization regex surrounded by |...|, together with the regex
+    /// itself. This option cannot be set in git config.
+    #[structopt(long = "show-token-types")]
+    pub show_token_types: bool,
+
+    /// Debugging aid for syntax definitions: mark each syntax-highlighted line that the syntax
+    /// definition failed to tokenize, i.e. in which it matched no scope other than that of the
+    /// whole file, with a bright red [!] at the start of the line. This option cannot be set in
+    /// git config.
+    #[structopt(long = "show-syntax-errors")]
+    pub show_syntax_errors: bool,
+
+    /// Replace text in diff lines that looks like a secret (API keys and access tokens, JWTs,
+    /// private keys, and values assigned to variables named like passwords) with "[REDACTED]". This
+    /// is intended for sharing delta output in screenshots and public issues; it is not a
+    /// guarantee that no secrets remain.
+    #[structopt(long = "redact-secrets")]
+    pub redact_secrets: bool,
+
+    /// A regular expression matching additional text to redact under --redact-secrets. This
+    /// option may be given more than once.
+    #[structopt(long = "redact-regex", number_of_values = 1)]
+    pub redact_regex: Vec<String>,
+
+    /// Debugging aid for git config: instead of displaying a diff, list the settings whose
+    /// values differ between the configuration read from git config and the configuration
+    /// obtained without git config, i.e. from the command line, environment and defaults alone.
+    /// This option cannot be set in git config.
+    #[structopt(long = "debug-config-diff")]
+    pub debug_config_diff: bool,
+
     /// Whether to emit 24-bit ("true color") RGB color codes. Options are auto, always, and never.
     /// "auto" means that delta will emit 24-bit color codes iff the environment variable COLORTERM
     /// has the value "truecolor" or "24bit". If your terminal application (the application you use
@@ -390,12 +1049,108 @@ pub struct Opt {
     #[structopt(long = "24-bit-color", default_value = "auto")]
     pub true_color: String,
 
+    /// Path to a JSON file mapping RGB hex codes to 256-color palette numbers, e.g. {"#3f0001":
+    /// 52}. When 24-bit color is not in use, RGB colors in styles and syntax themes are converted
+    /// using this mapping, falling back to the closest color in the standard 256-color palette for
+    /// colors that the mapping does not contain.
+    #[structopt(long = "24-bit-color-fallback-map")]
+    pub true_color_fallback_map: Option<String>,
+
     /// Whether to use a pager when displaying output. Options are: auto, always, and never. The
     /// default pager is `less`: this can be altered by setting the environment variables BAT_PAGER
     /// or PAGER (BAT_PAGER has priority).
     #[structopt(long = "paging", default_value = "auto")]
     pub paging_mode: String,
 
+    #[structopt(long = "test-no-pager")]
+    /// Never use a pager, regardless of --paging and git config, and treat --24-bit-color=auto as
+    /// "never" instead of inspecting the terminal. This makes the output independent of the
+    /// environment, as is needed in tests. It can also be enabled by setting the environment
+    /// variable DELTA_NO_PAGER=1.
+    pub test_no_pager: bool,
+
+    /// Additional arguments to pass to the pager, e.g. --pager-args="--chop-long-lines". These are
+    /// appended to the arguments that delta passes to the pager by default, rather than replacing
+    /// them.
+    #[structopt(long = "pager-args", allow_hyphen_values = true)]
+    pub pager_args: Option<String>,
+
+    /// The value of the LESS environment variable with which less is run as the pager, e.g.
+    /// --pager-less-options=FRX. This replaces the options that delta passes to less by default
+    /// (--RAW-CONTROL-CHARS, and --quit-if-one-screen under --paging=auto). The value should
+    /// include R, without which less does not display colors.
+    #[structopt(long = "pager-less-options", allow_hyphen_values = true)]
+    pub pager_less_options: Option<String>,
+
+    /// Pass delta's output through this command, e.g. --post-process-cmd="my-filter --strict",
+    /// and write the command's output to the terminal or pager instead. The command reads the
+    /// rendered output, including ANSI escape sequences, on its standard input. If it exits
+    /// before reading all of the output, the remainder is discarded.
+    #[structopt(long = "post-process-cmd", allow_hyphen_values = true)]
+    pub post_process_cmd: Option<String>,
+
+    /// Write the output directly to the terminal, rather than to the pager, when it has fewer than
+    /// this many lines. Unlike `less --quit-if-one-screen`, this does not depend on the terminal
+    /// height. The output is rendered in full before it is written.
+    #[structopt(long = "pager-quit-if-smaller-than")]
+    pub pager_quit_if_smaller_than: Option<usize>,
+
+    /// Run `git diff` with these arguments and display its output, instead of reading a diff from
+    /// standard input, e.g. --diff-args="--word-diff=plain HEAD~1". Use this when invoking delta
+    /// directly or from a git alias: when delta is used as git's pager, the diff on standard input
+    /// has already been produced by git. This option can only be given on the command line.
+    #[structopt(long = "diff-args", allow_hyphen_values = true)]
+    pub diff_args: Option<String>,
+
+    #[structopt(long = "indent-heuristic", overrides_with = "no-indent-heuristic")]
+    /// Pass --indent-heuristic to `git diff` under --diff-args, so that git aligns changed blocks
+    /// at indentation boundaries (this is the default behavior). Overrides
+    /// --no-indent-heuristic. The option has no effect on a diff read from standard input.
+    pub indent_heuristic: bool,
+
+    #[structopt(long = "no-indent-heuristic", overrides_with = "indent-heuristic")]
+    /// Pass --no-indent-heuristic to `git diff` under --diff-args.
+    pub no_indent_heuristic: bool,
+
+    /// Display the differences between two files, e.g. `delta --from-two-files old.sh new.sh`.
+    /// This is equivalent to giving the two files as positional arguments, except that delta does
+    /// so even when standard input is not a terminal. This option can only be given on the
+    /// command line.
+    #[structopt(
+        long = "from-two-files",
+        number_of_values = 2,
+        value_names = &["old", "new"],
+        conflicts_with_all = &["minus-file", "plus-file"],
+        parse(from_os_str)
+    )]
+    pub from_two_files: Option<Vec<PathBuf>>,
+
+    /// Check that the diff in this file is well formed, without displaying it: report malformed
+    /// hunk headers, hunks whose number of lines does not match their header, and unrecognized
+    /// lines in file headers, with their line numbers. Delta exits with status 1 if any problem
+    /// is found, e.g. for use in CI scripts before applying a patch. This option can only be
+    /// given on the command line.
+    #[structopt(long = "check", value_name = "diff-file", parse(from_os_str))]
+    pub check: Option<PathBuf>,
+
+    /// Use this language for syntax highlighting, instead of inferring it from the file names,
+    /// e.g. --language=sh for shell snippets stored in .txt files. The value is a language name
+    /// or file extension, as shown by --list-languages. --language-override is an alias.
+    #[structopt(long = "language", alias = "language-override")]
+    pub language: Option<String>,
+
+    /// The format of the output: "terminal" (text with ANSI escape sequences for colors and
+    /// styles) or "html" (a self-contained HTML document with the styles as CSS classes, e.g.
+    /// `git diff | delta --output-format=html > diff.html`).
+    #[structopt(long = "output-format", default_value = "terminal")]
+    pub output_format: String,
+
+    /// The format of the diff on standard input: "unified" (as written by `git diff` and `diff
+    /// -u`), "context" (as written by `diff -c`) or "normal" (as written by `diff` with no output
+    /// format option). Context and normal diffs are displayed as the equivalent unified diff.
+    #[structopt(long = "diff-mode", default_value = "unified")]
+    pub diff_mode: String,
+
     /// First file to be compared when delta is being used in diff mode.
     #[structopt(parse(from_os_str))]
     pub minus_file: Option<PathBuf>,
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+use std::fs;
 use std::process;
 use std::str::FromStr;
 
 use ansi_term::Color;
 use syntect::highlighting::Color as SyntectColor;
+use syntect::highlighting::Theme as SyntaxTheme;
 
 use crate::bat::terminal::to_ansi_color;
 use crate::syntect_color;
@@ -39,6 +42,82 @@ pub fn color_from_rgb_or_ansi_code_with_default(
     }
 }
 
+/// Return the 256-color equivalent of an RGB color, using the user's mapping if it contains the
+/// color, and otherwise the closest color in the standard 256-color palette.
+pub fn remap_color(rgb: (u8, u8, u8), map: &HashMap<(u8, u8, u8), u8>) -> Color {
+    match map.get(&rgb) {
+        Some(n) => Color::Fixed(*n),
+        None => Color::Fixed(ansi_colours::ansi256_from_rgb(rgb)),
+    }
+}
+
+/// Read a --24-bit-color-fallback-map file. This is a JSON object mapping RGB hex codes to
+/// 256-color palette numbers, e.g. {"#3f0001": 52, "#002800": 22}.
+pub fn read_true_color_fallback_map(path: &str) -> Result<HashMap<(u8, u8, u8), u8>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let json: serde_json::Value = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+    let entries = json
+        .as_object()
+        .ok_or_else(|| "expected a JSON object".to_string())?;
+    let mut map = HashMap::new();
+    for (key, value) in entries {
+        let color =
+            SyntectColor::from_str(key).map_err(|_| format!("invalid RGB hex code: {}", key))?;
+        let number = value
+            .as_u64()
+            .filter(|n| *n <= 255)
+            .ok_or_else(|| format!("invalid 256-color palette number for {}: {}", key, value))?;
+        map.insert((color.r, color.g, color.b), number as u8);
+    }
+    Ok(map)
+}
+
+/// Replace the RGB hex codes in a style string with their 256-color equivalents.
+pub fn remap_rgb_colors_in_style_string(
+    style_string: &str,
+    map: &HashMap<(u8, u8, u8), u8>,
+) -> String {
+    style_string
+        .split_whitespace()
+        .map(|word| match SyntectColor::from_str(word) {
+            Ok(color) if word.starts_with('#') => {
+                match remap_color((color.r, color.g, color.b), map) {
+                    Color::Fixed(n) => n.to_string(),
+                    _ => word.to_string(),
+                }
+            }
+            _ => word.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Replace the RGB colors in a syntax theme with their 256-color equivalents. The replacements
+/// use syntect's convention for palette colors: alpha 0, with the palette number in the red
+/// channel.
+pub fn remap_rgb_colors_in_syntax_theme(theme: &mut SyntaxTheme, map: &HashMap<(u8, u8, u8), u8>) {
+    let remap = |color: &mut Option<SyntectColor>| {
+        if let Some(c) = color {
+            if c.a != 0 {
+                if let Color::Fixed(n) = remap_color((c.r, c.g, c.b), map) {
+                    *c = SyntectColor {
+                        r: n,
+                        g: 0,
+                        b: 0,
+                        a: 0,
+                    };
+                }
+            }
+        }
+    };
+    remap(&mut theme.settings.foreground);
+    remap(&mut theme.settings.background);
+    for item in theme.scopes.iter_mut() {
+        remap(&mut item.style.foreground);
+        remap(&mut item.style.background);
+    }
+}
+
 // See
 // https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit
 pub fn ansi_color_name_to_number(name: &str) -> Option<u8> {
@@ -110,6 +189,18 @@ pub fn get_plus_emph_background_color_default(is_light_mode: bool, is_true_color
     }
 }
 
+pub fn get_number_gutter_background_color_default(
+    is_light_mode: bool,
+    is_true_color: bool,
+) -> Color {
+    match (is_light_mode, is_true_color) {
+        (true, true) => LIGHT_THEME_NUMBER_GUTTER_COLOR,
+        (true, false) => LIGHT_THEME_NUMBER_GUTTER_COLOR_256,
+        (false, true) => DARK_THEME_NUMBER_GUTTER_COLOR,
+        (false, false) => DARK_THEME_NUMBER_GUTTER_COLOR_256,
+    }
+}
+
 const LIGHT_THEME_MINUS_COLOR: Color = Color::RGB(0xff, 0xe0, 0xe0);
 
 const LIGHT_THEME_MINUS_COLOR_256: Color = Color::Fixed(224);
@@ -141,3 +232,11 @@ const DARK_THEME_PLUS_COLOR_256: Color = Color::Fixed(22);
 const DARK_THEME_PLUS_EMPH_COLOR: Color = Color::RGB(0x00, 0x60, 0x00);
 
 const DARK_THEME_PLUS_EMPH_COLOR_256: Color = Color::Fixed(28);
+
+const LIGHT_THEME_NUMBER_GUTTER_COLOR: Color = Color::RGB(0xe8, 0xe8, 0xe8);
+
+const LIGHT_THEME_NUMBER_GUTTER_COLOR_256: Color = Color::Fixed(254);
+
+const DARK_THEME_NUMBER_GUTTER_COLOR: Color = Color::RGB(0x30, 0x30, 0x30);
+
+const DARK_THEME_NUMBER_GUTTER_COLOR_256: Color = Color::Fixed(236);
@@ -1,5 +1,7 @@
+use std::cell::RefCell;
 use std::cmp::min;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use console::Term;
@@ -9,68 +11,223 @@ use syntect::highlighting::Style as SyntectStyle;
 use syntect::highlighting::Theme as SyntaxTheme;
 use syntect::parsing::SyntaxSet;
 
+use crate::align::IntraLineDiffAlgorithm;
 use crate::bat::assets::HighlightingAssets;
 use crate::bat::output::PagingMode;
 use crate::cli;
 use crate::color;
 use crate::delta::State;
+use crate::draw;
 use crate::env;
+use crate::experimental;
+use crate::git_attributes;
 use crate::git_config::GitConfig;
 use crate::rewrite_options;
 use crate::set_options;
-use crate::style::Style;
+use crate::style::{DecorationStyle, Style};
 use crate::syntax_theme;
+use crate::terminal;
+use crate::utils;
 
+#[derive(Debug)]
 pub enum Width {
     Fixed(usize),
     Variable,
 }
 
+/// How the background color of a line is extended to the decoration width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineFillMethod {
+    Ansi,   // Emit an ANSI "erase in line" sequence, which fills using the current background color
+    Spaces, // Pad the line with spaces
+    None,   // Do not extend the background color beyond the end of the text
+}
+
+/// The format of delta's output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Terminal, // Text with ANSI escape sequences
+    Html,     // A self-contained HTML document
+}
+
+/// The format of the diff that delta reads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffMode {
+    Unified, // As written by git diff and diff -u
+    Context, // As written by diff -c
+    Normal,  // As written by diff with no output format option
+}
+
+/// How a renamed file is shown in diff stat output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffStatRenameFormat {
+    Git,   // Leave git's notation unchanged
+    Short, // The new name only
+    Full,  // old_name → new_name
+    Arrow, // {old,new}/path
+}
+
+#[derive(Debug)]
+pub enum NumberColumnPlacement {
+    Left,
+    Right,
+}
+
+/// The characters with which box and line decorations are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoxStyle {
+    Ascii,   // +---+
+    Unicode, // ┌───┐, or ┏━━━┓ if the decoration style is bold
+    Heavy,   // ┏━━━┓
+    Double,  // ╔═══╗
+}
+
 pub struct Config<'a> {
-    pub background_color_extends_to_terminal_width: bool,
+    pub binary_hex_dump_max_bytes: Option<u64>,
+    pub box_drawing_character_set: BoxStyle,
+    pub check_file: Option<PathBuf>,
+    pub collapse_renames: bool,
+    pub color_moved: bool,
+    pub color_moved_alternate_style: Style,
+    pub color_moved_style: Style,
+    pub commit_format: Option<String>,
+    pub commit_limit: Option<usize>,
     pub commit_style: Style,
+    pub commit_style_by_author: Vec<(Regex, Style)>,
+    pub conflict_marker_size: usize,
+    pub context_line_patterns: Vec<(Regex, Style)>,
+    pub context_style: Style,
+    pub debug_config_diff: bool,
+    pub decoration_fallback_width: usize,
+    pub decoration_style_fallback: Option<DecorationStyle>,
     pub decorations_width: Width,
+    pub decrement_leading_spaces: usize,
+    // The delta-style git attribute values of the files displayed so far, by file path, under
+    // --per-file-git-attributes.
+    pub delta_style_attributes: RefCell<HashMap<String, String>>,
+    pub diff_args: Option<Vec<String>>,
+    pub diff_color_words: bool,
+    pub diff_mode: DiffMode,
+    // The compiled --diff-filter patterns, each with whether it excludes (rather than selects)
+    // the files it matches.
+    pub diff_filter_globs: Vec<(Regex, bool)>,
+    pub diff_header_only: bool,
+    pub diff_split_output: Option<String>,
+    pub diff_stat_rename_format: DiffStatRenameFormat,
+    pub ellipsis_style: Style,
+    pub emit_null_for_missing_files: bool,
+    pub empty_line_context: Option<usize>,
     pub file_added_label: String,
+    pub file_copied_label: String,
     pub file_modified_label: String,
+    pub file_path_truncation_prefix: Option<String>,
     pub file_removed_label: String,
     pub file_renamed_label: String,
+    pub file_renamed_new_style: Style,
+    pub file_renamed_old_style: Style,
     pub file_style: Style,
+    pub from_two_files: Option<(PathBuf, PathBuf)>,
+    // The styles with which git marks moved lines under its --color-moved option, each with
+    // whether it marks the alternate blocks (see --color-moved).
+    pub git_color_moved_styles: Vec<(ansi_term::Style, bool)>,
+    pub git_repo_root: Option<PathBuf>,
+    pub highlight_copied_lines: bool,
+    pub hunk_header_always: bool,
+    pub hunk_header_function_hint_max_length: Option<usize>,
+    pub hunk_header_include_file_path: bool,
     pub hunk_header_style: Style,
+    pub ignore_whitespace_change: bool,
+    pub ignored_commit_prefixes: Vec<String>,
+    pub indent_heuristic: bool,
+    pub intra_line_diff_algorithm: IntraLineDiffAlgorithm,
+    pub io_buffer_size_kb: usize,
+    pub language: Option<String>,
+    pub line_fill_method: LineFillMethod,
+    pub line_numbers_format: Option<String>,
+    pub list_experimental_features: bool,
     pub list_languages: bool,
     pub list_syntax_theme_names: bool,
     pub list_syntax_themes: bool,
     pub max_buffered_lines: usize,
+    pub max_file_size: Option<u64>,
     pub max_line_distance: f64,
     pub max_line_distance_for_naively_paired_lines: f64,
+    pub merged_diff: bool,
     pub minus_emph_style: Style,
+    pub minus_empty_line_marker: String,
+    pub minus_empty_line_marker_style: Style,
     pub minus_file: Option<PathBuf>,
     pub minus_line_marker: &'a str,
     pub minus_non_emph_style: Style,
+    pub minus_ruler: bool,
     pub minus_style: Style,
+    pub moved_style: Style,
     pub navigate: bool,
+    pub navigate_regexp: String,
+    pub null_file_indicator: String,
     pub null_style: Style,
     pub null_syntect_style: SyntectStyle,
+    pub number_column_placement: NumberColumnPlacement,
+    pub number_gutter_background: Option<ansi_term::Color>,
     pub number_minus_format: String,
     pub number_minus_format_style: Style,
     pub number_minus_style: Style,
     pub number_plus_format: String,
     pub number_plus_format_style: Style,
     pub number_plus_style: Style,
+    pub number_right_align: bool,
+    pub number_separator: String,
+    pub number_separator_style: Style,
+    pub output_format: OutputFormat,
+    pub pager_extra_args: Vec<String>,
+    pub pager_less_options: Option<String>,
+    pub pager_quit_if_smaller_than: Option<usize>,
     pub paging_mode: PagingMode,
+    pub per_file_git_attributes: bool,
     pub plus_emph_style: Style,
+    pub plus_emphasis_regex: Option<Regex>,
+    pub plus_empty_line_marker: String,
+    pub plus_empty_line_marker_style: Style,
     pub plus_file: Option<PathBuf>,
     pub plus_line_marker: &'a str,
     pub plus_non_emph_style: Style,
+    pub plus_ruler: bool,
     pub plus_style: Style,
+    pub post_process_cmd: Option<Vec<String>>,
+    pub rebase_action_styles: Vec<(&'static str, Style)>,
+    pub rebase_mode: bool,
+    pub redact_patterns: Vec<Regex>,
+    pub redact_secrets: bool,
     pub show_background_colors: bool,
+    pub show_colors: bool,
+    pub line_numbers_for_hunks_only: bool,
     pub show_line_numbers: bool,
+    pub show_line_numbers_for_context: bool,
+    pub show_rename_score: bool,
+    pub show_stats: bool,
+    pub show_syntax_errors: bool,
+    pub show_token_types: bool,
+    pub show_whitespace_errors: bool,
+    pub stash_style: Style,
+    pub strip_trailing_cr: bool,
+    pub submodule_diff_output: bool,
     pub syntax_dummy_theme: SyntaxTheme,
+    pub syntax_highlight_minus: bool,
+    pub syntax_highlight_plus: bool,
     pub syntax_set: SyntaxSet,
     pub syntax_theme: Option<SyntaxTheme>,
     pub syntax_theme_name: String,
     pub tab_width: usize,
+    pub tabs_to_spaces: bool,
+    pub test_no_pager: bool,
+    pub theme_preview_diff_path: Option<PathBuf>,
+    pub theme_set_dir: Option<String>,
     pub true_color: bool,
     pub tokenization_regex: Regex,
+    pub tokenization_regex_by_language: HashMap<String, Regex>,
+    pub whitespace_change_style: Style,
+    pub whitespace_error_style: Style,
+    pub width_minus_padding: usize,
     pub zero_style: Style,
 }
 
@@ -82,28 +239,368 @@ impl<'a> Config<'a> {
     pub fn from_arg_matches(
         arg_matches: clap::ArgMatches,
         git_config: &mut Option<GitConfig>,
+    ) -> Self {
+        Self::from_arg_matches_with_file_preset(arg_matches, git_config, None)
+    }
+
+    /// As `from_arg_matches`, but with `file_preset`, the preset named by the delta-style git
+    /// attribute of a file (see --per-file-git-attributes), activated after any others.
+    pub fn from_arg_matches_with_file_preset(
+        arg_matches: clap::ArgMatches,
+        git_config: &mut Option<GitConfig>,
+        file_preset: Option<&str>,
     ) -> Self {
         let mut opt = cli::Opt::from_clap(&arg_matches);
-        set_options::set_options(&mut opt, git_config, &arg_matches);
+        set_options::set_options(&mut opt, git_config, &arg_matches, file_preset);
         rewrite_options::apply_rewrite_rules(&mut opt, &arg_matches);
-        Self::from(opt)
+        let git_color_moved_styles = if opt.color_moved {
+            make_git_color_moved_styles(git_config)
+        } else {
+            Vec::new()
+        };
+        Self {
+            git_color_moved_styles,
+            ..Self::from(opt)
+        }
     }
 
     pub fn get_style(&self, state: &State) -> &Style {
         match state {
             State::CommitMeta => &self.commit_style,
+            State::StashHeader => &self.stash_style,
             State::FileMeta => &self.file_style,
-            State::HunkHeader => &self.hunk_header_style,
-            _ => unreachable("Unreachable code reached in get_style."),
+            State::HunkHeader | State::MergeConflict | State::SubmoduleMeta => {
+                &self.hunk_header_style
+            }
+            State::HunkZero | State::RebaseTodo => &self.zero_style,
+            State::HunkMinus => &self.minus_style,
+            State::HunkPlus => &self.plus_style,
+            State::Suppressed | State::Unknown => &self.null_style,
+        }
+    }
+
+    /// Under --per-file-git-attributes, the preset named by the delta-style git attribute of the
+    /// file whose diff starts at `line`, e.g. "diff --git a/README.md b/README.md". Attribute
+    /// values are looked up once per file path.
+    pub fn get_file_preset(&self, line: &str) -> Option<String> {
+        if !self.per_file_git_attributes {
+            return None;
         }
+        let path = git_attributes::get_file_path_from_diff_line(line)?;
+        let mut delta_style_attributes = self.delta_style_attributes.borrow_mut();
+        let value = delta_style_attributes
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                git_attributes::get_delta_style_attribute(path, self.git_repo_root.as_deref())
+            });
+        git_attributes::get_preset_from_attribute(value).map(str::to_string)
+    }
+
+    /// The style of the commit hash line of a commit by `author`, the value of its "Author:"
+    /// field, according to --commit-style-by-author-regexp.
+    pub fn get_commit_style(&self, author: &str) -> Style {
+        self.commit_style_by_author
+            .iter()
+            .find(|(regex, _)| regex.is_match(author))
+            .map_or(self.commit_style, |(_, style)| *style)
+    }
+
+    /// The --word-diff-regex to use for files in `language`.
+    pub fn get_tokenization_regex(&self, language: &str) -> &Regex {
+        self.tokenization_regex_by_language
+            .get(language)
+            .unwrap_or(&self.tokenization_regex)
+    }
+
+    /// Whether the file at `path` is to be omitted from the output according to --diff-filter.
+    pub fn is_file_filtered_out(&self, path: &str) -> bool {
+        let mut has_selecting_globs = false;
+        let mut is_selected = false;
+        for (regex, is_exclusion) in &self.diff_filter_globs {
+            if *is_exclusion {
+                if regex.is_match(path) {
+                    return true;
+                }
+            } else {
+                has_selecting_globs = true;
+                is_selected = is_selected || regex.is_match(path);
+            }
+        }
+        has_selecting_globs && !is_selected
+    }
+
+    /// Return the styles set by the style options, with the option names.
+    pub fn get_named_styles(&self) -> Vec<(&'static str, Style)> {
+        vec![
+            ("commit-style", self.commit_style),
+            ("file-style", self.file_style),
+            ("file-renamed-old-style", self.file_renamed_old_style),
+            ("file-renamed-new-style", self.file_renamed_new_style),
+            ("hunk-header-style", self.hunk_header_style),
+            ("stash-style", self.stash_style),
+            ("minus-style", self.minus_style),
+            ("minus-non-emph-style", self.minus_non_emph_style),
+            ("minus-emph-style", self.minus_emph_style),
+            (
+                "minus-empty-line-marker-style",
+                self.minus_empty_line_marker_style,
+            ),
+            ("zero-style", self.zero_style),
+            ("context-style", self.context_style),
+            ("plus-style", self.plus_style),
+            ("plus-non-emph-style", self.plus_non_emph_style),
+            ("plus-emph-style", self.plus_emph_style),
+            (
+                "plus-empty-line-marker-style",
+                self.plus_empty_line_marker_style,
+            ),
+            ("number-minus-style", self.number_minus_style),
+            ("number-minus-format-style", self.number_minus_format_style),
+            ("number-plus-style", self.number_plus_style),
+            ("number-plus-format-style", self.number_plus_format_style),
+            ("number-separator-style", self.number_separator_style),
+            ("whitespace-error-style", self.whitespace_error_style),
+            ("whitespace-change-style", self.whitespace_change_style),
+            ("moved-style", self.moved_style),
+            ("color-moved-style", self.color_moved_style),
+            (
+                "color-moved-alternate-style",
+                self.color_moved_alternate_style,
+            ),
+            ("ellipsis-style", self.ellipsis_style),
+        ]
+    }
+
+    /// Return the settings whose values differ between this config and `other`. The syntax
+    /// definitions and themes are not compared, other than by syntax theme name.
+    pub fn diff(&self, other: &Config) -> Vec<ConfigDiff> {
+        let mut diffs = Vec::new();
+        macro_rules! compare_fields {
+            ($($field:ident),*) => {
+                $(
+                    let left = format!("{:?}", self.$field);
+                    let right = format!("{:?}", other.$field);
+                    if left != right {
+                        diffs.push(ConfigDiff {
+                            field: stringify!($field),
+                            left,
+                            right,
+                        });
+                    }
+                )*
+            };
+        }
+        compare_fields!(
+            binary_hex_dump_max_bytes,
+            box_drawing_character_set,
+            check_file,
+            collapse_renames,
+            color_moved,
+            color_moved_alternate_style,
+            color_moved_style,
+            commit_format,
+            commit_limit,
+            commit_style,
+            commit_style_by_author,
+            conflict_marker_size,
+            context_line_patterns,
+            context_style,
+            debug_config_diff,
+            decoration_fallback_width,
+            decoration_style_fallback,
+            decorations_width,
+            decrement_leading_spaces,
+            delta_style_attributes,
+            diff_args,
+            diff_color_words,
+            diff_mode,
+            diff_filter_globs,
+            diff_header_only,
+            diff_split_output,
+            diff_stat_rename_format,
+            ellipsis_style,
+            emit_null_for_missing_files,
+            empty_line_context,
+            file_added_label,
+            file_copied_label,
+            file_modified_label,
+            file_path_truncation_prefix,
+            file_removed_label,
+            file_renamed_label,
+            file_renamed_new_style,
+            file_renamed_old_style,
+            file_style,
+            from_two_files,
+            git_color_moved_styles,
+            git_repo_root,
+            highlight_copied_lines,
+            hunk_header_always,
+            hunk_header_function_hint_max_length,
+            hunk_header_include_file_path,
+            hunk_header_style,
+            ignore_whitespace_change,
+            ignored_commit_prefixes,
+            indent_heuristic,
+            intra_line_diff_algorithm,
+            io_buffer_size_kb,
+            language,
+            line_fill_method,
+            line_numbers_format,
+            list_experimental_features,
+            list_languages,
+            list_syntax_theme_names,
+            list_syntax_themes,
+            max_buffered_lines,
+            max_file_size,
+            max_line_distance,
+            max_line_distance_for_naively_paired_lines,
+            merged_diff,
+            minus_emph_style,
+            minus_empty_line_marker,
+            minus_empty_line_marker_style,
+            minus_file,
+            minus_line_marker,
+            minus_non_emph_style,
+            minus_ruler,
+            minus_style,
+            moved_style,
+            navigate,
+            navigate_regexp,
+            null_file_indicator,
+            null_style,
+            number_column_placement,
+            number_gutter_background,
+            number_minus_format,
+            number_minus_format_style,
+            number_minus_style,
+            number_plus_format,
+            number_plus_format_style,
+            number_plus_style,
+            number_right_align,
+            number_separator,
+            number_separator_style,
+            output_format,
+            pager_extra_args,
+            pager_less_options,
+            pager_quit_if_smaller_than,
+            paging_mode,
+            per_file_git_attributes,
+            plus_emph_style,
+            plus_emphasis_regex,
+            plus_empty_line_marker,
+            plus_empty_line_marker_style,
+            plus_file,
+            plus_line_marker,
+            plus_non_emph_style,
+            plus_ruler,
+            plus_style,
+            post_process_cmd,
+            rebase_action_styles,
+            rebase_mode,
+            redact_patterns,
+            redact_secrets,
+            show_background_colors,
+            show_colors,
+            line_numbers_for_hunks_only,
+            show_line_numbers,
+            show_line_numbers_for_context,
+            show_rename_score,
+            show_stats,
+            show_syntax_errors,
+            show_token_types,
+            show_whitespace_errors,
+            stash_style,
+            strip_trailing_cr,
+            submodule_diff_output,
+            syntax_highlight_minus,
+            syntax_highlight_plus,
+            syntax_theme_name,
+            tab_width,
+            tabs_to_spaces,
+            test_no_pager,
+            theme_preview_diff_path,
+            theme_set_dir,
+            true_color,
+            tokenization_regex,
+            tokenization_regex_by_language,
+            whitespace_change_style,
+            whitespace_error_style,
+            width_minus_padding,
+            zero_style
+        );
+        diffs
     }
 }
 
+/// A setting whose value differs between two configs.
+#[derive(Debug, PartialEq)]
+pub struct ConfigDiff {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
 fn _check_validity(opt: &cli::Opt, assets: &HighlightingAssets) {
     if opt.light && opt.dark {
         eprintln!("--light and --dark cannot be used together.");
         process::exit(1);
     }
+    if opt.merged_diff && opt.rebase_mode {
+        eprintln!("--merged-diff and --rebase-mode cannot be used together.");
+        process::exit(1);
+    }
+    for value in &opt.rebase_action_style {
+        let action = value.split(':').next().unwrap_or("");
+        if !value.contains(':') || !REBASE_ACTIONS.iter().any(|(name, _)| *name == action) {
+            eprintln!(
+                "Invalid rebase-action-style: {}. The value must have the form <ACTION>:<STYLE>, \
+                 where <ACTION> is one of pick, reword, edit, squash, fixup, drop.",
+                value
+            );
+            process::exit(1);
+        }
+    }
+    if opt.ignore_rev.iter().any(|rev| rev.is_empty()) {
+        eprintln!("--ignore-rev must not be empty.");
+        process::exit(1);
+    }
+    if let Some(pager_less_options) = opt.pager_less_options.as_ref() {
+        if !(pager_less_options.contains('R') || pager_less_options.contains("--raw-control-chars"))
+        {
+            eprintln!(
+                "Warning: --pager-less-options {:?} does not include -R, \
+                 so less will not display colors.",
+                pager_less_options
+            );
+        }
+    }
+    if let Some(command) = opt.feature_flags.as_deref() {
+        if command != "experimental-features-list" {
+            eprintln!(
+                "Invalid value for --feature-flags option: {} \
+                 (the only valid value is experimental-features-list)",
+                command
+            );
+            process::exit(1);
+        }
+    }
+    if opt.io_buffer_size == 0 {
+        eprintln!("--io-buffer-size must be at least 1.");
+        process::exit(1);
+    }
+    if opt.conflict_marker_size == 0 {
+        eprintln!("--conflict-marker-size must be at least 1.");
+        process::exit(1);
+    }
+    if let Some(language) = opt.language.as_ref() {
+        if assets.syntax_set.find_syntax_by_token(language).is_none() {
+            eprintln!(
+                "Unknown language: {}. Use --list-languages to see the available languages.",
+                language
+            );
+            process::exit(1);
+        }
+    }
     if let Some(ref syntax_theme) = opt.syntax_theme {
         if !syntax_theme::is_no_syntax_highlighting_theme_name(&syntax_theme) {
             if !assets.theme_set.themes.contains_key(syntax_theme.as_str()) {
@@ -150,11 +647,21 @@ fn is_truecolor_terminal() -> bool {
 }
 
 impl<'a> From<cli::Opt> for Config<'a> {
-    fn from(opt: cli::Opt) -> Self {
-        let assets = HighlightingAssets::new();
+    fn from(mut opt: cli::Opt) -> Self {
+        let mut assets = HighlightingAssets::new();
+        if let Some(theme_set_dir) = opt.theme_set_dir.as_deref() {
+            syntax_theme::add_themes_from_dir(&mut assets.theme_set, Path::new(theme_set_dir))
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load syntax themes from --theme-set-dir: {}", err);
+                    process::exit(1);
+                });
+        }
 
         _check_validity(&opt, &assets);
 
+        let test_no_pager =
+            opt.test_no_pager || env::get_env_var("DELTA_NO_PAGER").as_deref() == Some("1");
+
         let paging_mode = match opt.paging_mode.as_ref() {
             "always" => PagingMode::Always,
             "never" => PagingMode::Never,
@@ -168,10 +675,42 @@ impl<'a> From<cli::Opt> for Config<'a> {
             }
         };
 
+        // --test-no-pager overrides all other sources of the paging mode.
+        let paging_mode = if test_no_pager {
+            PagingMode::Never
+        } else {
+            paging_mode
+        };
+
+        let pager_extra_args = match opt.pager_args.as_deref() {
+            Some(pager_args) => shell_words::split(pager_args).unwrap_or_else(|_| {
+                eprintln!("Could not parse --pager-args: {}", pager_args);
+                process::exit(1);
+            }),
+            None => Vec::new(),
+        };
+
+        let post_process_cmd = opt.post_process_cmd.as_deref().map(|post_process_cmd| {
+            match shell_words::split(post_process_cmd) {
+                Ok(words) if !words.is_empty() => words,
+                _ => {
+                    eprintln!("Could not parse --post-process-cmd: {}", post_process_cmd);
+                    process::exit(1);
+                }
+            }
+        });
+
+        let diff_args = opt.diff_args.as_deref().map(|diff_args| {
+            shell_words::split(diff_args).unwrap_or_else(|_| {
+                eprintln!("Could not parse --diff-args: {}", diff_args);
+                process::exit(1);
+            })
+        });
+
         let true_color = match opt.true_color.as_ref() {
             "always" => true,
             "never" => false,
-            "auto" => is_truecolor_terminal(),
+            "auto" => !test_no_pager && is_truecolor_terminal(),
             _ => {
                 eprintln!(
                 "Invalid value for --24-bit-color option: {} (valid values are \"always\", \"never\", and \"auto\")",
@@ -181,26 +720,137 @@ impl<'a> From<cli::Opt> for Config<'a> {
             }
         };
 
+        let true_color_fallback_map = match opt.true_color_fallback_map.as_deref() {
+            Some(path) => color::read_true_color_fallback_map(path).unwrap_or_else(|err| {
+                eprintln!(
+                    "Could not read --24-bit-color-fallback-map file {}: {}",
+                    path, err
+                );
+                process::exit(1);
+            }),
+            None => HashMap::new(),
+        };
+        if !true_color && !true_color_fallback_map.is_empty() {
+            remap_rgb_colors_in_style_options(&mut opt, &true_color_fallback_map);
+        }
+
         // Allow one character in case e.g. `less --status-column` is in effect. See #41 and #10.
-        let available_terminal_width = (Term::stdout().size().1 - 1) as usize;
-        let (decorations_width, background_color_extends_to_terminal_width) =
-            match opt.width.as_deref() {
-                Some("variable") => (Width::Variable, false),
-                Some(width) => {
-                    let width = width.parse().unwrap_or_else(|_| {
-                        eprintln!("Could not parse width as a positive integer: {:?}", width);
-                        process::exit(1);
-                    });
-                    (Width::Fixed(min(width, available_terminal_width)), true)
-                }
-                None => (Width::Fixed(available_terminal_width), true),
-            };
+        let terminal_width = (Term::stdout().size().1 - 1) as usize;
+        let available_terminal_width = terminal_width.saturating_sub(opt.width_minus_left_margin);
+        let decorations_width = make_decorations_width(&opt, terminal_width);
+
+        let output_format = match opt.output_format.as_ref() {
+            "terminal" => OutputFormat::Terminal,
+            "html" => OutputFormat::Html,
+            _ => {
+                eprintln!(
+                    "Invalid value for --output-format option: {} (valid values are \"terminal\" and \"html\")",
+                    opt.output_format
+                );
+                process::exit(1);
+            }
+        };
+
+        let diff_mode = match opt.diff_mode.as_ref() {
+            "unified" => DiffMode::Unified,
+            "context" => DiffMode::Context,
+            "normal" => DiffMode::Normal,
+            _ => {
+                eprintln!(
+                    "Invalid value for --diff-mode option: {} (valid values are \"unified\", \"context\" and \"normal\")",
+                    opt.diff_mode
+                );
+                process::exit(1);
+            }
+        };
 
+        let intra_line_diff_algorithm = match opt.intra_line_diff_algorithm.as_ref() {
+            "myers" => IntraLineDiffAlgorithm::Myers,
+            "patience" => IntraLineDiffAlgorithm::Patience,
+            "histogram" => IntraLineDiffAlgorithm::Histogram,
+            _ => {
+                eprintln!(
+                    "Invalid value for --intra-line-diff-algorithm option: {} (valid values are \"myers\", \"patience\" and \"histogram\")",
+                    opt.intra_line_diff_algorithm
+                );
+                process::exit(1);
+            }
+        };
+
+        let line_fill_method = match (&decorations_width, opt.line_fill_method.as_ref()) {
+            (Width::Variable, _) => LineFillMethod::None,
+            // HTML has no equivalent of the "erase in line" sequence.
+            (_, "ansi") if output_format == OutputFormat::Html => LineFillMethod::Spaces,
+            (_, "ansi") => LineFillMethod::Ansi,
+            (_, "spaces") => LineFillMethod::Spaces,
+            (_, "none") => LineFillMethod::None,
+            _ => {
+                eprintln!(
+                    "Invalid value for --line-fill-method option: {} (valid values are \"ansi\", \"spaces\", and \"none\")",
+                    opt.line_fill_method
+                );
+                process::exit(1);
+            }
+        };
+
+        let diff_stat_rename_format = match opt.diff_stat_file_rename_format.as_ref() {
+            "git" => DiffStatRenameFormat::Git,
+            "short" => DiffStatRenameFormat::Short,
+            "full" => DiffStatRenameFormat::Full,
+            "arrow" => DiffStatRenameFormat::Arrow,
+            _ => {
+                eprintln!(
+                    "Invalid value for --diff-stat-file-rename-format option: {} (valid values are \"git\", \"short\", \"full\", and \"arrow\")",
+                    opt.diff_stat_file_rename_format
+                );
+                process::exit(1);
+            }
+        };
+
+        let box_drawing_character_set = match opt.box_style.as_ref() {
+            "ascii" => BoxStyle::Ascii,
+            "unicode" => BoxStyle::Unicode,
+            "heavy" => BoxStyle::Heavy,
+            "double" => BoxStyle::Double,
+            _ => {
+                eprintln!(
+                    "Invalid value for --box-style option: {} (valid values are \"ascii\", \"unicode\", \"heavy\" and \"double\")",
+                    opt.box_style
+                );
+                process::exit(1);
+            }
+        };
+
+        let number_column_placement = match opt.number_column_placement.as_ref() {
+            "left" => NumberColumnPlacement::Left,
+            "right" => NumberColumnPlacement::Right,
+            _ => {
+                eprintln!(
+                    "Invalid value for --number-column-placement option: {} (valid values are \"left\" and \"right\")",
+                    opt.number_column_placement
+                );
+                process::exit(1);
+            }
+        };
+
+        if opt.null_file_indicator.chars().count() != 1 {
+            eprintln!(
+                "Invalid value for --null-file-indicator option: {:?} (the value must be a single character)",
+                opt.null_file_indicator
+            );
+            process::exit(1);
+        }
+
+        let light = if opt.auto_dark_light && !opt.light && !opt.dark {
+            terminal::is_light_background().unwrap_or(false)
+        } else {
+            opt.light
+        };
         let syntax_theme_name_from_bat_theme = env::get_env_var("BAT_THEME");
         let (is_light_mode, syntax_theme_name) = syntax_theme::get_is_light_mode_and_theme_name(
             opt.syntax_theme.as_ref(),
             syntax_theme_name_from_bat_theme.as_ref(),
-            opt.light,
+            light,
             &assets.theme_set,
         );
 
@@ -214,26 +864,157 @@ impl<'a> From<cli::Opt> for Config<'a> {
             plus_non_emph_style,
         ) = make_hunk_styles(&opt, is_light_mode, true_color);
 
-        let (commit_style, file_style, hunk_header_style) =
+        let git_repo_root = if opt.per_file_git_attributes {
+            git_attributes::get_repo_root()
+        } else {
+            None
+        };
+
+        let number_gutter_background = if opt.highlight_line_numbers {
+            color::color_from_rgb_or_ansi_code_with_default(
+                &opt.number_gutter_background_color,
+                Some(color::get_number_gutter_background_color_default(
+                    is_light_mode,
+                    true_color,
+                )),
+                true_color,
+            )
+        } else {
+            None
+        };
+
+        let (mut commit_style, mut file_style, mut hunk_header_style) =
             make_commit_file_hunk_header_styles(&opt, true_color);
 
+        let context_line_patterns = make_context_line_patterns(&opt, true_color);
+        let rebase_action_styles = make_rebase_action_styles(&opt, true_color);
+        let plus_emphasis_regex = opt.plus_emphasis_regexp.as_deref().map(|pattern| {
+            Regex::new(pattern).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid plus-emphasis-regexp: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    pattern
+                );
+                process::exit(1);
+            })
+        });
+
+        let diff_filter_globs = make_diff_filter_globs(&opt);
+
+        let whitespace_error_style = Style::from_str(
+            &opt.whitespace_error_style,
+            None,
+            None,
+            None,
+            true_color,
+            false,
+        );
+
+        let minus_empty_line_marker_style = Style::from_str(
+            &opt.minus_empty_line_marker_style,
+            None,
+            minus_style.ansi_term_style.background,
+            None,
+            true_color,
+            false,
+        );
+        let plus_empty_line_marker_style = Style::from_str(
+            &opt.plus_empty_line_marker_style,
+            None,
+            plus_style.ansi_term_style.background,
+            None,
+            true_color,
+            false,
+        );
+
+        let ellipsis_style =
+            Style::from_str(&opt.ellipsis_style, None, None, None, true_color, false);
+
+        let moved_style = Style::from_str(&opt.moved_style, None, None, None, true_color, false);
+
+        let color_moved_style =
+            Style::from_str(&opt.color_moved_style, None, None, None, true_color, false);
+        let color_moved_alternate_style = Style::from_str(
+            &opt.color_moved_alternate_style,
+            None,
+            None,
+            None,
+            true_color,
+            false,
+        );
+
+        let make_file_renamed_style = |style: Option<&str>| match style {
+            Some(style) => Style::from_str(style, None, None, None, true_color, false),
+            None => file_style,
+        };
+        let file_renamed_old_style = make_file_renamed_style(opt.file_renamed_old_style.as_deref());
+        let file_renamed_new_style = make_file_renamed_style(opt.file_renamed_new_style.as_deref());
+
+        let context_style = match opt.context_style.as_deref() {
+            Some(context_style) => {
+                Style::from_str(context_style, None, None, None, true_color, false)
+            }
+            None => zero_style,
+        };
+
+        let whitespace_change_style = match opt.whitespace_change_style.as_deref() {
+            Some(style) => Style::from_str(style, None, None, None, true_color, false),
+            None => zero_style,
+        };
+
+        let mut commit_style_by_author = make_commit_style_by_author(&opt, true_color);
+
+        let mut stash_style = match opt.stash_style.as_deref() {
+            Some(stash_style) => make_style_with_commit_decoration(&opt, stash_style, true_color),
+            None => commit_style,
+        };
+
+        let decoration_style_fallback = opt
+            .decoration_style_fallback
+            .as_deref()
+            .map(|style| DecorationStyle::from_str(style, true_color));
+        if let Some(fallback) = decoration_style_fallback {
+            if available_terminal_width < opt.decoration_fallback_width {
+                for style in vec![
+                    &mut commit_style,
+                    &mut file_style,
+                    &mut hunk_header_style,
+                    &mut stash_style,
+                ]
+                .into_iter()
+                .chain(commit_style_by_author.iter_mut().map(|(_, style)| style))
+                {
+                    if style.decoration_style != DecorationStyle::NoDecoration {
+                        style.decoration_style = fallback;
+                    }
+                }
+            }
+        }
+
         let (
             number_minus_format_style,
             number_minus_style,
             number_plus_format_style,
             number_plus_style,
+            number_separator_style,
         ) = make_line_number_styles(
             &opt,
             hunk_header_style.decoration_ansi_term_style(),
             true_color,
         );
 
-        let syntax_theme = if syntax_theme::is_no_syntax_highlighting_theme_name(&syntax_theme_name)
-        {
-            None
-        } else {
-            Some(assets.theme_set.themes[&syntax_theme_name].clone())
-        };
+        let mut syntax_theme =
+            if syntax_theme::is_no_syntax_highlighting_theme_name(&syntax_theme_name) {
+                None
+            } else {
+                Some(assets.theme_set.themes[&syntax_theme_name].clone())
+            };
+        if !true_color && !true_color_fallback_map.is_empty() {
+            if let Some(syntax_theme) = syntax_theme.as_mut() {
+                color::remap_rgb_colors_in_syntax_theme(syntax_theme, &true_color_fallback_map);
+            }
+        }
         let syntax_dummy_theme = assets.theme_set.themes.values().next().unwrap().clone();
 
         let minus_line_marker = if opt.keep_plus_minus_markers {
@@ -247,10 +1028,29 @@ impl<'a> From<cli::Opt> for Config<'a> {
             " "
         };
 
-        let max_line_distance_for_naively_paired_lines =
-            env::get_env_var("DELTA_EXPERIMENTAL_MAX_LINE_DISTANCE_FOR_NAIVELY_PAIRED_LINES")
-                .map(|s| s.parse::<f64>().unwrap_or(0.0))
-                .unwrap_or(0.0);
+        let experimental_features = experimental::parse_enabled_features(&opt.enable_experimental);
+        let max_line_distance_for_naively_paired_lines = experimental::get_feature_value(
+            &experimental_features,
+            "max-line-distance-for-naively-paired-lines",
+        )
+        .map(|s| s.parse::<f64>().unwrap_or(0.0))
+        .unwrap_or(0.0);
+
+        let navigate_regexp = make_navigate_regexp(&opt, &commit_style, box_drawing_character_set);
+        if opt.navigate_regex.is_some() && Regex::new(&navigate_regexp).is_err() {
+            eprintln!(
+                "Invalid navigate-regex: {}. \
+                 The value must be a valid regular expression.",
+                navigate_regexp
+            );
+            process::exit(1);
+        }
+
+        let redact_patterns = if opt.redact_secrets {
+            make_redact_patterns(&opt)
+        } else {
+            Vec::new()
+        };
 
         let tokenization_regex = Regex::new(&opt.tokenization_regex).unwrap_or_else(|_| {
             eprintln!(
@@ -261,52 +1061,158 @@ impl<'a> From<cli::Opt> for Config<'a> {
             );
             process::exit(1);
         });
+        let tokenization_regex_by_language = make_tokenization_regex_by_language(&opt);
 
         Self {
-            background_color_extends_to_terminal_width,
+            binary_hex_dump_max_bytes: opt.binary_hex_dump_max_bytes,
+            box_drawing_character_set,
+            check_file: opt.check,
+            collapse_renames: opt.collapse_renames,
+            color_moved: opt.color_moved,
+            color_moved_alternate_style,
+            color_moved_style,
+            commit_format: opt.commit_format,
+            commit_limit: opt.commit_limit,
             commit_style,
+            commit_style_by_author,
+            conflict_marker_size: opt.conflict_marker_size,
+            context_line_patterns,
+            context_style,
+            debug_config_diff: opt.debug_config_diff,
+            decoration_fallback_width: opt.decoration_fallback_width,
+            decoration_style_fallback,
             decorations_width,
-            file_added_label: opt.file_added_label,
-            file_modified_label: opt.file_modified_label,
-            file_removed_label: opt.file_removed_label,
-            file_renamed_label: opt.file_renamed_label,
+            decrement_leading_spaces: opt.decrement_leading_spaces,
+            delta_style_attributes: RefCell::new(HashMap::new()),
+            diff_args,
+            diff_color_words: opt.diff_color_words,
+            diff_mode,
+            diff_filter_globs,
+            diff_header_only: opt.diff_header_only,
+            diff_split_output: opt.diff_split_output,
+            diff_stat_rename_format,
+            ellipsis_style,
+            emit_null_for_missing_files: opt.emit_null_for_missing_files,
+            empty_line_context: opt.empty_line_context,
+            file_added_label: opt.file_added_label.replace("\\n", "\n"),
+            file_copied_label: opt.file_copied_label.replace("\\n", "\n"),
+            file_modified_label: opt.file_modified_label.replace("\\n", "\n"),
+            file_path_truncation_prefix: opt.file_path_truncation_prefix,
+            file_removed_label: opt.file_removed_label.replace("\\n", "\n"),
+            file_renamed_label: opt.file_renamed_label.replace("\\n", "\n"),
+            file_renamed_new_style,
+            file_renamed_old_style,
             file_style,
+            from_two_files: opt
+                .from_two_files
+                .map(|files| (files[0].clone(), files[1].clone())),
+            git_color_moved_styles: if opt.color_moved {
+                make_git_color_moved_styles(&None)
+            } else {
+                Vec::new()
+            },
+            git_repo_root,
+            highlight_copied_lines: opt.highlight_copied_lines,
+            hunk_header_always: opt.hunk_header_always,
+            hunk_header_function_hint_max_length: opt.hunk_header_function_hint_max_length,
+            hunk_header_include_file_path: opt.hunk_header_include_file_path,
             hunk_header_style,
+            ignore_whitespace_change: opt.ignore_whitespace_change,
+            ignored_commit_prefixes: opt
+                .ignore_rev
+                .iter()
+                .map(|rev| rev.to_lowercase())
+                .collect(),
+            indent_heuristic: !opt.no_indent_heuristic,
+            intra_line_diff_algorithm,
+            io_buffer_size_kb: opt.io_buffer_size,
+            language: opt.language,
+            line_fill_method,
+            line_numbers_format: opt.line_numbers_format,
+            list_experimental_features: opt.feature_flags.is_some(),
             list_languages: opt.list_languages,
             list_syntax_theme_names: opt.list_syntax_theme_names,
             list_syntax_themes: opt.list_syntax_themes,
             max_buffered_lines: 32,
+            max_file_size: opt.max_file_size,
             max_line_distance: opt.max_line_distance,
             max_line_distance_for_naively_paired_lines,
+            merged_diff: opt.merged_diff,
             minus_emph_style,
+            minus_empty_line_marker: opt.minus_empty_line_marker,
+            minus_empty_line_marker_style,
             minus_file: opt.minus_file.map(|s| s.clone()),
             minus_line_marker,
             minus_non_emph_style,
+            minus_ruler: opt.minus_ruler,
             minus_style,
+            moved_style,
             navigate: opt.navigate,
+            navigate_regexp,
+            null_file_indicator: opt.null_file_indicator,
             null_style: Style::new(),
             null_syntect_style: SyntectStyle::default(),
+            number_column_placement,
+            number_gutter_background,
             number_minus_format: opt.number_minus_format,
             number_minus_format_style,
             number_minus_style,
             number_plus_format: opt.number_plus_format,
             number_plus_format_style,
             number_plus_style,
+            number_right_align: opt.number_right_align,
+            number_separator: opt.number_separator,
+            number_separator_style,
+            output_format,
+            pager_extra_args,
+            pager_less_options: opt.pager_less_options,
+            pager_quit_if_smaller_than: opt.pager_quit_if_smaller_than,
             paging_mode,
+            per_file_git_attributes: opt.per_file_git_attributes,
             plus_emph_style,
+            plus_emphasis_regex,
+            plus_empty_line_marker: opt.plus_empty_line_marker,
+            plus_empty_line_marker_style,
             plus_file: opt.plus_file.map(|s| s.clone()),
             plus_line_marker,
             plus_non_emph_style,
+            plus_ruler: opt.plus_ruler,
             plus_style,
+            post_process_cmd,
+            rebase_action_styles,
+            rebase_mode: opt.rebase_mode,
+            redact_patterns,
+            redact_secrets: opt.redact_secrets,
             show_background_colors: opt.show_background_colors,
-            show_line_numbers: opt.show_line_numbers,
+            show_colors: opt.show_colors,
+            line_numbers_for_hunks_only: opt.line_numbers_for_hunks_only,
+            show_line_numbers: opt.show_line_numbers && !opt.line_numbers_for_hunks_only,
+            show_line_numbers_for_context: !opt.no_show_line_numbers_for_context,
+            show_rename_score: opt.show_rename_score,
+            show_stats: opt.show_stats,
+            show_syntax_errors: opt.show_syntax_errors,
+            show_token_types: opt.show_token_types,
+            show_whitespace_errors: opt.show_whitespace_errors,
+            stash_style,
+            strip_trailing_cr: opt.strip_trailing_cr,
+            submodule_diff_output: opt.submodule_diff_output,
             syntax_dummy_theme,
+            syntax_highlight_minus: !opt.no_syntax_highlight_removed,
+            syntax_highlight_plus: !opt.no_syntax_highlight_added,
             syntax_set: assets.syntax_set,
             syntax_theme,
             syntax_theme_name,
             tab_width: opt.tab_width,
+            tabs_to_spaces: opt.tabs_to_spaces,
+            test_no_pager,
+            theme_preview_diff_path: opt.theme_preview_diff,
+            theme_set_dir: opt.theme_set_dir,
             tokenization_regex,
+            tokenization_regex_by_language,
+            whitespace_change_style,
+            whitespace_error_style,
             true_color,
+            width_minus_padding: opt.width_minus_padding,
             zero_style,
         }
     }
@@ -400,7 +1306,7 @@ fn make_line_number_styles<'a>(
     opt: &'a cli::Opt,
     default_style: Option<ansi_term::Style>,
     true_color: bool,
-) -> (Style, Style, Style, Style) {
+) -> (Style, Style, Style, Style, Style) {
     let (default_foreground, default_background) = match default_style {
         Some(default_style) => (default_style.foreground, default_style.background),
         None => (None, None),
@@ -442,11 +1348,21 @@ fn make_line_number_styles<'a>(
         false,
     );
 
+    let number_separator_style = Style::from_str(
+        &opt.number_separator_style,
+        default_foreground,
+        default_background,
+        None,
+        true_color,
+        false,
+    );
+
     (
         number_minus_format_style,
         number_minus_style,
         number_plus_format_style,
         number_plus_style,
+        number_separator_style,
     )
 }
 
@@ -482,20 +1398,393 @@ fn make_commit_file_hunk_header_styles(opt: &cli::Opt, true_color: bool) -> (Sty
     )
 }
 
-pub fn make_navigate_regexp(config: &Config) -> String {
-    format!(
-        "^(commit|{}|{}|{}|{})",
-        config.file_modified_label,
-        config.file_added_label,
-        config.file_removed_label,
-        config.file_renamed_label
+/// Text matching these patterns is redacted under --redact-secrets.
+const SECRET_PATTERNS: &[&str] = &[
+    // AWS access key IDs
+    r"\b(AKIA|ASIA)[0-9A-Z]{16}\b",
+    // GitHub tokens
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    // Slack tokens
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    // JSON Web Tokens
+    r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+    // PEM private keys
+    r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+    // Values assigned to passwords, secrets, API keys and tokens. The name of the variable and
+    // the assignment are captured as "key", so that only the value is redacted.
+    r#"(?i)(?P<key>\b(?:password|passwd|secret|api_?key|access_?token|auth_?token)\b["']?\s*[:=]\s*["']?)[^\s"',;]+"#,
+];
+
+/// Compile the builtin secret patterns, followed by those given with --redact-regex.
+fn make_redact_patterns(opt: &cli::Opt) -> Vec<Regex> {
+    let builtin_patterns = SECRET_PATTERNS
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap());
+    let user_patterns = opt.redact_regex.iter().map(|pattern| {
+        Regex::new(pattern).unwrap_or_else(|_| {
+            eprintln!(
+                "Invalid redact-regex: {}. \
+                 The value must be a valid Rust regular expression. \
+                 See https://docs.rs/regex.",
+                pattern
+            );
+            process::exit(1);
+        })
+    });
+    builtin_patterns.chain(user_patterns).collect()
+}
+
+/// Parse the "<PATTERN>:<STYLE>" values of --context-line-regex. The style is taken to be
+/// everything after the final colon, so that the pattern itself may contain colons.
+fn make_context_line_patterns(opt: &cli::Opt, true_color: bool) -> Vec<(Regex, Style)> {
+    opt.context_line_regex
+        .iter()
+        .map(|value| {
+            let (pattern, style) = match value.rfind(':') {
+                Some(i) => (&value[..i], &value[i + 1..]),
+                None => {
+                    eprintln!(
+                        "Invalid context-line-regex: {}. \
+                         The value must have the form <PATTERN>:<STYLE>.",
+                        value
+                    );
+                    process::exit(1);
+                }
+            };
+            let regex = Regex::new(pattern).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid context-line-regex: {}. \
+                     The pattern must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    pattern
+                );
+                process::exit(1);
+            });
+            let style = Style::from_str(style, None, None, None, true_color, false);
+            (regex, style)
+        })
+        .collect()
+}
+
+/// Parse the "<REGEX>:<STYLE>,..." value of --commit-style-by-author-regexp. As for
+/// --tokenization-regex-by-language, an entry ends at a comma that follows its style (which
+/// contains no comma or colon), so that the regexes may contain commas and colons.
+fn make_commit_style_by_author(opt: &cli::Opt, true_color: bool) -> Vec<(Regex, Style)> {
+    let value = match opt.commit_style_by_author_regexp.as_deref() {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+    let entry_regex = Regex::new(r"(.+?):([^:,]*)(?:,|$)").unwrap();
+    let entries: Vec<_> = entry_regex.captures_iter(value).collect();
+    let mut end = 0;
+    for captures in &entries {
+        let entry = captures.get(0).unwrap();
+        if entry.start() != end {
+            break;
+        }
+        end = entry.end();
+    }
+    if entries.is_empty() || end != value.len() {
+        eprintln!(
+            "Invalid commit-style-by-author-regexp: {}. \
+             The value must be a comma-separated list of <REGEX>:<STYLE> entries.",
+            value
+        );
+        process::exit(1);
+    }
+    entries
+        .iter()
+        .map(|captures| {
+            let (pattern, style) = (captures[1].trim(), &captures[2]);
+            let regex = Regex::new(pattern).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid commit-style-by-author-regexp regex: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    pattern
+                );
+                process::exit(1);
+            });
+            (
+                regex,
+                make_style_with_commit_decoration(opt, style, true_color),
+            )
+        })
+        .collect()
+}
+
+/// The actions of a rebase TODO list that are styled under --rebase-mode, with their default
+/// styles.
+const REBASE_ACTIONS: [(&str, &str); 6] = [
+    ("pick", "green"),
+    ("reword", "blue"),
+    ("edit", "yellow"),
+    ("squash", "cyan"),
+    ("fixup", "purple"),
+    ("drop", "red"),
+];
+
+/// Return the style of each rebase action, applying the "<ACTION>:<STYLE>" values of
+/// --rebase-action-style to the default styles.
+fn make_rebase_action_styles(opt: &cli::Opt, true_color: bool) -> Vec<(&'static str, Style)> {
+    REBASE_ACTIONS
+        .iter()
+        .map(|(action, default_style)| {
+            let style = opt
+                .rebase_action_style
+                .iter()
+                .rev()
+                .find_map(|value| value.strip_prefix(action)?.strip_prefix(':'))
+                .unwrap_or(default_style);
+            (
+                *action,
+                Style::from_str(style, None, None, None, true_color, false),
+            )
+        })
+        .collect()
+}
+
+/// Parse the "<LANGUAGE>:<REGEX>,..." value of --tokenization-regex-by-language. An entry ends
+/// at a comma that is followed by the next "<LANGUAGE>:", so that the regexes may contain commas.
+fn make_tokenization_regex_by_language(opt: &cli::Opt) -> HashMap<String, Regex> {
+    let value = match opt.tokenization_regex_by_language.as_deref() {
+        Some(value) => value,
+        None => return HashMap::new(),
+    };
+    let language_regex = Regex::new(r"(?:^|,)\s*([A-Za-z][\w #+-]*):").unwrap();
+    let languages: Vec<_> = language_regex.captures_iter(value).collect();
+    if languages
+        .first()
+        .map(|captures| captures.get(0).unwrap().start())
+        != Some(0)
+    {
+        eprintln!(
+            "Invalid value for tokenization-regex-by-language: {}. \
+             The value must be a comma-separated list of <LANGUAGE>:<REGEX> entries.",
+            value
+        );
+        process::exit(1);
+    }
+    languages
+        .iter()
+        .enumerate()
+        .map(|(i, captures)| {
+            let language = captures[1].trim_end();
+            let end = languages
+                .get(i + 1)
+                .map_or(value.len(), |next| next.get(0).unwrap().start());
+            let pattern = &value[captures.get(0).unwrap().end()..end];
+            let regex = Regex::new(pattern).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid tokenization-regex-by-language regex for {}: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    language, pattern
+                );
+                process::exit(1);
+            });
+            (language.to_string(), regex)
+        })
+        .collect()
+}
+
+fn make_diff_filter_globs(opt: &cli::Opt) -> Vec<(Regex, bool)> {
+    opt.diff_filter
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|glob| !glob.is_empty())
+        .map(|glob| {
+            let (glob, is_exclusion) = match glob.strip_prefix('!') {
+                Some(glob) => (glob, true),
+                None => (glob, false),
+            };
+            let regex = Regex::new(&utils::glob_to_regex(glob)).unwrap_or_else(|_| {
+                eprintln!("Invalid pattern in --diff-filter: {}", glob);
+                process::exit(1);
+            });
+            (regex, is_exclusion)
+        })
+        .collect()
+}
+
+/// The git config keys of the colors with which git marks moved lines under its --color-moved
+/// option, with git's default values, and whether they mark the alternate blocks of the zebra
+/// modes.
+const GIT_COLOR_MOVED_COLORS: [(&str, &str, bool); 8] = [
+    ("color.diff.oldMoved", "bold magenta", false),
+    ("color.diff.newMoved", "bold cyan", false),
+    ("color.diff.oldMovedAlternative", "bold blue", true),
+    ("color.diff.newMovedAlternative", "bold yellow", true),
+    ("color.diff.oldMovedDimmed", "dim", false),
+    ("color.diff.newMovedDimmed", "dim", false),
+    ("color.diff.oldMovedAlternativeDimmed", "dim italic", true),
+    ("color.diff.newMovedAlternativeDimmed", "dim italic", true),
+];
+
+/// Compute the width of decorations and background colors. Only a width detected from the terminal
+/// is reduced by --width-minus-padding; an explicit --width is limited by the terminal width.
+fn make_decorations_width(opt: &cli::Opt, terminal_width: usize) -> Width {
+    let available_terminal_width = terminal_width.saturating_sub(opt.width_minus_left_margin);
+    match opt.width.as_deref() {
+        Some("variable") => Width::Variable,
+        Some(width) => {
+            let width = width.parse().unwrap_or_else(|_| {
+                eprintln!("Could not parse width as a positive integer: {:?}", width);
+                process::exit(1);
+            });
+            Width::Fixed(min(width, available_terminal_width))
+        }
+        None => {
+            let margins = opt.width_minus_left_margin + opt.width_minus_padding;
+            if margins >= terminal_width {
+                eprintln!(
+                    "Invalid values for --width-minus-left-margin and --width-minus-padding options: {} and {} (the terminal is only {} columns wide)",
+                    opt.width_minus_left_margin, opt.width_minus_padding, terminal_width
+                );
+                process::exit(1);
+            }
+            Width::Fixed(terminal_width - margins)
+        }
+    }
+}
+
+fn make_git_color_moved_styles(git_config: &Option<GitConfig>) -> Vec<(ansi_term::Style, bool)> {
+    GIT_COLOR_MOVED_COLORS
+        .iter()
+        .map(|(key, default, is_alternate)| {
+            let color = git_config
+                .as_ref()
+                .and_then(|git_config| git_config.get::<String>(key))
+                .unwrap_or_else(|| default.to_string());
+            // Git writes RGB colors as such, whatever the terminal.
+            let style = Style::from_str(&color, None, None, None, true, false);
+            (style.ansi_term_style, *is_alternate)
+        })
+        .collect()
+}
+
+/// Make a style for a commit-like header line, decorated with --commit-decoration-style.
+fn make_style_with_commit_decoration(opt: &cli::Opt, style: &str, true_color: bool) -> Style {
+    Style::from_str_with_handling_of_special_decoration_attributes_and_respecting_deprecated_foreground_color_arg(
+        style,
+        None,
+        None,
+        Some(&opt.commit_decoration_style),
+        None,
+        true_color,
+        false,
     )
 }
 
+/// Replace the RGB hex codes in all style options with the 256-color equivalents given by the
+/// --24-bit-color-fallback-map mapping.
+fn remap_rgb_colors_in_style_options(opt: &mut cli::Opt, map: &HashMap<(u8, u8, u8), u8>) {
+    for style_string in [
+        &mut opt.minus_style,
+        &mut opt.zero_style,
+        &mut opt.plus_style,
+        &mut opt.minus_emph_style,
+        &mut opt.minus_non_emph_style,
+        &mut opt.plus_emph_style,
+        &mut opt.plus_non_emph_style,
+        &mut opt.minus_empty_line_marker_style,
+        &mut opt.plus_empty_line_marker_style,
+        &mut opt.whitespace_error_style,
+        &mut opt.ellipsis_style,
+        &mut opt.moved_style,
+        &mut opt.color_moved_style,
+        &mut opt.color_moved_alternate_style,
+        &mut opt.commit_style,
+        &mut opt.commit_decoration_style,
+        &mut opt.file_style,
+        &mut opt.file_decoration_style,
+        &mut opt.hunk_header_style,
+        &mut opt.hunk_header_decoration_style,
+        &mut opt.number_minus_style,
+        &mut opt.number_plus_style,
+        &mut opt.number_minus_format_style,
+        &mut opt.number_plus_format_style,
+        &mut opt.number_separator_style,
+        &mut opt.number_gutter_background_color,
+    ] {
+        *style_string = color::remap_rgb_colors_in_style_string(style_string, map);
+    }
+    if let Some(file_renamed_old_style) = opt.file_renamed_old_style.as_mut() {
+        *file_renamed_old_style =
+            color::remap_rgb_colors_in_style_string(file_renamed_old_style, map);
+    }
+    if let Some(file_renamed_new_style) = opt.file_renamed_new_style.as_mut() {
+        *file_renamed_new_style =
+            color::remap_rgb_colors_in_style_string(file_renamed_new_style, map);
+    }
+    if let Some(context_style) = opt.context_style.as_mut() {
+        *context_style = color::remap_rgb_colors_in_style_string(context_style, map);
+    }
+    if let Some(stash_style) = opt.stash_style.as_mut() {
+        *stash_style = color::remap_rgb_colors_in_style_string(stash_style, map);
+    }
+    if let Some(whitespace_change_style) = opt.whitespace_change_style.as_mut() {
+        *whitespace_change_style =
+            color::remap_rgb_colors_in_style_string(whitespace_change_style, map);
+    }
+}
+
+fn make_navigate_regexp(opt: &cli::Opt, commit_style: &Style, box_style: BoxStyle) -> String {
+    match &opt.navigate_regex {
+        Some(navigate_regex) => navigate_regex.to_string(),
+        None => {
+            let decoration_line = if opt.navigate_include_decorations {
+                make_commit_decoration_line_regexp(commit_style, box_style)
+            } else {
+                None
+            };
+            // A multi-line label is matched by its first line, where the file header starts.
+            let first_line = |label: &str| label.split("\\n").next().unwrap_or("").to_string();
+            format!(
+                "^(commit|{}|{}|{}|{}|{}{})",
+                first_line(&opt.file_modified_label),
+                first_line(&opt.file_added_label),
+                first_line(&opt.file_removed_label),
+                first_line(&opt.file_renamed_label),
+                first_line(&opt.file_copied_label),
+                decoration_line
+                    .map(|regexp| format!("|{}", regexp))
+                    .unwrap_or_default()
+            )
+        }
+    }
+}
+
+/// Return a regular expression matching the decoration line that is drawn above the commit line
+/// under `commit_style`, if there is one.
+fn make_commit_decoration_line_regexp(commit_style: &Style, box_style: BoxStyle) -> Option<String> {
+    match commit_style.decoration_style {
+        DecorationStyle::Box(style)
+        | DecorationStyle::BoxWithUnderline(style)
+        | DecorationStyle::BoxWithOverline(style)
+        | DecorationStyle::BoxWithUnderOverline(style) => {
+            let characters = draw::get_box_drawing_characters(box_style, style);
+            Some(format!(
+                "{}+{}$",
+                regex::escape(characters.horizontal),
+                regex::escape(characters.down_left)
+            ))
+        }
+        DecorationStyle::Overline(style) | DecorationStyle::UnderOverline(style) => {
+            let characters = draw::get_box_drawing_characters(box_style, style);
+            Some(format!("{}+$", regex::escape(characters.horizontal)))
+        }
+        DecorationStyle::Underline(_) | DecorationStyle::NoDecoration => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::env;
+    use std::fs;
 
     use crate::color;
     use crate::tests::integration_test_utils::integration_test_utils;
@@ -617,4 +1906,426 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_navigate_regexp() {
+        let config = integration_test_utils::make_config(&["--navigate"]);
+        assert_eq!(
+            config.navigate_regexp,
+            "^(commit|Δ|added:|removed:|renamed:|copied:)"
+        );
+
+        let config =
+            integration_test_utils::make_config(&["--navigate", "--navigate-regex", "^@@"]);
+        assert_eq!(config.navigate_regexp, "^@@");
+    }
+
+    #[test]
+    fn test_navigate_regexp_include_decorations() {
+        let config = integration_test_utils::make_config(&[
+            "--navigate",
+            "--navigate-include-decorations",
+            "--commit-decoration-style",
+            "box",
+        ]);
+        assert_eq!(
+            config.navigate_regexp,
+            "^(commit|Δ|added:|removed:|renamed:|copied:|─+┐$)"
+        );
+
+        let config = integration_test_utils::make_config(&[
+            "--navigate",
+            "--navigate-include-decorations",
+            "--commit-decoration-style",
+            "bold ol",
+        ]);
+        assert_eq!(
+            config.navigate_regexp,
+            "^(commit|Δ|added:|removed:|renamed:|copied:|━+$)"
+        );
+
+        let config = integration_test_utils::make_config(&[
+            "--navigate",
+            "--navigate-include-decorations",
+            "--commit-decoration-style",
+            "ul",
+        ]);
+        assert_eq!(
+            config.navigate_regexp,
+            "^(commit|Δ|added:|removed:|renamed:|copied:)"
+        );
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let config = integration_test_utils::make_config(&[]);
+        assert_eq!(
+            config.diff(&integration_test_utils::make_config(&[])),
+            vec![]
+        );
+
+        let other = integration_test_utils::make_config(&["--navigate", "--tabs", "8"]);
+        let fields: Vec<&str> = config.diff(&other).iter().map(|diff| diff.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "file_modified_label",
+                "navigate",
+                "navigate_regexp",
+                "tab_width"
+            ]
+        );
+        let diffs = config.diff(&other);
+        assert_eq!(
+            diffs[1],
+            ConfigDiff {
+                field: "navigate",
+                left: "false".to_string(),
+                right: "true".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_pager_extra_args() {
+        let config = integration_test_utils::make_config(&[]);
+        assert!(config.pager_extra_args.is_empty());
+
+        let config = integration_test_utils::make_config(&[
+            "--pager-args",
+            "--chop-long-lines --prompt='delta diff'",
+        ]);
+        assert_eq!(
+            config.pager_extra_args,
+            vec!["--chop-long-lines", "--prompt=delta diff"]
+        );
+    }
+
+    #[test]
+    fn test_pager_less_options() {
+        let config = integration_test_utils::make_config(&[]);
+        assert_eq!(config.pager_less_options, None);
+
+        let config = integration_test_utils::make_config(&["--pager-less-options", "-FRX"]);
+        assert_eq!(config.pager_less_options, Some("-FRX".to_string()));
+    }
+
+    #[test]
+    fn test_pager_quit_if_smaller_than() {
+        use crate::bat::output::OutputType;
+        let config = integration_test_utils::make_config(&["--pager-quit-if-smaller-than", "10"]);
+        let output_type =
+            OutputType::from_mode_for_output_size(PagingMode::Always, Some("cat"), 9, &config);
+        assert!(matches!(output_type, Ok(OutputType::Stdout(_))));
+        let output_type =
+            OutputType::from_mode_for_output_size(PagingMode::Always, Some("cat"), 10, &config);
+        assert!(matches!(output_type, Ok(OutputType::Pager(_))));
+    }
+
+    #[test]
+    fn test_test_no_pager() {
+        let config = integration_test_utils::make_config(&["--paging", "always"]);
+        assert_eq!(config.paging_mode, PagingMode::Never);
+        assert!(!config.true_color);
+        let config = integration_test_utils::make_config(&["--24-bit-color", "always"]);
+        assert!(config.true_color);
+    }
+
+    #[test]
+    fn test_diff_args() {
+        let config = integration_test_utils::make_config(&[]);
+        assert_eq!(config.diff_args, None);
+
+        let config =
+            integration_test_utils::make_config(&["--diff-args", "--word-diff=plain 'HEAD~1'"]);
+        assert_eq!(
+            config.diff_args,
+            Some(vec!["--word-diff=plain".to_string(), "HEAD~1".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_diff_color_words() {
+        let config = integration_test_utils::make_config(&[]);
+        assert!(!config.diff_color_words);
+        let config = integration_test_utils::make_config(&["--diff-color-words"]);
+        assert!(config.diff_color_words);
+        assert_eq!(config.tokenization_regex.as_str(), "[^[:space:]]+");
+    }
+
+    #[test]
+    fn test_indent_heuristic() {
+        let config = integration_test_utils::make_config(&[]);
+        assert!(config.indent_heuristic);
+        let config = integration_test_utils::make_config(&["--no-indent-heuristic"]);
+        assert!(!config.indent_heuristic);
+        let config =
+            integration_test_utils::make_config(&["--no-indent-heuristic", "--indent-heuristic"]);
+        assert!(config.indent_heuristic);
+    }
+
+    #[test]
+    fn test_post_process_cmd() {
+        let config = integration_test_utils::make_config(&[]);
+        assert_eq!(config.post_process_cmd, None);
+
+        let config = integration_test_utils::make_config(&["--post-process-cmd", "sed 's/a b/c/'"]);
+        assert_eq!(
+            config.post_process_cmd,
+            Some(vec!["sed".to_string(), "s/a b/c/".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_commit_style_by_author_regexp_with_commas_and_colons() {
+        let config = integration_test_utils::make_config(&[
+            "--commit-style-by-author-regexp",
+            r"^\w{2,} Davison:bold yellow, (?i:NOBODY):red",
+        ]);
+        let patterns: Vec<_> = config
+            .commit_style_by_author
+            .iter()
+            .map(|(regex, _)| regex.as_str())
+            .collect();
+        assert_eq!(patterns, vec![r"^\w{2,} Davison", "(?i:NOBODY)"]);
+        assert_eq!(
+            config.commit_style_by_author[1]
+                .1
+                .ansi_term_style
+                .foreground,
+            Some(ansi_term::Color::Fixed(1))
+        );
+    }
+
+    #[test]
+    fn test_tokenization_regex_by_language() {
+        let config = integration_test_utils::make_config(&[
+            "--word-diff-regex",
+            r"\S+",
+            "--tokenization-regex-by-language",
+            r"Rust:\w+|::, LaTeX:\\[a-zA-Z]+|\d{1,3}",
+        ]);
+        assert_eq!(config.get_tokenization_regex("Rust").as_str(), r"\w+|::");
+        assert_eq!(
+            config.get_tokenization_regex("LaTeX").as_str(),
+            r"\\[a-zA-Z]+|\d{1,3}"
+        );
+        assert_eq!(config.get_tokenization_regex("Python").as_str(), r"\S+");
+    }
+
+    #[test]
+    fn test_from_two_files() {
+        let config = integration_test_utils::make_config(&[]);
+        assert_eq!(config.from_two_files, None);
+
+        // The positional file arguments given by make_config cannot be combined with the option.
+        let config = Config::from_args(
+            &[
+                "delta",
+                "--from-two-files",
+                "a.txt",
+                "b.txt",
+                "--no-gitconfig",
+            ],
+            &mut None,
+        );
+        assert_eq!(
+            config.from_two_files,
+            Some((PathBuf::from("a.txt"), PathBuf::from("b.txt")))
+        );
+        assert!(cli::Opt::clap()
+            .get_matches_from_safe(["delta", "x", "y", "--from-two-files", "a.txt", "b.txt"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_width_minus_left_margin() {
+        let get_width = |config: &Config| match config.decorations_width {
+            Width::Fixed(width) => width,
+            Width::Variable => panic!("expected a fixed width"),
+        };
+        let terminal_width = get_width(&integration_test_utils::make_config(&[]));
+        let config = integration_test_utils::make_config(&["--width-minus-left-margin", "10"]);
+        assert_eq!(get_width(&config), terminal_width - 10);
+
+        // An explicit --width is limited by the reduced terminal width.
+        let config = integration_test_utils::make_config(&[
+            "--width-minus-left-margin",
+            "10",
+            "--width",
+            &terminal_width.to_string(),
+        ]);
+        assert_eq!(get_width(&config), terminal_width - 10);
+    }
+
+    #[test]
+    fn test_width_minus_padding() {
+        let get_width = |config: &Config| match config.decorations_width {
+            Width::Fixed(width) => width,
+            Width::Variable => panic!("expected a fixed width"),
+        };
+        let terminal_width = get_width(&integration_test_utils::make_config(&[]));
+        let config = integration_test_utils::make_config(&[
+            "--width-minus-left-margin",
+            "10",
+            "--width-minus-padding",
+            "5",
+        ]);
+        assert_eq!(config.width_minus_padding, 5);
+        assert_eq!(get_width(&config), terminal_width - 15);
+
+        // An explicit --width is not reduced by the padding, and fits a narrow terminal.
+        let opt = cli::Opt::from_iter(&["delta", "--width", "80", "--width-minus-padding", "50"]);
+        match make_decorations_width(&opt, 40) {
+            Width::Fixed(width) => assert_eq!(width, 40),
+            Width::Variable => panic!("expected a fixed width"),
+        }
+        match make_decorations_width(&opt, 120) {
+            Width::Fixed(width) => assert_eq!(width, 80),
+            Width::Variable => panic!("expected a fixed width"),
+        }
+    }
+
+    #[test]
+    fn test_decoration_style_fallback() {
+        let make_config = |fallback_width: usize| {
+            integration_test_utils::make_config(&[
+                "--commit-decoration-style",
+                "box",
+                "--file-decoration-style",
+                "ul",
+                "--hunk-header-decoration-style",
+                "omit",
+                "--decoration-style-fallback",
+                "overline",
+                "--decoration-fallback-width",
+                &fallback_width.to_string(),
+            ])
+        };
+        let overline = DecorationStyle::from_str("overline", true);
+
+        // The decorations are unchanged when the terminal is at least as wide as the threshold.
+        let config = make_config(1);
+        assert!(matches!(
+            config.commit_style.decoration_style,
+            DecorationStyle::Box(_)
+        ));
+
+        let config = make_config(10000);
+        assert_eq!(config.commit_style.decoration_style, overline);
+        assert_eq!(config.file_style.decoration_style, overline);
+        assert_eq!(
+            config.hunk_header_style.decoration_style,
+            DecorationStyle::NoDecoration
+        );
+    }
+
+    #[test]
+    fn test_get_style() {
+        let config = integration_test_utils::make_config(&[
+            "--commit-style",
+            "red",
+            "--file-style",
+            "green",
+            "--hunk-header-style",
+            "blue",
+            "--zero-style",
+            "yellow",
+            "--minus-style",
+            "magenta",
+            "--plus-style",
+            "cyan",
+        ]);
+        assert_eq!(config.get_style(&State::CommitMeta), &config.commit_style);
+        assert_eq!(config.get_style(&State::StashHeader), &config.stash_style);
+        assert_eq!(config.get_style(&State::FileMeta), &config.file_style);
+        assert_eq!(
+            config.get_style(&State::HunkHeader),
+            &config.hunk_header_style
+        );
+        assert_eq!(config.get_style(&State::HunkZero), &config.zero_style);
+        assert_eq!(config.get_style(&State::HunkMinus), &config.minus_style);
+        assert_eq!(config.get_style(&State::HunkPlus), &config.plus_style);
+        assert_eq!(config.get_style(&State::Unknown), &config.null_style);
+    }
+
+    #[test]
+    fn test_true_color_fallback_map() {
+        let path = std::env::temp_dir().join(format!(
+            "delta__test_true_color_fallback_map.{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, r##"{"#3f0001": 88, "#ffc0c0": 210}"##).unwrap();
+        let config = integration_test_utils::make_config(&[
+            "--24-bit-color",
+            "never",
+            "--24-bit-color-fallback-map",
+            path.to_str().unwrap(),
+            "--minus-style",
+            "normal #3f0001",
+            "--minus-emph-style",
+            "normal #ffc0c0",
+            "--plus-style",
+            "normal #002800",
+        ]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            config.minus_style.ansi_term_style.background,
+            Some(ansi_term::Color::Fixed(88))
+        );
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.background,
+            Some(ansi_term::Color::Fixed(210))
+        );
+        // Colors absent from the mapping are converted to the closest 256-color palette color.
+        assert_eq!(
+            config.plus_style.ansi_term_style.background,
+            Some(ansi_term::Color::Fixed(234))
+        );
+    }
+
+    #[test]
+    fn test_theme_set_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("delta__test_theme_set_dir.{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("MyTheme.tmTheme"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>MyTheme</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>background</key>
+                <string>#102030</string>
+                <key>foreground</key>
+                <string>#f0e0d0</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#,
+        )
+        .unwrap();
+        let config = integration_test_utils::make_config(&[
+            "--theme-set-dir",
+            dir.to_str().unwrap(),
+            "--syntax-theme",
+            "MyTheme",
+        ]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(config.syntax_theme_name, "MyTheme");
+        let background = config.syntax_theme.unwrap().settings.background.unwrap();
+        assert_eq!(
+            (background.r, background.g, background.b),
+            (0x10, 0x20, 0x30)
+        );
+    }
 }
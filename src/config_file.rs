@@ -0,0 +1,282 @@
+//! The delta config file given by --config (or the DELTA_CONFIG_PATH environment variable). It is
+//! a TOML file using the same option names as the "delta" section of git config:
+//!
+//! ```toml
+//! number = true
+//! zero-style = "dim syntax"
+//!
+//! [my-delta-preset]
+//! syntax-theme = "Dracula"
+//! ```
+//!
+//! Keys before the first table, or in a table named "delta", are main settings; keys in any other
+//! table are settings of the preset with that name. Each value is stored under the key that it
+//! would have in git config (e.g. "delta.number" or "delta.my-delta-preset.syntax-theme"), so
+//! that the file can be consulted wherever git config is. Only the part of TOML needed for delta
+//! options is supported: tables, and string, boolean, integer, and float values.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+pub struct ConfigFile {
+    values: HashMap<String, String>,
+}
+
+impl ConfigFile {
+    pub fn from_path(path: &Path) -> Self {
+        let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read config file {}: {}", path.display(), err);
+            process::exit(1);
+        });
+        Self::parse(&contents).unwrap_or_else(|err| {
+            eprintln!("Failed to parse config file {}: {}", path.display(), err);
+            process::exit(1);
+        })
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut section = "delta".to_string();
+        for (i, line) in contents.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {}", i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[') {
+                let (table, rest) = parse_key(table).map_err(|msg| error(&msg))?;
+                if !strip_comment(
+                    rest.strip_prefix(']')
+                        .ok_or_else(|| error("expected ']'"))?,
+                )
+                .is_empty()
+                {
+                    return Err(error("unexpected text after table header"));
+                }
+                section = match table.as_str() {
+                    "delta" => table,
+                    _ => format!("delta.{}", table.strip_prefix("delta.").unwrap_or(&table)),
+                };
+                continue;
+            }
+            let (key, rest) = parse_key(line).map_err(|msg| error(&msg))?;
+            let value = rest
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or_else(|| error("expected '='"))?;
+            let value = parse_value(value.trim_start()).map_err(|msg| error(&msg))?;
+            values.insert(format!("{}.{}", section, key), value);
+        }
+        Ok(Self { values })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
+
+/// Parse a key, possibly dotted, at the start of `s` and return it with the remainder of `s`. The
+/// parts of a dotted key are joined with '.', so that [delta."my-preset"] and [delta.my-preset]
+/// are equivalent.
+fn parse_key(s: &str) -> Result<(String, &str), String> {
+    let mut parts = Vec::new();
+    let mut rest = s.trim_start();
+    loop {
+        let (part, after_part) = if rest.starts_with('"') || rest.starts_with('\'') {
+            parse_string(rest)?
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err("expected a key".to_string());
+            }
+            (rest[..end].to_string(), &rest[end..])
+        };
+        parts.push(part);
+        rest = after_part.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after_dot) => rest = after_dot.trim_start(),
+            None => return Ok((parts.join("."), rest)),
+        }
+    }
+}
+
+/// Parse a value, returned as the string that git config would hold for it.
+fn parse_value(s: &str) -> Result<String, String> {
+    let (value, rest) = if s.starts_with('"') || s.starts_with('\'') {
+        parse_string(s)?
+    } else {
+        let end = s
+            .find(|c: char| c.is_whitespace() || c == '#')
+            .unwrap_or(s.len());
+        let value = &s[..end];
+        let number = value.replace('_', "");
+        if !(value == "true"
+            || value == "false"
+            || number.parse::<i64>().is_ok()
+            || number.parse::<f64>().is_ok())
+        {
+            return Err(format!("invalid value: {}", s));
+        }
+        (number, &s[end..])
+    };
+    if !strip_comment(rest).is_empty() {
+        return Err("unexpected text after value".to_string());
+    }
+    Ok(value)
+}
+
+/// Parse a basic ("...") or literal ('...') string at the start of `s` and return it with the
+/// remainder of `s`.
+fn parse_string(s: &str) -> Result<(String, &str), String> {
+    let mut chars = s.char_indices();
+    let quote = chars.next().map(|(_, c)| c);
+    let mut string = String::new();
+    while let Some((i, c)) = chars.next() {
+        if Some(c) == quote {
+            return Ok((string, &s[i + 1..]));
+        } else if c == '\\' && quote == Some('"') {
+            match chars.next() {
+                Some((_, '"')) => string.push('"'),
+                Some((_, '\\')) => string.push('\\'),
+                Some((_, 'n')) => string.push('\n'),
+                Some((_, 't')) => string.push('\t'),
+                Some((_, 'r')) => string.push('\r'),
+                _ => return Err(format!("invalid escape sequence in string: {}", s)),
+            }
+        } else {
+            string.push(c);
+        }
+    }
+    Err(format!("unterminated string: {}", s))
+}
+
+fn strip_comment(s: &str) -> &str {
+    let s = s.trim();
+    if s.starts_with('#') {
+        ""
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{remove_file, File};
+    use std::io::Write;
+    use std::path::Path;
+
+    use super::*;
+    use crate::config;
+    use crate::git_config::GitConfig;
+    use crate::style::Style;
+
+    #[test]
+    fn test_parse() {
+        let config_file = ConfigFile::parse(
+            "
+# Main settings
+number = true
+zero-style = \"dim syntax\"  # a comment
+tabs = 2
+max-line-distance = 0.3
+file-added-label = '[+]'
+file-removed-label = \"\\\"-\\\"\"
+
+[delta]
+width = \"80\"
+
+[my-preset]
+syntax-theme = \"Dracula\"
+
+[delta.\"other-preset\"]
+\"plus-style\" = \"green\"
+",
+        )
+        .unwrap();
+        for (key, value) in &[
+            ("delta.number", "true"),
+            ("delta.zero-style", "dim syntax"),
+            ("delta.tabs", "2"),
+            ("delta.max-line-distance", "0.3"),
+            ("delta.file-added-label", "[+]"),
+            ("delta.file-removed-label", "\"-\""),
+            ("delta.width", "80"),
+            ("delta.my-preset.syntax-theme", "Dracula"),
+            ("delta.other-preset.plus-style", "green"),
+        ] {
+            assert_eq!(config_file.get(key), Some(*value), "{}", key);
+        }
+        assert_eq!(config_file.get("delta.syntax-theme"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for contents in &[
+            "number true",
+            "zero-style = dim syntax",
+            "zero-style = \"dim syntax",
+            "zero-style = \"dim\" syntax",
+            "[my-preset",
+            "= true",
+        ] {
+            assert!(ConfigFile::parse(contents).is_err(), "{}", contents);
+        }
+        assert_eq!(
+            ConfigFile::parse("number = true\nnumber true").err(),
+            Some("line 2: expected '='".to_string())
+        );
+    }
+
+    #[test]
+    fn test_config_file_priority() {
+        let config_file_path = "delta__test_config_file_priority.toml";
+        let git_config_path = "delta__test_config_file_priority.gitconfig";
+        File::create(config_file_path)
+            .unwrap()
+            .write_all(b"minus-style = \"green\"\n\n[my-preset]\nzero-style = \"blue\"\n")
+            .unwrap();
+        File::create(git_config_path)
+            .unwrap()
+            .write_all(b"[delta]\n    minus-style = red\n    plus-style = red\n")
+            .unwrap();
+        let make_config = |args: &[&str]| {
+            let args: Vec<&str> = [
+                "/dev/null",
+                "/dev/null",
+                "--24-bit-color",
+                "always",
+                "--config",
+                config_file_path,
+            ]
+            .iter()
+            .chain(args)
+            .copied()
+            .collect();
+            let mut git_config = Some(GitConfig::from_path(Path::new(git_config_path)));
+            config::Config::from_args(&args, &mut git_config)
+        };
+        let make_style = |s| Style::from_str(s, None, None, None, true, false);
+
+        // The config file takes precedence over git config, and the command line over both.
+        let config = make_config(&[]);
+        assert_eq!(config.minus_style, make_style("green"));
+        assert_eq!(config.plus_style, make_style("red"));
+        let config = make_config(&["--minus-style", "yellow"]);
+        assert_eq!(config.minus_style, make_style("yellow"));
+
+        // Presets can be defined in the config file.
+        let config = make_config(&["--presets", "my-preset"]);
+        assert_eq!(config.zero_style, make_style("blue"));
+
+        // The config file is used even when git config is not.
+        let config = make_config(&["--no-gitconfig"]);
+        assert_eq!(config.minus_style, make_style("green"));
+        assert_ne!(config.plus_style, make_style("red"));
+
+        remove_file(config_file_path).unwrap();
+        remove_file(git_config_path).unwrap();
+    }
+}
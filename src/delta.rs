@@ -1,24 +1,37 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
 use std::io::BufRead;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 
 use bytelines::ByteLines;
 use console::strip_ansi_codes;
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::config::Config;
+use crate::binary;
+use crate::config::{BoxStyle, Config, LineFillMethod, Width};
+use crate::diff_conversion::DiffConverter;
 use crate::draw;
+use crate::html;
 use crate::paint::Painter;
 use crate::parse;
-use crate::style::DecorationStyle;
+use crate::style::{DecorationStyle, Style};
+use crate::utils;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum State {
-    CommitMeta, // In commit metadata section
-    FileMeta,   // In diff metadata section, between (possible) commit metadata and first hunk
-    HunkHeader, // In hunk metadata line
-    HunkZero,   // In hunk; unchanged line
-    HunkMinus,  // In hunk; removed line
-    HunkPlus,   // In hunk; added line
+    CommitMeta,    // In commit metadata section
+    StashHeader,   // In stash entry header line, e.g. stash@{0}: WIP on master
+    FileMeta,      // In diff metadata section, between (possible) commit metadata and first hunk
+    HunkHeader,    // In hunk metadata line
+    HunkZero,      // In hunk; unchanged line
+    HunkMinus,     // In hunk; removed line
+    HunkPlus,      // In hunk; added line
+    Suppressed,    // In a file omitted from the output by --diff-filter
+    MergeConflict, // In merge conflict marker line (under --merged-diff), e.g. <<<<<<< HEAD
+    RebaseTodo,    // In rebase TODO list (under --rebase-mode), e.g. pick 1a2b3c4 Add feature
+    SubmoduleMeta, // In submodule change (under --submodule-diff-output), e.g. Submodule x 1a2..3b4:
     Unknown,
 }
 
@@ -50,75 +63,509 @@ impl State {
 // | HunkMinus   | flush, emit | flush, emit | flush, emit | flush, emit | push        | push     |
 // | HunkPlus    | flush, emit | flush, emit | flush, emit | flush, emit | flush, push | push     |
 
-pub fn delta<I>(
+pub fn delta<I>(lines: ByteLines<I>, writer: &mut dyn Write, config: &Config) -> std::io::Result<()>
+where
+    I: BufRead,
+{
+    delta_with_reload(lines, writer, config, &mut |_| None)
+}
+
+/// As `delta`, but at each line that starts a commit or a file, call `reload_config` with the
+/// preset named by the file's delta-style git attribute, if any (see Config::get_file_preset).
+/// If it returns a new config, the output pending so far is written, and that line and the rest
+/// of the input are displayed with the new config. The --stats totals and the --commit-limit
+/// count carry over to the new config.
+pub fn delta_with_reload<'a, 'c: 'a, I>(
     mut lines: ByteLines<I>,
-    writer: &mut dyn Write,
-    config: &Config,
+    writer: &'a mut dyn Write,
+    config: &'c Config<'c>,
+    reload_config: &mut dyn FnMut(Option<&str>) -> Option<&'c Config<'c>>,
 ) -> std::io::Result<()>
 where
     I: BufRead,
 {
-    let mut painter = Painter::new(writer, config);
-    let mut minus_file = "".to_string();
-    let mut plus_file;
-    let mut state = State::Unknown;
-    let mut source = Source::Unknown;
-
+    let mut config = config;
+    let mut state_machine = StateMachine::new(Box::new(writer), config)?;
     while let Some(Ok(raw_line_bytes)) = lines.next() {
+        let raw_line_bytes = if config.strip_trailing_cr {
+            strip_trailing_cr(raw_line_bytes)
+        } else {
+            Cow::Borrowed(raw_line_bytes)
+        };
+        let raw_line_bytes = &*raw_line_bytes;
+        if let Some(line) = get_commit_or_file_start_line(raw_line_bytes) {
+            if let Some(new_config) = reload_config(config.get_file_preset(&line).as_deref()) {
+                // The attribute values already looked up remain valid.
+                new_config
+                    .delta_style_attributes
+                    .replace(config.delta_style_attributes.take());
+                state_machine.set_config(new_config)?;
+                config = new_config;
+            }
+        }
+        if !state_machine.handle_line(raw_line_bytes)? {
+            break;
+        }
+    }
+    state_machine.finish()
+}
+
+/// If the line is a conflict marker of --merged-diff, i.e. a run of exactly `marker_size` of one
+/// of the characters '<', '|', '=' or '>', followed by a space (and e.g. a branch name) or the
+/// end of the line, return that character.
+fn get_conflict_marker(line: &str, marker_size: usize) -> Option<char> {
+    let marker = line.chars().next().filter(|c| "<|=>".contains(*c))?;
+    let run_length = line.chars().take_while(|c| *c == marker).count();
+    let rest = &line[run_length..];
+    if run_length == marker_size && (rest.is_empty() || rest.starts_with(' ')) {
+        Some(marker)
+    } else {
+        None
+    }
+}
+
+/// Remove a carriage return from the end of the line, or from before the ANSI escape sequences
+/// that end it in colored input, e.g. "+x\x1b[41m\r\x1b[m". (A carriage return immediately
+/// before the newline has already been removed by ByteLines.)
+fn strip_trailing_cr(raw_line_bytes: &[u8]) -> Cow<'_, [u8]> {
+    match raw_line_bytes.iter().rposition(|byte| *byte == b'\r') {
+        Some(i) if i + 1 == raw_line_bytes.len() => Cow::Borrowed(&raw_line_bytes[..i]),
+        Some(i)
+            if strip_ansi_codes(&String::from_utf8_lossy(&raw_line_bytes[i + 1..])).is_empty() =>
+        {
+            Cow::Owned([&raw_line_bytes[..i], &raw_line_bytes[i + 1..]].concat())
+        }
+        _ => Cow::Borrowed(raw_line_bytes),
+    }
+}
+
+/// If the line starts a commit or a file, return it, without ANSI escape sequences.
+fn get_commit_or_file_start_line(raw_line_bytes: &[u8]) -> Option<String> {
+    let raw_line = String::from_utf8_lossy(raw_line_bytes);
+    let line = strip_ansi_codes(&raw_line);
+    if line.starts_with("commit ") || line.starts_with("diff ") {
+        Some(line.to_string())
+    } else {
+        None
+    }
+}
+
+/// The state of delta while it processes its input. Each line of input is passed to handle_line,
+/// which writes any output that the line completes to the painter's writer.
+pub struct StateMachine<'a> {
+    painter: Painter<'a>,
+    config: &'a Config<'a>,
+    minus_file: String,
+    plus_file: String,
+    state: State,
+    source: Source,
+    split_output: Option<SplitOutput>,
+    // Under --collapse-renames, a rename is held here until it is known whether the file's
+    // content also changed, i.e. whether the rename is followed by "---"/"+++" lines.
+    pending_rename: Option<(String, String)>,
+    // Under --commit-format, the commit header block is collected here and written when it ends.
+    pending_commit_header: Option<parse::CommitHeader>,
+    // Under --commit-style-by-author-regexp, the commit line (and raw line) is held here, with
+    // any "Merge:" lines following it, until the "Author:" line determines its style.
+    pending_commit_line: Option<(String, String, Vec<String>)>,
+    // Under --show-rename-score, the similarity score of the current file, if it was renamed.
+    rename_score: Option<String>,
+    // Whether the current file is a copy of another ("copy from" in the file metadata).
+    is_copy: bool,
+    // Under --binary-hex-dump-max-bytes, the lines of a "GIT binary patch" section are collected
+    // here and the file contents are displayed when the section ends.
+    pending_binary_patch: Option<binary::BinaryPatch>,
+    diff_line: String,
+    // Under --hunk-header-always, hunk lines that follow the file header without a hunk header
+    // are collected here, so that a hunk header can be reconstructed from them.
+    pending_headerless_hunk: Option<Vec<(String, String)>>,
+    // Under --highlight-copied-lines, the lines of the current hunk are collected here, so that
+    // the lines moved within the hunk are known before it is painted.
+    pending_hunk: Option<Vec<(String, String)>>,
+    commit_count: usize,
+    // Whether the current line may belong to a diff stat block, i.e. a run of diff stat lines
+    // at the start of the input or following a blank line or a "---" line.
+    is_in_diff_stat: bool,
+    // Whether the current commit is omitted from the output by --ignore-rev.
+    is_in_ignored_commit: bool,
+    // The totals displayed under --stats. A file is counted at its "diff" line or, for diff -u
+    // output without "diff" lines, at its "+++" line.
+    stats: Stats,
+    is_file_counted: bool,
+    // Under --merged-diff, the side of the conflict that the current line belongs to.
+    merge_conflict_side: Option<MergeConflictSide>,
+    is_first_line: bool,
+    // Under --diff-mode=context or --diff-mode=normal, the conversion of the input lines to
+    // unified diff lines.
+    diff_converter: Option<DiffConverter>,
+}
+
+impl<'a> StateMachine<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>, config: &'a Config) -> std::io::Result<Self> {
+        let mut painter = Painter::new(writer, config);
+        let (state, split_output) = if config.rebase_mode {
+            (State::RebaseTodo, None)
+        } else if config.merged_diff {
+            painter.minus_line_number = 1;
+            painter.plus_line_number = 1;
+            (State::HunkZero, None)
+        } else {
+            let split_output = match &config.diff_split_output {
+                Some(prefix) => Some(SplitOutput::create(prefix)?),
+                None => None,
+            };
+            (State::Unknown, split_output)
+        };
+        Ok(Self {
+            painter,
+            config,
+            minus_file: "".to_string(),
+            plus_file: "".to_string(),
+            state,
+            source: Source::Unknown,
+            split_output,
+            pending_rename: None,
+            pending_commit_header: None,
+            pending_commit_line: None,
+            rename_score: None,
+            is_copy: false,
+            pending_binary_patch: None,
+            diff_line: String::new(),
+            pending_headerless_hunk: None,
+            pending_hunk: None,
+            commit_count: 0,
+            is_in_diff_stat: true,
+            is_in_ignored_commit: false,
+            stats: Stats::default(),
+            is_file_counted: false,
+            merge_conflict_side: None,
+            is_first_line: true,
+            diff_converter: DiffConverter::new(config.diff_mode),
+        })
+    }
+
+    /// Process one line of input. Return false if the remaining input is to be ignored.
+    pub fn handle_line(&mut self, raw_line_bytes: &[u8]) -> std::io::Result<bool> {
+        if self.config.rebase_mode {
+            self.handle_rebase_todo_line(raw_line_bytes)?;
+            return Ok(true);
+        }
+        if self.config.merged_diff {
+            self.handle_merged_diff_line(raw_line_bytes)?;
+            return Ok(true);
+        }
+        if let Some(mut diff_converter) = self.diff_converter.take() {
+            let raw_line = String::from_utf8_lossy(raw_line_bytes);
+            let mut result = Ok(true);
+            for line in diff_converter.push_line(&strip_ansi_codes(&raw_line)) {
+                result = self.handle_line(line.as_bytes());
+                if !matches!(result, Ok(true)) {
+                    break;
+                }
+            }
+            self.diff_converter = Some(diff_converter);
+            return result;
+        }
+        let config = self.config;
         let raw_line = String::from_utf8_lossy(&raw_line_bytes);
         let line = strip_ansi_codes(&raw_line).to_string();
-        if source == Source::Unknown {
-            source = detect_source(&line);
+        if self.is_in_ignored_commit && !line.starts_with("commit ") {
+            return Ok(true);
+        }
+        if self.source == Source::Unknown {
+            self.source = detect_source(&line);
+        }
+        if let Some((old_path, new_path)) = self.pending_rename.take() {
+            if line.starts_with("index ") {
+                self.pending_rename = Some((old_path, new_path));
+            } else if !line.starts_with("--- ") {
+                self.painter.emit()?;
+                handle_collapsed_rename_line(
+                    &mut self.painter,
+                    &old_path,
+                    &new_path,
+                    self.rename_score.as_deref(),
+                    config,
+                )?;
+            }
+        }
+        if let Some(commit_header) = self.pending_commit_header.as_mut() {
+            if !line.starts_with("commit ") && commit_header.push_line(&line) {
+                return Ok(true);
+            }
+        }
+        if let Some(commit_header) = self.pending_commit_header.take() {
+            handle_formatted_commit_header(&mut self.painter, &commit_header, config)?;
+        }
+        if let Some((_, _, merge_lines)) = self.pending_commit_line.as_mut() {
+            if line.starts_with("Merge:") {
+                merge_lines.push(raw_line.to_string());
+                return Ok(true);
+            }
+        }
+        if let Some((commit_line, commit_raw_line, merge_lines)) = self.pending_commit_line.take() {
+            let style = match line.strip_prefix("Author:") {
+                Some(author) => config.get_commit_style(author.trim()),
+                None => config.commit_style,
+            };
+            _handle_commit_meta_header_line(
+                &mut self.painter,
+                &commit_line,
+                &commit_raw_line,
+                style,
+                config,
+            )?;
+            for merge_line in merge_lines {
+                writeln!(self.painter.writer, "{}", merge_line)?;
+            }
+        }
+        if let Some(binary_patch) = self.pending_binary_patch.as_mut() {
+            let is_binary_patch_line = binary_patch.push_line(&line);
+            if is_binary_patch_line && !binary_patch.is_complete() {
+                return Ok(true);
+            }
+            if let Some(binary_patch) = self.pending_binary_patch.take() {
+                self.painter.emit()?;
+                handle_binary_patch(&mut self.painter, &binary_patch, config)?;
+            }
+            if is_binary_patch_line {
+                return Ok(true);
+            }
+        }
+        if let Some(hunk_lines) = self.pending_headerless_hunk.as_mut() {
+            if parse::is_headerless_hunk_line(&line) {
+                self.stats.add_hunk_line(&line);
+                hunk_lines.push((line, raw_line.to_string()));
+                return Ok(true);
+            }
+        }
+        if let Some(hunk_lines) = self.pending_headerless_hunk.take() {
+            self.painter.emit()?;
+            self.state = handle_headerless_hunk(
+                &mut self.painter,
+                &hunk_lines,
+                get_file_path(&self.minus_file, &self.plus_file),
+                self.split_output.as_mut(),
+                config,
+            )?;
+        }
+        // Any line other than a hunk line ends the hunk. The whole hunk is buffered, so that
+        // lines moved any distance within it are recognized.
+        if self.pending_hunk.is_some()
+            && !(self.state.is_in_hunk() && parse::is_headerless_hunk_line(&line))
+        {
+            self.handle_pending_hunk()?;
+        }
+        if self.state == State::Suppressed
+            && !(line.starts_with("diff ")
+                || line.starts_with("commit ")
+                || self.source == Source::DiffUnified
+                    && (line.starts_with("--- ") || line.starts_with("Only in ")))
+        {
+            return Ok(true);
+        }
+        // With --commit-style none, the whole commit header is omitted, up to the first file.
+        if self.state == State::CommitMeta
+            && config.commit_style.is_omitted
+            && !(line.starts_with("diff ") || line.starts_with("commit "))
+        {
+            return Ok(true);
+        }
+        if self.state == State::CommitMeta || self.state == State::Unknown {
+            let is_diff_stat_line = self.is_in_diff_stat && parse::is_diff_stat_line(&line);
+            self.is_in_diff_stat = is_diff_stat_line || line.is_empty() || line == "---";
+            if let Some(diff_stat_line) = is_diff_stat_line
+                .then(|| {
+                    parse::rewrite_diff_stat_rename_line(&raw_line, config.diff_stat_rename_format)
+                })
+                .flatten()
+            {
+                self.painter.emit()?;
+                writeln!(self.painter.writer, "{}", diff_stat_line)?;
+                return Ok(true);
+            }
         }
         if line.starts_with("commit ") {
-            painter.paint_buffered_lines();
-            state = State::CommitMeta;
-            if should_handle(&state, config) {
-                painter.emit()?;
-                handle_commit_meta_header_line(&mut painter, &line, &raw_line, config)?;
-                continue;
+            self.is_in_ignored_commit = is_ignored_commit_line(&line, config);
+            if self.is_in_ignored_commit {
+                self.painter.paint_buffered_lines();
+                self.painter.emit()?;
+                return Ok(true);
+            }
+            self.commit_count += 1;
+            if config
+                .commit_limit
+                .map_or(false, |limit| self.commit_count > limit)
+            {
+                // The output of the last commit is completed by finish.
+                return Ok(false);
+            }
+            self.painter.paint_buffered_lines();
+            self.state = State::CommitMeta;
+            if config.commit_format.is_some() {
+                self.painter.emit()?;
+                self.pending_commit_header = Some(parse::CommitHeader::new(&line));
+                return Ok(true);
+            }
+            if should_handle(&self.state, config) {
+                self.painter.emit()?;
+                if !config.commit_style_by_author.is_empty() {
+                    self.pending_commit_line = Some((line, raw_line.to_string(), Vec::new()));
+                    return Ok(true);
+                }
+                handle_commit_meta_header_line(&mut self.painter, &line, &raw_line, config)?;
+                return Ok(true);
+            }
+        } else if let Some((stash_ref, message)) = parse::parse_stash_header_line(&line) {
+            // Lines that merely look like a stash header fall through to the other cases.
+            self.painter.paint_buffered_lines();
+            self.state = State::StashHeader;
+            if should_handle(&self.state, config) {
+                self.painter.emit()?;
+                handle_stash_header_line(&mut self.painter, stash_ref, message, &raw_line, config)?;
+                return Ok(true);
             }
         } else if line.starts_with("diff ") {
-            painter.paint_buffered_lines();
-            state = State::FileMeta;
-        } else if (state == State::FileMeta || source == Source::DiffUnified)
+            self.painter.paint_buffered_lines();
+            self.state = State::FileMeta;
+            self.painter.minus_file_is_null = false;
+            self.painter.plus_file_is_null = false;
+            self.painter.file_hunk_bytes = 0;
+            self.rename_score = None;
+            self.is_copy = false;
+            self.diff_line = line.clone();
+            // A renamed file is checked against --diff-filter at "rename to".
+            if parse::get_file_path_from_diff_git_line(&line)
+                .map_or(false, |path| config.is_file_filtered_out(path))
+            {
+                self.state = State::Suppressed;
+                return Ok(true);
+            }
+            self.stats.files_changed += 1;
+            self.is_file_counted = true;
+        } else if self.state == State::FileMeta
+            && config.show_rename_score
+            && line.starts_with("similarity index ")
+        {
+            self.rename_score = parse::parse_similarity_index_line(&line).map(str::to_string);
+        } else if self.state == State::FileMeta && line.starts_with("copy from ") {
+            self.is_copy = true;
+        } else if (self.state == State::FileMeta || self.source == Source::DiffUnified)
             && (line.starts_with("--- ") || line.starts_with("rename from "))
             && should_handle(&State::FileMeta, config)
         {
-            minus_file = parse::get_file_path_from_file_meta_line(&line, source == Source::GitDiff);
-            if source == Source::DiffUnified {
-                state = State::FileMeta;
-                painter.set_syntax(parse::get_file_extension_from_marker_line(&line));
+            self.minus_file =
+                parse::get_file_path_from_file_meta_line(&line, self.source == Source::GitDiff);
+            self.painter.minus_file_is_null = self.minus_file == "/dev/null";
+            self.painter.file_hunk_bytes = 0;
+            if self.source == Source::DiffUnified {
+                self.state = State::FileMeta;
+                self.painter
+                    .set_syntax(parse::get_file_extension_from_marker_line(&line));
             } else {
-                state = State::FileMeta;
-                painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
-                    &minus_file,
-                ));
+                self.state = State::FileMeta;
+                self.painter
+                    .set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+                        &self.minus_file,
+                    ));
             }
         } else if (line.starts_with("+++ ") || line.starts_with("rename to "))
             && should_handle(&State::FileMeta, config)
         {
-            plus_file = parse::get_file_path_from_file_meta_line(&line, source == Source::GitDiff);
-            painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
-                &plus_file,
+            self.plus_file =
+                parse::get_file_path_from_file_meta_line(&line, self.source == Source::GitDiff);
+            if config.is_file_filtered_out(get_file_path(&self.minus_file, &self.plus_file)) {
+                self.state = State::Suppressed;
+                return Ok(true);
+            }
+            if line.starts_with("+++ ") {
+                if !self.is_file_counted {
+                    self.stats.files_changed += 1;
+                }
+                self.is_file_counted = false;
+            }
+            self.painter.plus_file_is_null = self.plus_file == "/dev/null";
+            self.painter
+                .set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+                    &self.plus_file,
+                ));
+            if config.collapse_renames && line.starts_with("rename to ") {
+                self.pending_rename = Some((self.minus_file.clone(), self.plus_file.clone()));
+            } else {
+                self.painter.emit()?;
+                handle_file_meta_header_line(
+                    &mut self.painter,
+                    &self.minus_file,
+                    &self.plus_file,
+                    self.rename_score.as_deref(),
+                    self.is_copy,
+                    config,
+                    self.source == Source::DiffUnified,
+                )?;
+            }
+        } else if self.state == State::FileMeta
+            && line == "GIT binary patch"
+            && config.binary_hex_dump_max_bytes.is_some()
+            && should_handle(&State::FileMeta, config)
+        {
+            // A binary file that was renamed already has a file header, written at "rename to".
+            self.pending_binary_patch = Some(binary::BinaryPatch::new(
+                parse::get_file_path_from_diff_git_line(&self.diff_line).map(str::to_string),
             ));
-            painter.emit()?;
-            handle_file_meta_header_line(
-                &mut painter,
-                &minus_file,
-                &plus_file,
-                config,
-                source == Source::DiffUnified,
-            )?;
+            return Ok(true);
+        } else if self.state == State::FileMeta
+            && config.hunk_header_always
+            && parse::is_headerless_hunk_line(&line)
+        {
+            // No hunk header has been seen since the file header.
+            self.stats.add_hunk_line(&line);
+            self.pending_headerless_hunk = Some(vec![(line, raw_line.to_string())]);
+            return Ok(true);
         } else if line.starts_with("@@") {
-            state = State::HunkHeader;
-            painter.set_highlighter();
-            if should_handle(&state, config) {
-                painter.emit()?;
-                handle_hunk_header_line(&mut painter, &line, &raw_line, config)?;
-                continue;
+            self.state = State::HunkHeader;
+            if config.diff_header_only {
+                return Ok(true);
+            }
+            self.painter.paint_buffered_lines();
+            self.painter.hunk_has_painted_minus_lines = false;
+            self.painter.hunk_has_painted_plus_lines = false;
+            self.painter.set_highlighter();
+            if should_handle(&self.state, config) {
+                self.painter.emit()?;
+                handle_hunk_header_line(
+                    &mut self.painter,
+                    &line,
+                    &raw_line,
+                    get_file_path(&self.minus_file, &self.plus_file),
+                    config,
+                )?;
+                return Ok(true);
+            }
+        } else if let Some((path, range, suffix)) =
+            parse::parse_submodule_header_line(&line).filter(|_| config.submodule_diff_output)
+        {
+            self.painter.paint_buffered_lines();
+            self.state = State::SubmoduleMeta;
+            self.painter.emit()?;
+            handle_submodule_header_line(&mut self.painter, path, range, suffix, config)?;
+            return Ok(true);
+        } else if self.state == State::SubmoduleMeta
+            && (line.starts_with("  > ") || line.starts_with("  < "))
+        {
+            // A commit summary line, e.g. "  > Fix the build".
+            self.painter.emit()?;
+            let style = config.hunk_header_style;
+            if style.is_raw {
+                writeln!(self.painter.writer, "{}", raw_line)?;
+            } else if !style.is_omitted {
+                writeln!(
+                    self.painter.writer,
+                    "{}",
+                    style.ansi_term_style.paint(&line)
+                )?;
             }
-        } else if source == Source::DiffUnified && line.starts_with("Only in ")
+            return Ok(true);
+        } else if self.source == Source::DiffUnified && line.starts_with("Only in ")
             || line.starts_with("Submodule ")
             || line.starts_with("Binary files ")
         {
@@ -135,33 +582,405 @@ where
             // See https://github.com/dandavison/delta/issues/60#issuecomment-557485242 for a
             // proposal for more robust parsing logic.
 
-            state = State::FileMeta;
-            painter.paint_buffered_lines();
+            self.state = State::FileMeta;
+            self.painter.paint_buffered_lines();
             if should_handle(&State::FileMeta, config) {
-                painter.emit()?;
-                handle_generic_file_meta_header_line(&mut painter, &line, &raw_line, config)?;
-                continue;
+                self.painter.emit()?;
+                handle_generic_file_meta_header_line(&mut self.painter, &line, &raw_line, config)?;
+                return Ok(true);
+            }
+        } else if self.state.is_in_hunk() {
+            if let Some(split_output) = self.split_output.as_mut() {
+                split_output.write_hunk_line(&line)?;
+            }
+            self.painter.file_hunk_bytes += line.len() as u64;
+            self.stats.add_hunk_line(&line);
+            if config.diff_header_only {
+                return Ok(true);
+            }
+            if config.highlight_copied_lines {
+                self.pending_hunk
+                    .get_or_insert_with(Vec::new)
+                    .push((line, raw_line.to_string()));
+                return Ok(true);
             }
-        } else if state.is_in_hunk() {
             // A true hunk line should start with one of: '+', '-', ' '. However, handle_hunk_line
             // handles all lines until the state machine transitions away from the hunk states.
-            state = handle_hunk_line(&mut painter, &line, &raw_line, state, config);
-            painter.emit()?;
-            continue;
+            self.state = handle_hunk_line(
+                &mut self.painter,
+                &line,
+                &raw_line,
+                self.state.clone(),
+                config,
+            );
+            self.painter.emit()?;
+            return Ok(true);
         }
 
-        if state == State::FileMeta && should_handle(&State::FileMeta, config) {
+        if self.state == State::FileMeta && should_handle(&State::FileMeta, config) {
             // The file metadata section is 4 lines. Skip them under non-plain file-styles.
-            continue;
+            return Ok(true);
         } else {
-            painter.emit()?;
-            writeln!(painter.writer, "{}", raw_line)?;
+            self.painter.emit()?;
+            writeln!(self.painter.writer, "{}", raw_line)?;
         }
+        Ok(true)
     }
 
-    painter.paint_buffered_lines();
-    painter.emit()?;
-    Ok(())
+    /// Display the rest of the input with `config`. The output pending so far is written with the
+    /// current config first.
+    pub fn set_config(&mut self, config: &'a Config<'a>) -> std::io::Result<()> {
+        if !self.config.rebase_mode {
+            self.write_pending_output()?;
+        }
+        self.config = config;
+        self.painter.set_config(config);
+        Ok(())
+    }
+
+    /// Write the output that is still pending at the end of the input.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        let config = self.config;
+        if config.rebase_mode {
+            return Ok(());
+        }
+        if let Some(mut diff_converter) = self.diff_converter.take() {
+            for line in diff_converter.finish() {
+                if !self.handle_line(line.as_bytes())? {
+                    break;
+                }
+            }
+        }
+        self.write_pending_output()?;
+        if config.merged_diff {
+            return Ok(());
+        }
+        if let Some(split_output) = self.split_output.as_mut() {
+            split_output.flush()?;
+        }
+        if config.show_stats {
+            writeln!(self.painter.writer)?;
+            let summary = self.stats.format_summary();
+            draw::write_no_decoration(
+                &mut self.painter.writer,
+                &summary,
+                &summary,
+                &config.decorations_width,
+                config.commit_style,
+                ansi_term::Style::new(),
+                config.box_drawing_character_set,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered lines and any other output still held back, pending input that did not
+    /// arrive.
+    fn write_pending_output(&mut self) -> std::io::Result<()> {
+        let config = self.config;
+        if config.merged_diff {
+            self.painter.paint_buffered_lines();
+            return self.painter.emit();
+        }
+        self.handle_pending_hunk()?;
+        if let Some(hunk_lines) = self.pending_headerless_hunk.take() {
+            self.painter.emit()?;
+            handle_headerless_hunk(
+                &mut self.painter,
+                &hunk_lines,
+                get_file_path(&self.minus_file, &self.plus_file),
+                self.split_output.as_mut(),
+                config,
+            )?;
+        }
+        self.painter.paint_buffered_lines();
+        self.painter.emit()?;
+        if let Some((old_path, new_path)) = self.pending_rename.take() {
+            handle_collapsed_rename_line(
+                &mut self.painter,
+                &old_path,
+                &new_path,
+                self.rename_score.as_deref(),
+                config,
+            )?;
+        }
+        if let Some(commit_header) = self.pending_commit_header.take() {
+            handle_formatted_commit_header(&mut self.painter, &commit_header, config)?;
+        }
+        if let Some((commit_line, commit_raw_line, merge_lines)) = self.pending_commit_line.take() {
+            handle_commit_meta_header_line(
+                &mut self.painter,
+                &commit_line,
+                &commit_raw_line,
+                config,
+            )?;
+            for merge_line in merge_lines {
+                writeln!(self.painter.writer, "{}", merge_line)?;
+            }
+        }
+        if let Some(binary_patch) = self.pending_binary_patch.take() {
+            handle_binary_patch(&mut self.painter, &binary_patch, config)?;
+        }
+        Ok(())
+    }
+
+    /// Paint the hunk lines collected under --highlight-copied-lines, with the lines that occur
+    /// both as removed and as added lines of the hunk painted as moved lines.
+    fn handle_pending_hunk(&mut self) -> std::io::Result<()> {
+        let config = self.config;
+        let hunk_lines = match self.pending_hunk.take() {
+            Some(hunk_lines) => hunk_lines,
+            None => return Ok(()),
+        };
+        // The number of times each line occurs as a removed and as an added line.
+        let mut occurrences: HashMap<String, (usize, usize)> = HashMap::new();
+        for (line, _) in &hunk_lines {
+            let is_minus = match line.chars().next() {
+                Some('-') => true,
+                Some('+') => false,
+                _ => continue,
+            };
+            if line[1..].trim().is_empty() {
+                continue;
+            }
+            let counts = occurrences
+                .entry(prepare(line, true, config))
+                .or_insert((0, 0));
+            if is_minus {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+        self.painter.moved_lines = occurrences
+            .into_iter()
+            .filter(|(_, (n_minus, n_plus))| *n_minus > 0 && *n_plus > 0)
+            .map(|(line, _)| line)
+            .collect();
+        for (line, raw_line) in &hunk_lines {
+            self.state = handle_hunk_line(
+                &mut self.painter,
+                line,
+                raw_line,
+                self.state.clone(),
+                config,
+            );
+        }
+        self.painter.paint_buffered_lines();
+        self.painter.moved_lines.clear();
+        self.painter.emit()
+    }
+
+    /// Process one line of a file containing merge conflict markers, displayed as a diff
+    /// (--merged-diff). In each conflict, the lines of our side ("<<<<<<<" to "=======") are
+    /// displayed as removed lines and the lines of their side ("=======" to ">>>>>>>") as added
+    /// lines, so that they receive syntax highlighting and within-line edit highlighting. The
+    /// common ancestor lines of a diff3-style conflict ("|||||||" to "=======") are omitted. Lines
+    /// outside conflicts are displayed as unchanged lines.
+    fn handle_merged_diff_line(&mut self, raw_line_bytes: &[u8]) -> std::io::Result<()> {
+        let config = self.config;
+        let raw_line = String::from_utf8_lossy(raw_line_bytes);
+        let line = strip_ansi_codes(&raw_line).to_string();
+        if self.is_first_line {
+            // When the file is read from standard input, its name is not known, and the language
+            // can only be inferred from the first line, e.g. a shebang line.
+            match config
+                .minus_file
+                .as_ref()
+                .and_then(|path| path.extension())
+                .and_then(|extension| extension.to_str())
+            {
+                Some(extension) => self.painter.set_syntax(Some(extension)),
+                None if config.language.is_some() => self.painter.set_syntax(None),
+                None => {
+                    if let Some(syntax) = config.syntax_set.find_syntax_by_first_line(&line) {
+                        self.painter.syntax = syntax;
+                    }
+                }
+            }
+            self.painter.set_highlighter();
+            self.is_first_line = false;
+        }
+        let marker = get_conflict_marker(&line, config.conflict_marker_size);
+        let (next_side, is_displayed_marker) = match (&self.merge_conflict_side, marker) {
+            (None, Some('<')) => (Some(MergeConflictSide::Ours), true),
+            (Some(MergeConflictSide::Ours), Some('|')) => (Some(MergeConflictSide::Base), false),
+            (Some(MergeConflictSide::Ours), Some('='))
+            | (Some(MergeConflictSide::Base), Some('=')) => {
+                (Some(MergeConflictSide::Theirs), false)
+            }
+            (Some(MergeConflictSide::Theirs), Some('>')) => (None, true),
+            _ => {
+                let marker = match self.merge_conflict_side {
+                    None => ' ',
+                    Some(MergeConflictSide::Ours) => '-',
+                    Some(MergeConflictSide::Base) => return Ok(()),
+                    Some(MergeConflictSide::Theirs) => '+',
+                };
+                self.state = handle_hunk_line(
+                    &mut self.painter,
+                    &format!("{}{}", marker, line),
+                    &format!("{}{}", marker, raw_line),
+                    self.state.clone(),
+                    config,
+                );
+                return self.painter.emit();
+            }
+        };
+        self.merge_conflict_side = next_side;
+        if is_displayed_marker {
+            self.painter.paint_buffered_lines();
+            self.painter.emit()?;
+            self.state = State::MergeConflict;
+            handle_merge_conflict_marker_line(&mut self.painter, &line, &raw_line, config)?;
+        }
+        Ok(())
+    }
+
+    /// Under --rebase-mode, write a line of a rebase TODO list with its action word in the style
+    /// of the action. Comment lines are written in the zero style, and other lines unchanged.
+    fn handle_rebase_todo_line(&mut self, raw_line_bytes: &[u8]) -> std::io::Result<()> {
+        let config = self.config;
+        let raw_line = String::from_utf8_lossy(raw_line_bytes);
+        let line = strip_ansi_codes(&raw_line).to_string();
+        if line.trim_start().starts_with('#') {
+            return writeln!(
+                self.painter.writer,
+                "{}",
+                config.zero_style.ansi_term_style.paint(line)
+            );
+        }
+        let trimmed_line = line.trim_start();
+        let indentation = &line[..line.len() - trimmed_line.len()];
+        let word_end = trimmed_line
+            .find(char::is_whitespace)
+            .unwrap_or(trimmed_line.len());
+        let (word, rest) = trimmed_line.split_at(word_end);
+        match config
+            .rebase_action_styles
+            .iter()
+            .find(|(action, _)| word == *action || word == &action[..1])
+        {
+            Some((_, style)) => writeln!(
+                self.painter.writer,
+                "{}{}{}",
+                indentation,
+                style.ansi_term_style.paint(word),
+                rest
+            ),
+            None => writeln!(self.painter.writer, "{}", line),
+        }
+    }
+}
+
+/// The numbers of files changed and of lines added and removed, counted for --stats.
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl Stats {
+    fn add_hunk_line(&mut self, line: &str) {
+        match line.chars().next() {
+            Some('+') => self.insertions += 1,
+            Some('-') => self.deletions += 1,
+            _ => {}
+        }
+    }
+
+    /// Return a summary line in the format used by `git diff --stat`, e.g.
+    /// " 2 files changed, 3 insertions(+), 1 deletion(-)".
+    fn format_summary(&self) -> String {
+        let plural = |n: usize, singular: &str, plural: &str| {
+            format!("{} {}", n, if n == 1 { singular } else { plural })
+        };
+        let mut summary = format!(
+            " {}",
+            plural(self.files_changed, "file changed", "files changed")
+        );
+        // As git does, the insertions and deletions are omitted only if the other is nonzero.
+        if self.insertions > 0 || self.deletions == 0 {
+            summary.push_str(&format!(
+                ", {}",
+                plural(self.insertions, "insertion(+)", "insertions(+)")
+            ));
+        }
+        if self.deletions > 0 || self.insertions == 0 {
+            summary.push_str(&format!(
+                ", {}",
+                plural(self.deletions, "deletion(-)", "deletions(-)")
+            ));
+        }
+        summary
+    }
+}
+
+/// Under --merged-diff, the side of a merge conflict that a line belongs to.
+#[derive(Debug, PartialEq)]
+enum MergeConflictSide {
+    Ours,
+    Base, // The common ancestor, in a diff3-style conflict
+    Theirs,
+}
+
+/// Write a merge conflict marker line, e.g. "<<<<<<< HEAD", in the hunk header style and
+/// decoration.
+fn handle_merge_conflict_marker_line(
+    painter: &mut Painter,
+    line: &str,
+    raw_line: &str,
+    config: &Config,
+) -> std::io::Result<()> {
+    if config.hunk_header_style.is_omitted {
+        return Ok(());
+    }
+    let (draw_fn, decoration_ansi_term_style) = get_hunk_header_draw_fn(config);
+    draw_fn(
+        &mut painter.writer,
+        &format!("{} ", line),
+        &format!("{} ", raw_line),
+        &config.decorations_width,
+        config.hunk_header_style,
+        decoration_ansi_term_style,
+        config.box_drawing_character_set,
+    )
+}
+
+/// The files written under --diff-split-output: PREFIX.old receives the minus side of every hunk
+/// and PREFIX.new the plus side. Unchanged lines belong to both sides.
+struct SplitOutput {
+    old: BufWriter<File>,
+    new: BufWriter<File>,
+}
+
+impl SplitOutput {
+    fn create(prefix: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            old: BufWriter::new(File::create(format!("{}.old", prefix))?),
+            new: BufWriter::new(File::create(format!("{}.new", prefix))?),
+        })
+    }
+
+    /// Write a hunk line, without its -/+/space marker, to the side(s) it belongs to. Lines that
+    /// are not true hunk lines, such as '\ No newline at end of file', are not written.
+    fn write_hunk_line(&mut self, line: &str) -> std::io::Result<()> {
+        match line.chars().next() {
+            Some('-') => writeln!(self.old, "{}", &line[1..]),
+            Some('+') => writeln!(self.new, "{}", &line[1..]),
+            Some(' ') => {
+                writeln!(self.old, "{}", &line[1..])?;
+                writeln!(self.new, "{}", &line[1..])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.old.flush()?;
+        self.new.flush()
+    }
 }
 
 /// Should a handle_* function be called on this element?
@@ -176,7 +995,8 @@ fn should_handle(state: &State, config: &Config) -> bool {
 /// * git diff
 /// * diff -u
 fn detect_source(line: &str) -> Source {
-    if line.starts_with("commit ") || line.starts_with("diff --git ") {
+    if line.starts_with("commit ") || line.starts_with("diff --git ") || line.starts_with("stash@{")
+    {
         Source::GitDiff
     } else if line.starts_with("diff -u ")
         || line.starts_with("diff -U")
@@ -194,12 +1014,65 @@ fn handle_commit_meta_header_line(
     raw_line: &str,
     config: &Config,
 ) -> std::io::Result<()> {
-    if config.commit_style.is_omitted {
+    _handle_commit_meta_header_line(painter, line, raw_line, config.commit_style, config)
+}
+
+/// Write a commit header block using the --commit-format template. The first line of the result
+/// has the commit style and decoration; subsequent lines have the commit style only.
+fn handle_formatted_commit_header(
+    painter: &mut Painter,
+    commit_header: &parse::CommitHeader,
+    config: &Config,
+) -> std::io::Result<()> {
+    let style = config.get_commit_style(&commit_header.author);
+    if style.is_omitted {
+        return Ok(());
+    }
+    let formatted = commit_header.format(config.commit_format.as_deref().unwrap_or(""));
+    let mut lines = formatted.lines();
+    if let Some(first_line) = lines.next() {
+        _handle_commit_meta_header_line(painter, first_line, first_line, style, config)?;
+    }
+    for line in lines {
+        if style.is_raw {
+            writeln!(painter.writer, "{}", line)?;
+        } else {
+            writeln!(painter.writer, "{}", style.ansi_term_style.paint(line))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a stash entry header line, e.g. "stash@{0}: WIP on master: 7417e1c baseline", with
+/// stash styling. The stash decoration is the commit decoration.
+fn handle_stash_header_line(
+    painter: &mut Painter,
+    stash_ref: &str,
+    message: &str,
+    raw_line: &str,
+    config: &Config,
+) -> std::io::Result<()> {
+    let line = if message.is_empty() {
+        stash_ref.to_string()
+    } else {
+        format!("{}: {}", stash_ref, message)
+    };
+    _handle_commit_meta_header_line(painter, &line, raw_line, config.stash_style, config)
+}
+
+fn _handle_commit_meta_header_line(
+    painter: &mut Painter,
+    line: &str,
+    raw_line: &str,
+    style: Style,
+    config: &Config,
+) -> std::io::Result<()> {
+    if style.is_omitted {
         return Ok(());
     }
     let decoration_ansi_term_style;
     let mut pad = false;
-    let draw_fn = match config.commit_style.decoration_style {
+    let draw_fn = match style.decoration_style {
         DecorationStyle::Box(style) => {
             pad = true;
             decoration_ansi_term_style = style;
@@ -238,12 +1111,13 @@ fn handle_commit_meta_header_line(
         }
     };
     draw_fn(
-        painter.writer,
+        &mut painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
         &config.decorations_width,
-        config.commit_style,
+        style,
         decoration_ansi_term_style,
+        config.box_drawing_character_set,
     )?;
     Ok(())
 }
@@ -253,22 +1127,256 @@ fn handle_file_meta_header_line(
     painter: &mut Painter,
     minus_file: &str,
     plus_file: &str,
+    rename_score: Option<&str>,
+    is_copy: bool,
     config: &Config,
     comparing: bool,
 ) -> std::io::Result<()> {
+    let is_rename = !comparing
+        && minus_file != plus_file
+        && minus_file != "/dev/null"
+        && plus_file != "/dev/null";
+    let (minus_file, plus_file) = truncate_file_paths(
+        minus_file,
+        plus_file,
+        rename_score,
+        is_copy,
+        config,
+        comparing,
+    );
+    let (minus_file, plus_file) = if is_rename {
+        (
+            paint_renamed_path(&minus_file, config.file_renamed_old_style, config),
+            paint_renamed_path(&plus_file, config.file_renamed_new_style, config),
+        )
+    } else {
+        (minus_file, plus_file)
+    };
     let line = parse::get_file_change_description_from_file_paths(
-        minus_file, plus_file, comparing, config,
+        &minus_file,
+        &plus_file,
+        comparing,
+        rename_score,
+        is_copy,
+        config,
     );
     // FIXME: no support for 'raw'
     handle_generic_file_meta_header_line(painter, &line, &line, config)
 }
 
+/// Under --file-path-truncation-prefix, shorten the paths in a file header from the start so that
+/// the header, including any box drawn around it, fits within the decoration width. When two
+/// different paths are displayed, the characters to be removed are shared between them in
+/// proportion to their widths.
+fn truncate_file_paths(
+    minus_file: &str,
+    plus_file: &str,
+    rename_score: Option<&str>,
+    is_copy: bool,
+    config: &Config,
+    comparing: bool,
+) -> (String, String) {
+    let (prefix, width) = match (
+        &config.file_path_truncation_prefix,
+        &config.decorations_width,
+    ) {
+        (Some(prefix), Width::Fixed(width)) => (prefix, *width),
+        _ => return (minus_file.to_string(), plus_file.to_string()),
+    };
+    let border_width = match config.file_style.decoration_style {
+        DecorationStyle::Box(_)
+        | DecorationStyle::BoxWithUnderline(_)
+        | DecorationStyle::BoxWithOverline(_)
+        | DecorationStyle::BoxWithUnderOverline(_) => 2,
+        _ => 0,
+    };
+    let line = parse::get_file_change_description_from_file_paths(
+        minus_file,
+        plus_file,
+        comparing,
+        rename_score,
+        is_copy,
+        config,
+    );
+    // Only the last line of a multi-line file label is decorated.
+    let line_width = UnicodeWidthStr::width(line.rsplit('\n').next().unwrap_or(""));
+    let excess = (line_width + border_width).saturating_sub(width);
+    if excess == 0 {
+        return (minus_file.to_string(), plus_file.to_string());
+    }
+    let (minus_width, plus_width) = (
+        UnicodeWidthStr::width(minus_file),
+        UnicodeWidthStr::width(plus_file),
+    );
+    let (minus_excess, plus_excess) = if minus_file == plus_file {
+        (excess, excess)
+    } else if plus_file == "/dev/null" {
+        (excess, 0)
+    } else if minus_file == "/dev/null" {
+        (0, excess)
+    } else {
+        // The excess is split in proportion to the widths, rounding up on the minus side.
+        let width = minus_width + plus_width;
+        let minus_excess = (excess * minus_width + width - 1) / width;
+        (minus_excess, excess - minus_excess)
+    };
+    (
+        truncate_path(minus_file, minus_width.saturating_sub(minus_excess), prefix),
+        truncate_path(plus_file, plus_width.saturating_sub(plus_excess), prefix),
+    )
+}
+
+/// Return `path` shortened to at most `max_width` columns by replacing its start with `prefix`.
+/// The path is returned unchanged if it already fits.
+fn truncate_path(path: &str, max_width: usize, prefix: &str) -> String {
+    if UnicodeWidthStr::width(path) <= max_width {
+        return path.to_string();
+    }
+    let mut budget = max_width.saturating_sub(UnicodeWidthStr::width(prefix));
+    let mut start = path.len();
+    for (i, c) in path.char_indices().rev() {
+        let char_width = UnicodeWidthChar::width(c).unwrap_or(0);
+        if char_width > budget {
+            break;
+        }
+        budget -= char_width;
+        start = i;
+    }
+    format!("{}{}", prefix, &path[start..])
+}
+
+/// Whether `line`, a "commit" line, names a commit whose SHA starts with one of the --ignore-rev
+/// values.
+fn is_ignored_commit_line(line: &str, config: &Config) -> bool {
+    match line.split_whitespace().nth(1) {
+        Some(sha) => {
+            let sha = sha.to_lowercase();
+            config
+                .ignored_commit_prefixes
+                .iter()
+                .any(|prefix| sha.starts_with(prefix.as_str()))
+        }
+        None => false,
+    }
+}
+
+/// Write a rename with no content change as a single line in the file style, without
+/// decoration.
+fn handle_collapsed_rename_line(
+    painter: &mut Painter,
+    old_path: &str,
+    new_path: &str,
+    rename_score: Option<&str>,
+    config: &Config,
+) -> std::io::Result<()> {
+    if config.file_style.is_omitted {
+        return Ok(());
+    }
+    let label = if config.file_renamed_label.is_empty() {
+        "".to_string()
+    } else {
+        format!("{} ", config.file_renamed_label)
+    };
+    let line = format!(
+        "{}{}{} → {}",
+        label,
+        parse::format_rename_score(rename_score),
+        paint_renamed_path(old_path, config.file_renamed_old_style, config),
+        paint_renamed_path(new_path, config.file_renamed_new_style, config)
+    );
+    writeln!(painter.writer)?;
+    draw::write_no_decoration(
+        &mut painter.writer,
+        &line,
+        &line,
+        &config.decorations_width,
+        config.file_style,
+        ansi_term::Style::new(),
+        config.box_drawing_character_set,
+    )
+}
+
+/// Return the old or new path of a renamed file painted in `style`, for inclusion in a file header
+/// painted in the file style. The file style is restored after the path.
+fn paint_renamed_path(path: &str, style: Style, config: &Config) -> String {
+    let file_style = config.file_style.ansi_term_style;
+    if style.ansi_term_style == file_style || config.file_style.is_raw {
+        return path.to_string();
+    }
+    format!(
+        "{}{}{}",
+        file_style.suffix(),
+        style.ansi_term_style.paint(path),
+        file_style.prefix()
+    )
+}
+
+/// Write the old and new contents of a binary file as a hex dump, or a "Binary files differ"
+/// line if they cannot be displayed.
+fn handle_binary_patch(
+    painter: &mut Painter,
+    binary_patch: &binary::BinaryPatch,
+    config: &Config,
+) -> std::io::Result<()> {
+    let max_bytes = config.binary_hex_dump_max_bytes.unwrap_or(0);
+    match binary_patch.get_contents(max_bytes) {
+        Some((old, new)) => {
+            if let Some(file_path) = &binary_patch.file_path {
+                handle_file_meta_header_line(
+                    painter, file_path, file_path, None, false, config, false,
+                )?;
+            }
+            writeln!(painter.writer)?;
+            for line in binary::format_hex_dump(&old, &new, config) {
+                writeln!(painter.writer, "{}", line)?;
+            }
+            Ok(())
+        }
+        None => {
+            let line = match &binary_patch.file_path {
+                Some(file_path) => format!("Binary files a/{0} and b/{0} differ", file_path),
+                None => "Binary files differ".to_string(),
+            };
+            handle_generic_file_meta_header_line(painter, &line, &line, config)
+        }
+    }
+}
+
+/// Write a submodule change line, e.g. "Submodule vendor/lib 1a2b3c4..5d6e7f8:", with the file
+/// decoration. The commit range is painted in the commit style and the rest in the file style.
+fn handle_submodule_header_line(
+    painter: &mut Painter,
+    path: &str,
+    range: &str,
+    suffix: &str,
+    config: &Config,
+) -> std::io::Result<()> {
+    let file_style = config.file_style.ansi_term_style;
+    let line = format!(
+        "{} {}{}",
+        file_style.paint(format!("Submodule {}", path)),
+        config.commit_style.ansi_term_style.paint(range),
+        file_style.paint(suffix)
+    );
+    _handle_generic_file_meta_header_line(painter, &line, &line, config.null_style, config)
+}
+
 /// Write `line` with FileMeta styling.
 fn handle_generic_file_meta_header_line(
     painter: &mut Painter,
     line: &str,
     raw_line: &str,
     config: &Config,
+) -> std::io::Result<()> {
+    _handle_generic_file_meta_header_line(painter, line, raw_line, config.file_style, config)
+}
+
+fn _handle_generic_file_meta_header_line(
+    painter: &mut Painter,
+    line: &str,
+    raw_line: &str,
+    text_style: Style,
+    config: &Config,
 ) -> std::io::Result<()> {
     if config.file_style.is_omitted {
         return Ok(());
@@ -314,107 +1422,161 @@ fn handle_generic_file_meta_header_line(
         }
     };
     writeln!(painter.writer)?;
+    // A file label may span several lines (e.g. --file-added-label='╔═ added ═╗\n║'). The
+    // leading lines are written in the file style and the decoration is applied to the last.
+    let mut leading_lines: Vec<&str> = line.split('\n').collect();
+    let line = leading_lines.pop().unwrap_or("");
+    let raw_line = raw_line.rsplit('\n').next().unwrap_or("");
+    for leading_line in leading_lines {
+        draw::write_no_decoration(
+            &mut painter.writer,
+            leading_line,
+            leading_line,
+            &config.decorations_width,
+            text_style,
+            ansi_term::Style::new(),
+            config.box_drawing_character_set,
+        )?;
+    }
     draw_fn(
-        painter.writer,
+        &mut painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
         &config.decorations_width,
-        config.file_style,
+        text_style,
         decoration_ansi_term_style,
+        config.box_drawing_character_set,
     )?;
     Ok(())
 }
 
+/// The path of the file that the current hunks belong to: the new path, unless the file was
+/// deleted.
+fn get_file_path<'a>(minus_file: &'a str, plus_file: &'a str) -> &'a str {
+    if plus_file == "/dev/null" {
+        minus_file
+    } else {
+        plus_file
+    }
+}
+
+type DrawFunction = fn(
+    &mut dyn Write,
+    &str,
+    &str,
+    &Width,
+    Style,
+    ansi_term::Style,
+    BoxStyle,
+) -> std::io::Result<()>;
+
+/// Return the function that draws a hunk header with its decoration, and the decoration style.
+fn get_hunk_header_draw_fn(config: &Config) -> (DrawFunction, ansi_term::Style) {
+    match config.hunk_header_style.decoration_style {
+        DecorationStyle::Box(style) => (draw::write_boxed, style),
+        DecorationStyle::BoxWithUnderline(style) => (draw::write_boxed_with_underline, style),
+        DecorationStyle::BoxWithOverline(style) => (draw::write_boxed, style), // TODO: not implemented
+        DecorationStyle::BoxWithUnderOverline(style) => (draw::write_boxed, style), // TODO: not implemented
+        DecorationStyle::Underline(style) => (draw::write_underlined, style),
+        DecorationStyle::Overline(style) => (draw::write_overlined, style),
+        DecorationStyle::UnderOverline(style) => (draw::write_underoverlined, style),
+        DecorationStyle::NoDecoration => (draw::write_no_decoration, ansi_term::Style::new()),
+    }
+}
+
 fn handle_hunk_header_line(
     painter: &mut Painter,
     line: &str,
     raw_line: &str,
+    file_path: &str,
     config: &Config,
 ) -> std::io::Result<()> {
     if config.hunk_header_style.is_omitted {
         return Ok(());
     }
-    let decoration_ansi_term_style;
-    let draw_fn = match config.hunk_header_style.decoration_style {
-        DecorationStyle::Box(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_boxed
-        }
-        DecorationStyle::BoxWithUnderline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_boxed_with_underline
-        }
-        DecorationStyle::BoxWithOverline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_boxed // TODO: not implemented
-        }
-        DecorationStyle::BoxWithUnderOverline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_boxed // TODO: not implemented
-        }
-        DecorationStyle::Underline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_underlined
-        }
-        DecorationStyle::Overline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_overlined
-        }
-        DecorationStyle::UnderOverline(style) => {
-            decoration_ansi_term_style = style;
-            draw::write_underoverlined
-        }
-        DecorationStyle::NoDecoration => {
-            decoration_ansi_term_style = ansi_term::Style::new();
-            draw::write_no_decoration
-        }
+    // Under --hunk-header-include-file-path, the hunk header starts with the file path.
+    let file_path_prefix = if config.hunk_header_include_file_path && !file_path.is_empty() {
+        config
+            .file_style
+            .ansi_term_style
+            .paint(file_path)
+            .to_string()
+    } else {
+        "".to_string()
     };
+    let (draw_fn, decoration_ansi_term_style) = get_hunk_header_draw_fn(config);
     let (raw_code_fragment, line_numbers) = parse::parse_hunk_metadata(&line);
     painter.minus_line_number = line_numbers[0];
     painter.plus_line_number = line_numbers[line_numbers.len() - 1];
+    painter.line_number_width = parse::parse_hunk_line_number_ranges(line)
+        .iter()
+        .map(|(start, count)| (start + count).saturating_sub(1).to_string().len())
+        .max()
+        .unwrap_or(1);
     if config.hunk_header_style.is_raw {
         writeln!(painter.writer)?;
         draw_fn(
-            painter.writer,
-            &format!("{} ", line),
-            &format!("{} ", raw_line),
+            &mut painter.writer,
+            &format!(
+                "{}{} ",
+                with_separator(&file_path_prefix),
+                truncate_function_hint(line, raw_code_fragment, config)
+            ),
+            &format!(
+                "{}{} ",
+                with_separator(&file_path_prefix),
+                truncate_function_hint(raw_line, raw_code_fragment, config)
+            ),
             &config.decorations_width,
             config.hunk_header_style,
             decoration_ansi_term_style,
+            config.box_drawing_character_set,
         )?;
     } else {
-        let line = match prepare(raw_code_fragment, false, config) {
+        let code_fragment = truncate_function_hint(raw_code_fragment, raw_code_fragment, config);
+        let line = match prepare(&code_fragment, false, config) {
             s if s.len() > 0 => format!("{} ", s),
             s => s,
         };
         writeln!(painter.writer)?;
-        if !line.is_empty() {
-            let lines = vec![line];
-            let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
-                &lines,
-                &State::HunkHeader,
-                &mut painter.highlighter,
-                &painter.config,
-            );
-            Painter::paint_lines(
-                syntax_style_sections,
-                vec![vec![(config.hunk_header_style, &lines[0])]],
-                vec![None],
-                &mut painter.output_buffer,
-                config,
-                "",
-                config.null_style,
-                config.null_style,
-                Some(false),
-            );
-            painter.output_buffer.pop(); // trim newline
+        if !line.is_empty() || !file_path_prefix.is_empty() {
+            if !line.is_empty() {
+                let lines = vec![line];
+                let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
+                    &lines,
+                    &State::HunkHeader,
+                    &mut painter.highlighter,
+                    &painter.config,
+                );
+                Painter::paint_lines(
+                    syntax_style_sections,
+                    vec![vec![(config.hunk_header_style, &lines[0])]],
+                    vec![None],
+                    0,
+                    &mut painter.output_buffer,
+                    config,
+                    "",
+                    None,
+                    config.null_style,
+                    config.null_style,
+                    Some(LineFillMethod::None),
+                );
+                painter.output_buffer.pop(); // trim newline
+            }
+            // The code fragment starts with a space, which separates it from the file path.
+            let text = if painter.output_buffer.is_empty() {
+                with_separator(&file_path_prefix)
+            } else {
+                format!("{}{}", file_path_prefix, painter.output_buffer)
+            };
             draw_fn(
-                painter.writer,
-                &painter.output_buffer,
-                &painter.output_buffer,
+                &mut painter.writer,
+                &text,
+                &text,
                 &config.decorations_width,
                 config.hunk_header_style,
                 decoration_ansi_term_style,
+                config.box_drawing_character_set,
             )?;
             if !config.hunk_header_style.is_raw {
                 painter.output_buffer.clear()
@@ -422,7 +1584,35 @@ fn handle_hunk_header_line(
         }
     };
 
-    if !config.show_line_numbers {
+    if config.line_numbers_for_hunks_only {
+        let ranges = parse::parse_hunk_line_number_ranges(line);
+        let (minus_start, minus_count) = ranges[0];
+        let (plus_start, plus_count) = ranges[ranges.len() - 1];
+        writeln!(
+            &mut painter.writer,
+            "{}{}{}",
+            config
+                .number_minus_style
+                .ansi_term_style
+                .paint(format_hunk_line_number_range(
+                    &config.number_minus_format,
+                    minus_start,
+                    minus_count
+                )),
+            config
+                .number_separator_style
+                .ansi_term_style
+                .paint(&config.number_separator),
+            config
+                .number_plus_style
+                .ansi_term_style
+                .paint(format_hunk_line_number_range(
+                    &config.number_plus_format,
+                    plus_start,
+                    plus_count
+                )),
+        )?;
+    } else if !config.show_line_numbers {
         let line_number = &format!("{}", painter.plus_line_number);
         match config.hunk_header_style.decoration_ansi_term_style() {
             Some(style) => writeln!(painter.writer, "{}", style.paint(line_number))?,
@@ -432,6 +1622,73 @@ fn handle_hunk_header_line(
     Ok(())
 }
 
+/// Under --hunk-header-function-hint-max-length, return `text`, a hunk header or its trailing
+/// part, possibly containing ANSI escape sequences, with the function hint in `code_fragment`
+/// (which follows the closing "@@" of the header and starts with a space) shortened to the
+/// maximum length, followed by "…".
+fn truncate_function_hint<'a>(text: &'a str, code_fragment: &str, config: &Config) -> Cow<'a, str> {
+    match config.hunk_header_function_hint_max_length {
+        Some(max_length) => {
+            let hint = code_fragment.strip_prefix(' ').unwrap_or(code_fragment);
+            let hint_start = UnicodeWidthStr::width(&*strip_ansi_codes(text))
+                .saturating_sub(UnicodeWidthStr::width(hint));
+            Cow::from(utils::truncate_to_width(text, hint_start + max_length, "…"))
+        }
+        None => Cow::from(text),
+    }
+}
+
+/// Write a hunk that was not preceded by a hunk header (--hunk-header-always), starting with a
+/// hunk header reconstructed from its lines. Return the state after the last hunk line.
+fn handle_headerless_hunk(
+    painter: &mut Painter,
+    hunk_lines: &[(String, String)],
+    file_path: &str,
+    mut split_output: Option<&mut SplitOutput>,
+    config: &Config,
+) -> std::io::Result<State> {
+    let mut state = State::HunkHeader;
+    if !config.diff_header_only {
+        let lines: Vec<&str> = hunk_lines.iter().map(|(line, _)| line.as_str()).collect();
+        let hunk_header = parse::make_hunk_header(&lines);
+        painter.set_highlighter();
+        if should_handle(&state, config) {
+            handle_hunk_header_line(painter, &hunk_header, &hunk_header, file_path, config)?;
+        } else {
+            writeln!(painter.writer, "{}", hunk_header)?;
+        }
+    }
+    for (line, raw_line) in hunk_lines {
+        if let Some(split_output) = split_output.as_mut() {
+            split_output.write_hunk_line(line)?;
+        }
+        painter.file_hunk_bytes += line.len() as u64;
+        if config.diff_header_only {
+            continue;
+        }
+        state = handle_hunk_line(painter, line, raw_line, state, config);
+        painter.emit()?;
+    }
+    Ok(state)
+}
+
+/// Follow `text` with a space, unless it is empty.
+fn with_separator(text: &str) -> String {
+    if text.is_empty() {
+        "".to_string()
+    } else {
+        format!("{} ", text)
+    }
+}
+
+/// Format a hunk's line number range for --line-numbers-for-hunks-only.
+fn format_hunk_line_number_range(number_format: &str, start: usize, count: usize) -> String {
+    number_format
+        .replace("%ln", "{start},{count}")
+        .replace("{start}", &start.to_string())
+        .replace("{count}", &count.to_string())
+}
+
 /// Handle a hunk line, i.e. a minus line, a plus line, or an unchanged line.
 // In the case of a minus or plus line, we store the line in a
 // buffer. When we exit the changed region we process the collected
@@ -453,48 +1710,65 @@ fn handle_hunk_line(
     {
         painter.paint_buffered_lines();
     }
+    let is_blank_zero_line =
+        config.empty_line_context.is_some() && line.starts_with(' ') && line[1..].trim().is_empty();
+    if !is_blank_zero_line {
+        painter.paint_blank_zero_lines();
+    }
+    let color_moved_style =
+        if config.color_moved && (line.starts_with('-') || line.starts_with('+')) {
+            let color_moved_style = get_color_moved_style(raw_line, config);
+            // A block of moved lines is not paired with the lines before or after it.
+            let previous_style = painter
+                .plus_lines_color_moved_styles
+                .last()
+                .or_else(|| painter.minus_lines_color_moved_styles.last());
+            if previous_style.map_or(false, |previous_style| *previous_style != color_moved_style) {
+                painter.paint_buffered_lines();
+            }
+            Some(color_moved_style)
+        } else {
+            None
+        };
     match line.chars().next() {
         Some('-') => {
             if state == State::HunkPlus {
                 painter.paint_buffered_lines();
             }
             painter.minus_lines.push(prepare(&line, true, config));
+            if let Some(color_moved_style) = color_moved_style {
+                painter
+                    .minus_lines_color_moved_styles
+                    .push(color_moved_style);
+            }
             State::HunkMinus
         }
         Some('+') => {
             painter.plus_lines.push(prepare(&line, true, config));
+            if let Some(color_moved_style) = color_moved_style {
+                painter
+                    .plus_lines_color_moved_styles
+                    .push(color_moved_style);
+            }
+            if config.show_whitespace_errors {
+                painter
+                    .plus_lines_indentation_is_mixed
+                    .push(has_mixed_indentation(&line[1..]));
+            }
             State::HunkPlus
         }
+        Some(' ') if is_blank_zero_line => {
+            // The run of blank lines starting here follows any buffered changed lines.
+            if painter.blank_zero_lines.is_empty() {
+                painter.paint_buffered_lines();
+            }
+            painter.blank_zero_lines.push(prepare(line, true, config));
+            State::HunkZero
+        }
         Some(' ') => {
-            let state = State::HunkZero;
-            let prefix = if line.is_empty() { "" } else { &line[..1] };
             painter.paint_buffered_lines();
-            let lines = vec![prepare(&line, true, config)];
-            let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
-                &lines,
-                &state,
-                &mut painter.highlighter,
-                &painter.config,
-            );
-            let diff_style_sections = vec![(config.zero_style, lines[0].as_str())];
-
-            Painter::paint_lines(
-                syntax_style_sections,
-                vec![diff_style_sections],
-                vec![Some((
-                    Some(painter.minus_line_number),
-                    Some(painter.plus_line_number),
-                ))],
-                &mut painter.output_buffer,
-                config,
-                prefix,
-                config.zero_style,
-                config.zero_style,
-                None,
-            );
-            painter.minus_line_number += 1;
-            painter.plus_line_number += 1;
-            state
+            painter.paint_zero_line(prepare(line, true, config));
+            State::HunkZero
         }
         _ => {
             // The first character here could be e.g. '\' from '\ No newline at end of file'. This
@@ -510,6 +1784,51 @@ fn handle_hunk_line(
     }
 }
 
+/// Under --color-moved, return the style in which to display a removed or added line, if git has
+/// marked it as moved by giving it one of its moved-line colors (see Config::git_color_moved_styles).
+fn get_color_moved_style(raw_line: &str, config: &Config) -> Option<Style> {
+    let mut style = ansi_term::Style::new();
+    let mut rest = raw_line;
+    while let Some(escape_sequence) = rest.strip_prefix("\x1b[") {
+        let end = escape_sequence.find('m')?;
+        style = html::apply_sgr_parameters(style, &escape_sequence[..end]);
+        rest = &escape_sequence[end + 1..];
+    }
+    let style = get_style_with_basic_colors_as_numbers(style);
+    config
+        .git_color_moved_styles
+        .iter()
+        .find(|(git_style, _)| get_style_with_basic_colors_as_numbers(*git_style) == style)
+        .map(|(_, is_alternate)| {
+            if *is_alternate {
+                config.color_moved_alternate_style
+            } else {
+                config.color_moved_style
+            }
+        })
+}
+
+/// Return the style with the 8 basic colors given by their ANSI color numbers, e.g. Fixed(5)
+/// rather than Purple, so that styles can be compared however their colors were specified.
+fn get_style_with_basic_colors_as_numbers(style: ansi_term::Style) -> ansi_term::Style {
+    let as_number = |color: ansi_term::Color| match color {
+        ansi_term::Color::Black => ansi_term::Color::Fixed(0),
+        ansi_term::Color::Red => ansi_term::Color::Fixed(1),
+        ansi_term::Color::Green => ansi_term::Color::Fixed(2),
+        ansi_term::Color::Yellow => ansi_term::Color::Fixed(3),
+        ansi_term::Color::Blue => ansi_term::Color::Fixed(4),
+        ansi_term::Color::Purple => ansi_term::Color::Fixed(5),
+        ansi_term::Color::Cyan => ansi_term::Color::Fixed(6),
+        ansi_term::Color::White => ansi_term::Color::Fixed(7),
+        color => color,
+    };
+    ansi_term::Style {
+        foreground: style.foreground.map(as_number),
+        background: style.background.map(as_number),
+        ..style
+    }
+}
+
 /// Replace initial -/+ character with ' ', expand tabs as spaces, and optionally terminate with
 /// newline.
 // Terminating with newline character is necessary for many of the sublime syntax definitions to
@@ -518,19 +1837,50 @@ fn handle_hunk_line(
 fn prepare(line: &str, append_newline: bool, config: &Config) -> String {
     let terminator = if append_newline { "\n" } else { "" };
     if !line.is_empty() {
-        let mut line = line.graphemes(true);
+        let mut line = line.graphemes(true).peekable();
 
         // The first column contains a -/+/space character, added by git. We substitute it for a
         // space now, so that it is not present during syntax highlighting, and substitute again
         // when emitting the line.
         line.next();
 
-        format!(" {}{}", expand_tabs(line, config.tab_width), terminator)
+        let mut n_spaces_to_remove = config.decrement_leading_spaces;
+        while n_spaces_to_remove > 0 && line.peek() == Some(&" ") {
+            line.next();
+            n_spaces_to_remove -= 1;
+        }
+
+        let line = if config.tabs_to_spaces {
+            utils::expand_tabs_to_tab_stops(line, config.tab_width)
+        } else {
+            expand_tabs(line, config.tab_width)
+        };
+        let line = format!(" {}{}", line, terminator);
+        if config.redact_secrets {
+            redact_secrets(line, config)
+        } else {
+            line
+        }
     } else {
         terminator.to_string()
     }
 }
 
+/// Whether the indentation of the line contains both spaces and tabs.
+fn has_mixed_indentation(line: &str) -> bool {
+    let indentation_end = line.len() - line.trim_start_matches(&[' ', '\t'][..]).len();
+    let indentation = &line[..indentation_end];
+    indentation.contains(' ') && indentation.contains('\t')
+}
+
+/// Replace the text matching any of the --redact-secrets patterns with "[REDACTED]", keeping the
+/// text captured by a group named "key", if any.
+fn redact_secrets(line: String, config: &Config) -> String {
+    config.redact_patterns.iter().fold(line, |line, pattern| {
+        pattern.replace_all(&line, "${key}[REDACTED]").into_owned()
+    })
+}
+
 /// Expand tabs as spaces.
 /// tab_width = 0 is documented to mean do not replace tabs.
 fn expand_tabs<'a, I>(line: I, tab_width: usize) -> String
@@ -0,0 +1,397 @@
+//! Conversion of context diffs (`diff -c`) and normal diffs (`diff` with no format option) to
+//! unified diff lines, for --diff-mode. The converted lines are processed exactly as lines of a
+//! unified diff would be, so that every other option applies to them unchanged.
+//!
+//! A normal diff hunk, e.g.
+//!
+//! ```text
+//! 2,3c2
+//! < b
+//! < c
+//! ---
+//! > B
+//! ```
+//!
+//! can be converted line by line. A context diff hunk lists the old lines and then the new lines,
+//! e.g.
+//!
+//! ```text
+//! ***************
+//! *** 1,3 ****
+//!   a
+//! ! b
+//!   c
+//! --- 1,3 ----
+//!   a
+//! ! B
+//!   c
+//! ```
+//!
+//! so it is buffered until it is complete, and then merged into a single sequence of removed,
+//! added and unchanged lines.
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::DiffMode;
+
+lazy_static! {
+    static ref NORMAL_HUNK_HEADER_REGEX: Regex =
+        Regex::new(r"^(\d+)(?:,(\d+))?([acd])(\d+)(?:,(\d+))?$").unwrap();
+    static ref CONTEXT_OLD_RANGE_REGEX: Regex =
+        Regex::new(r"^\*\*\* (\d+)(?:,(\d+))? \*\*\*\*$").unwrap();
+    static ref CONTEXT_NEW_RANGE_REGEX: Regex = Regex::new(r"^--- (\d+)(?:,(\d+))? ----$").unwrap();
+}
+
+const CONTEXT_HUNK_SEPARATOR: &str = "***************";
+
+#[derive(Debug, PartialEq)]
+enum ContextDiffSection {
+    None,
+    Old,
+    New,
+}
+
+/// The state of the conversion of a context or normal diff, built up one line at a time.
+pub struct DiffConverter {
+    diff_mode: DiffMode,
+    // The context diff hunk being read: the start line of the old and new ranges, and the old and
+    // new lines, each with its two-character marker ("  ", "- ", "+ " or "! ") removed and its
+    // marker character kept.
+    section: ContextDiffSection,
+    old_start: usize,
+    new_start: usize,
+    old_lines: Vec<(char, String)>,
+    new_lines: Vec<(char, String)>,
+}
+
+impl DiffConverter {
+    /// Return a converter for the given input format, or None if the input is a unified diff.
+    pub fn new(diff_mode: DiffMode) -> Option<Self> {
+        if diff_mode == DiffMode::Unified {
+            return None;
+        }
+        Some(Self {
+            diff_mode,
+            section: ContextDiffSection::None,
+            old_start: 0,
+            new_start: 0,
+            old_lines: Vec::new(),
+            new_lines: Vec::new(),
+        })
+    }
+
+    /// Return the unified diff lines that `line`, with ANSI escape sequences removed, completes.
+    pub fn push_line(&mut self, line: &str) -> Vec<String> {
+        match self.diff_mode {
+            DiffMode::Normal => convert_normal_diff_line(line),
+            DiffMode::Context => self.push_context_diff_line(line),
+            DiffMode::Unified => vec![line.to_string()],
+        }
+    }
+
+    /// Return the unified diff lines that are still pending at the end of the input.
+    pub fn finish(&mut self) -> Vec<String> {
+        self.flush_context_diff_hunk()
+    }
+
+    fn push_context_diff_line(&mut self, line: &str) -> Vec<String> {
+        if let Some(captures) = CONTEXT_OLD_RANGE_REGEX.captures(line) {
+            let lines = self.flush_context_diff_hunk();
+            self.section = ContextDiffSection::Old;
+            self.old_start = captures[1].parse().unwrap_or(0);
+            return lines;
+        }
+        if self.section != ContextDiffSection::None {
+            if let Some(captures) = CONTEXT_NEW_RANGE_REGEX.captures(line) {
+                self.section = ContextDiffSection::New;
+                self.new_start = captures[1].parse().unwrap_or(0);
+                return Vec::new();
+            }
+            if let Some(marker) = get_context_diff_line_marker(line) {
+                let text = line.get(2..).unwrap_or("").to_string();
+                match self.section {
+                    ContextDiffSection::Old => self.old_lines.push((marker, text)),
+                    _ => self.new_lines.push((marker, text)),
+                }
+                return Vec::new();
+            }
+            if line.starts_with('\\') {
+                match self.section {
+                    ContextDiffSection::Old => self.old_lines.push(('\\', line.to_string())),
+                    _ => self.new_lines.push(('\\', line.to_string())),
+                }
+                return Vec::new();
+            }
+        }
+        let mut lines = self.flush_context_diff_hunk();
+        if line == CONTEXT_HUNK_SEPARATOR {
+            return lines;
+        }
+        // The file header: "*** old-path<TAB>date" followed by "--- new-path<TAB>date".
+        if let Some(old_file) = line.strip_prefix("*** ") {
+            lines.push(format!("--- {}", old_file));
+        } else if let Some(new_file) = line.strip_prefix("--- ") {
+            lines.push(format!("+++ {}", new_file));
+        } else {
+            lines.push(line.to_string());
+        }
+        lines
+    }
+
+    /// Return the unified diff lines of the buffered context diff hunk, if any.
+    fn flush_context_diff_hunk(&mut self) -> Vec<String> {
+        if self.section == ContextDiffSection::None {
+            return Vec::new();
+        }
+        self.section = ContextDiffSection::None;
+        let mut old_lines = std::mem::take(&mut self.old_lines);
+        let mut new_lines = std::mem::take(&mut self.new_lines);
+        // A section that would contain only unchanged lines is omitted from the diff.
+        if old_lines.is_empty() {
+            old_lines = get_unchanged_lines(&new_lines);
+        } else if new_lines.is_empty() {
+            new_lines = get_unchanged_lines(&old_lines);
+        }
+        let hunk_lines = merge_context_diff_sections(&old_lines, &new_lines);
+        let count = |markers: &[char]| {
+            hunk_lines
+                .iter()
+                .filter(|line| line.starts_with(markers))
+                .count()
+        };
+        let mut lines = vec![format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start,
+            count(&[' ', '-']),
+            self.new_start,
+            count(&[' ', '+'])
+        )];
+        lines.extend(hunk_lines);
+        lines
+    }
+}
+
+/// Return the unified diff lines corresponding to a line of a normal diff.
+fn convert_normal_diff_line(line: &str) -> Vec<String> {
+    if let Some(captures) = NORMAL_HUNK_HEADER_REGEX.captures(line) {
+        let get = |i: usize| {
+            captures
+                .get(i)
+                .and_then(|m| m.as_str().parse::<usize>().ok())
+        };
+        let range = |start: Option<usize>, end: Option<usize>| {
+            let start = start.unwrap_or(0);
+            (start, end.map_or(1, |end| end + 1 - start.min(end)))
+        };
+        let (mut old_range, mut new_range) = (range(get(1), get(2)), range(get(4), get(5)));
+        // Under "a" the old range is the line after which lines are added, and under "d" the new
+        // range is the line after which lines were deleted. In a unified diff such an empty range
+        // is given by that line number and a count of 0.
+        match &captures[3] {
+            "a" => old_range.1 = 0,
+            "d" => new_range.1 = 0,
+            _ => {}
+        }
+        return vec![format!(
+            "@@ -{},{} +{},{} @@",
+            old_range.0, old_range.1, new_range.0, new_range.1
+        )];
+    }
+    if let Some(text) = line.strip_prefix('<') {
+        return vec![format!("-{}", text.strip_prefix(' ').unwrap_or(text))];
+    }
+    if let Some(text) = line.strip_prefix('>') {
+        return vec![format!("+{}", text.strip_prefix(' ').unwrap_or(text))];
+    }
+    if line == "---" {
+        return Vec::new();
+    }
+    // `diff -r` precedes the hunks of each file with e.g. "diff -r old/x new/x". File headers
+    // are added after it, so that the file is displayed with a file header.
+    if line.starts_with("diff ") {
+        let paths: Vec<&str> = line.split_whitespace().rev().take(2).collect();
+        if let [new_path, old_path] = paths.as_slice() {
+            return vec![
+                line.to_string(),
+                format!("--- {}", old_path),
+                format!("+++ {}", new_path),
+            ];
+        }
+    }
+    vec![line.to_string()]
+}
+
+/// Return the marker character of a line in a section of a context diff hunk, or None if the line
+/// is not such a line.
+fn get_context_diff_line_marker(line: &str) -> Option<char> {
+    let mut chars = line.chars();
+    match (chars.next(), chars.next()) {
+        (Some(marker @ ' '), Some(' '))
+        | (Some(marker @ '-'), Some(' '))
+        | (Some(marker @ '+'), Some(' '))
+        | (Some(marker @ '!'), Some(' ')) => Some(marker),
+        // An empty unchanged line may have lost its trailing space.
+        (Some(' '), None) => Some(' '),
+        _ => None,
+    }
+}
+
+fn get_unchanged_lines(lines: &[(char, String)]) -> Vec<(char, String)> {
+    lines
+        .iter()
+        .filter(|(marker, _)| *marker == ' ')
+        .cloned()
+        .collect()
+}
+
+/// Merge the old and new sections of a context diff hunk into unified diff lines. The unchanged
+/// lines are the same in both sections; between each pair of them, the old section's removed and
+/// changed lines become removed lines, and the new section's added and changed lines become added
+/// lines.
+fn merge_context_diff_sections(
+    old_lines: &[(char, String)],
+    new_lines: &[(char, String)],
+) -> Vec<String> {
+    let format_line = |(marker, text): &(char, String), unified_marker: char| match marker {
+        '\\' => text.clone(),
+        _ => format!("{}{}", unified_marker, text),
+    };
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        while i < old_lines.len() && old_lines[i].0 != ' ' {
+            lines.push(format_line(&old_lines[i], '-'));
+            i += 1;
+        }
+        while j < new_lines.len() && new_lines[j].0 != ' ' {
+            lines.push(format_line(&new_lines[j], '+'));
+            j += 1;
+        }
+        match (old_lines.get(i), new_lines.get(j)) {
+            (Some(line), Some(_)) | (Some(line), None) | (None, Some(line)) => {
+                lines.push(format_line(line, ' '));
+            }
+            (None, None) => return lines,
+        }
+        i += 1;
+        j += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(diff_mode: DiffMode, input: &str) -> Vec<String> {
+        let mut converter = DiffConverter::new(diff_mode).unwrap();
+        let mut lines: Vec<String> = input
+            .lines()
+            .flat_map(|line| converter.push_line(line))
+            .collect();
+        lines.extend(converter.finish());
+        lines
+    }
+
+    #[test]
+    fn test_convert_normal_diff() {
+        assert_eq!(
+            convert(
+                DiffMode::Normal,
+                "2,3c2\n< b\n< c\n---\n> B\n5a6,7\n> x\n> y\n8d9\n< z\n"
+            ),
+            vec![
+                "@@ -2,2 +2,1 @@",
+                "-b",
+                "-c",
+                "+B",
+                "@@ -5,0 +6,2 @@",
+                "+x",
+                "+y",
+                "@@ -8,1 +9,0 @@",
+                "-z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_normal_diff_file_headers() {
+        assert_eq!(
+            convert(
+                DiffMode::Normal,
+                "diff -r old/a.py new/a.py\n1c1\n< x\n---\n> y\n"
+            ),
+            vec![
+                "diff -r old/a.py new/a.py",
+                "--- old/a.py",
+                "+++ new/a.py",
+                "@@ -1,1 +1,1 @@",
+                "-x",
+                "+y",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_context_diff() {
+        let input = "\
+*** a.py\t2020-01-01 00:00:00
+--- b.py\t2020-01-01 00:00:00
+***************
+*** 1,4 ****
+  a
+! b
+- c
+  d
+--- 1,4 ----
+  a
+! B
+  d
++ e
+***************
+*** 9,10 ****
+--- 9,11 ----
+  f
++ g
+  h
+";
+        assert_eq!(
+            convert(DiffMode::Context, input),
+            vec![
+                "--- a.py\t2020-01-01 00:00:00",
+                "+++ b.py\t2020-01-01 00:00:00",
+                "@@ -1,4 +1,4 @@",
+                " a",
+                "-b",
+                "-c",
+                "+B",
+                " d",
+                "+e",
+                "@@ -9,2 +9,3 @@",
+                " f",
+                "+g",
+                " h",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_context_diff_no_newline_at_end_of_file() {
+        let input = "\
+***************
+*** 1 ****
+! a
+\\ No newline at end of file
+--- 1 ----
+! b
+";
+        assert_eq!(
+            convert(DiffMode::Context, input),
+            vec![
+                "@@ -1,1 +1,1 @@",
+                "-a",
+                "\\ No newline at end of file",
+                "+b",
+            ]
+        );
+    }
+}
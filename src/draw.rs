@@ -6,9 +6,56 @@ use box_drawing;
 use console::strip_ansi_codes;
 use unicode_width::UnicodeWidthStr;
 
-use crate::config::Width;
+use crate::config::{BoxStyle, Width};
 use crate::style::Style;
 
+/// The characters with which a box, or a horizontal line, is drawn.
+pub struct BoxDrawingCharacters {
+    pub horizontal: &'static str,
+    pub vertical: &'static str,
+    pub down_left: &'static str,
+    pub up_left: &'static str,
+    pub up_horizontal: &'static str,
+}
+
+/// Return the characters of `box_style`. Under the default unicode style, bold decorations are
+/// drawn with heavy lines.
+pub fn get_box_drawing_characters(
+    box_style: BoxStyle,
+    decoration_style: ansi_term::Style,
+) -> BoxDrawingCharacters {
+    match box_style {
+        BoxStyle::Ascii => BoxDrawingCharacters {
+            horizontal: "-",
+            vertical: "|",
+            down_left: "+",
+            up_left: "+",
+            up_horizontal: "+",
+        },
+        BoxStyle::Unicode if !decoration_style.is_bold => BoxDrawingCharacters {
+            horizontal: box_drawing::light::HORIZONTAL,
+            vertical: box_drawing::light::VERTICAL,
+            down_left: box_drawing::light::DOWN_LEFT,
+            up_left: box_drawing::light::UP_LEFT,
+            up_horizontal: box_drawing::light::UP_HORIZONTAL,
+        },
+        BoxStyle::Unicode | BoxStyle::Heavy => BoxDrawingCharacters {
+            horizontal: box_drawing::heavy::HORIZONTAL,
+            vertical: box_drawing::heavy::VERTICAL,
+            down_left: box_drawing::heavy::DOWN_LEFT,
+            up_left: box_drawing::heavy::UP_LEFT,
+            up_horizontal: box_drawing::heavy::UP_HORIZONTAL,
+        },
+        BoxStyle::Double => BoxDrawingCharacters {
+            horizontal: box_drawing::double::HORIZONTAL,
+            vertical: box_drawing::double::VERTICAL,
+            down_left: box_drawing::double::DOWN_LEFT,
+            up_left: box_drawing::double::UP_LEFT,
+            up_horizontal: box_drawing::double::UP_HORIZONTAL,
+        },
+    }
+}
+
 pub fn write_no_decoration(
     writer: &mut dyn Write,
     text: &str,
@@ -16,6 +63,7 @@ pub fn write_no_decoration(
     _line_width: &Width, // ignored
     text_style: Style,
     _decoration_style: ansi_term::Style,
+    _box_style: BoxStyle,
 ) -> std::io::Result<()> {
     if text_style.is_raw {
         writeln!(writer, "{}", raw_text)?;
@@ -34,12 +82,9 @@ pub fn write_boxed(
     _line_width: &Width, // ignored
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
-    let up_left = if decoration_style.is_bold {
-        box_drawing::heavy::UP_LEFT
-    } else {
-        box_drawing::light::UP_LEFT
-    };
+    let up_left = get_box_drawing_characters(box_style, decoration_style).up_left;
     let box_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
     write_boxed_partial(
         writer,
@@ -48,6 +93,7 @@ pub fn write_boxed(
         box_width,
         text_style,
         decoration_style,
+        box_style,
     )?;
     writeln!(writer, "{}", decoration_style.paint(up_left))?;
     Ok(())
@@ -62,6 +108,7 @@ pub fn write_boxed_with_underline(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
     let box_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
     write_boxed_with_horizontal_whisker(
@@ -71,6 +118,7 @@ pub fn write_boxed_with_underline(
         box_width,
         text_style,
         decoration_style,
+        box_style,
     )?;
     let line_width = match *line_width {
         Width::Fixed(n) => n,
@@ -85,6 +133,7 @@ pub fn write_boxed_with_underline(
         },
         text_style,
         decoration_style,
+        box_style,
     )?;
     write!(writer, "\n")?;
     Ok(())
@@ -103,6 +152,7 @@ pub fn write_underlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Under,
@@ -112,6 +162,7 @@ pub fn write_underlined(
         line_width,
         text_style,
         decoration_style,
+        box_style,
     )
 }
 
@@ -122,6 +173,7 @@ pub fn write_overlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Over,
@@ -131,6 +183,7 @@ pub fn write_overlined(
         line_width,
         text_style,
         decoration_style,
+        box_style,
     )
 }
 
@@ -141,6 +194,7 @@ pub fn write_underoverlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Underover,
@@ -150,9 +204,11 @@ pub fn write_underoverlined(
         line_width,
         text_style,
         decoration_style,
+        box_style,
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn _write_under_or_over_lined(
     underoverline: UnderOverline,
     writer: &mut dyn Write,
@@ -161,6 +217,7 @@ fn _write_under_or_over_lined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
     let text_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
     let line_width = match *line_width {
@@ -169,7 +226,7 @@ fn _write_under_or_over_lined(
     };
     let mut write_line: Box<dyn FnMut(&mut dyn Write) -> std::io::Result<()>> =
         Box::new(|writer| {
-            write_horizontal_line(writer, line_width, text_style, decoration_style)?;
+            write_horizontal_line(writer, line_width, text_style, decoration_style, box_style)?;
             write!(writer, "\n")?;
             Ok(())
         });
@@ -194,12 +251,9 @@ fn write_horizontal_line(
     width: usize,
     _text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
-    let horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::HORIZONTAL
-    } else {
-        box_drawing::light::HORIZONTAL
-    };
+    let horizontal = get_box_drawing_characters(box_style, decoration_style).horizontal;
     write!(
         writer,
         "{}",
@@ -214,12 +268,9 @@ fn write_boxed_with_horizontal_whisker(
     box_width: usize,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
-    let up_horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::UP_HORIZONTAL
-    } else {
-        box_drawing::light::UP_HORIZONTAL
-    };
+    let up_horizontal = get_box_drawing_characters(box_style, decoration_style).up_horizontal;
     write_boxed_partial(
         writer,
         text,
@@ -227,6 +278,7 @@ fn write_boxed_with_horizontal_whisker(
         box_width,
         text_style,
         decoration_style,
+        box_style,
     )?;
     write!(writer, "{}", decoration_style.paint(up_horizontal))?;
     Ok(())
@@ -239,20 +291,14 @@ fn write_boxed_partial(
     box_width: usize,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    box_style: BoxStyle,
 ) -> std::io::Result<()> {
-    let (horizontal, down_left, vertical) = if decoration_style.is_bold {
-        (
-            box_drawing::heavy::HORIZONTAL,
-            box_drawing::heavy::DOWN_LEFT,
-            box_drawing::heavy::VERTICAL,
-        )
-    } else {
-        (
-            box_drawing::light::HORIZONTAL,
-            box_drawing::light::DOWN_LEFT,
-            box_drawing::light::VERTICAL,
-        )
-    };
+    let BoxDrawingCharacters {
+        horizontal,
+        down_left,
+        vertical,
+        ..
+    } = get_box_drawing_characters(box_style, decoration_style);
     let horizontal_edge = horizontal.repeat(box_width);
     write!(
         writer,
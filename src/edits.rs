@@ -17,6 +17,7 @@ pub fn infer_edits<'a, EditOperation>(
     noop_insertion: EditOperation,
     insertion: EditOperation,
     tokenization_regex: &Regex,
+    algorithm: align::IntraLineDiffAlgorithm,
     max_line_distance: f64,
     max_line_distance_for_naively_paired_lines: f64,
 ) -> (
@@ -35,9 +36,10 @@ where
     'minus_lines_loop: for minus_line in minus_lines {
         let mut considered = 0; // plus lines considered so far as match for minus_line
         for plus_line in &plus_lines[emitted..] {
-            let alignment = align::Alignment::new(
+            let alignment = align::Alignment::new_with_algorithm(
                 tokenize(minus_line, tokenization_regex),
                 tokenize(plus_line, tokenization_regex),
+                algorithm,
             );
             let (annotated_minus_line, annotated_plus_line, distance) = annotate(
                 alignment,
@@ -82,7 +84,7 @@ where
 
 /// Split line into tokens for alignment. The alignment algorithm aligns sequences of substrings;
 /// not individual characters.
-fn tokenize<'a>(line: &'a str, regex: &Regex) -> Vec<&'a str> {
+pub fn tokenize<'a>(line: &'a str, regex: &Regex) -> Vec<&'a str> {
     let mut tokens = Vec::new();
     let mut offset = 0;
     for m in regex.find_iter(line) {
@@ -489,6 +491,61 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_infer_edits_insertion_of_parentheses() {
+        assert_paired_edits(
+            vec!["x = a + b"],
+            vec!["x = (a + b) * c"],
+            (
+                vec![vec![(MinusNoop, "x = "), (MinusNoop, "a + b")]],
+                vec![vec![
+                    (PlusNoop, "x = "),
+                    (Insertion, "("),
+                    (PlusNoop, "a + b"),
+                    (Insertion, ") * c"),
+                ]],
+            ),
+        );
+        assert_paired_edits(
+            vec!["return a+b;"],
+            vec!["return (a+b);"],
+            (
+                vec![vec![
+                    (MinusNoop, "return "),
+                    (MinusNoop, "a+b"),
+                    (MinusNoop, ";"),
+                ]],
+                vec![vec![
+                    (PlusNoop, "return "),
+                    (Insertion, "("),
+                    (PlusNoop, "a+b"),
+                    (Insertion, ")"),
+                    (PlusNoop, ";"),
+                ]],
+            ),
+        )
+    }
+
+    #[test]
+    fn test_infer_edits_changed_operator() {
+        assert_paired_edits(
+            vec!["if a == b {"],
+            vec!["if a != b {"],
+            (
+                vec![vec![
+                    (MinusNoop, "if a "),
+                    (Deletion, "="),
+                    (MinusNoop, "= b {"),
+                ]],
+                vec![vec![
+                    (PlusNoop, "if a "),
+                    (Insertion, "!"),
+                    (PlusNoop, "= b {"),
+                ]],
+            ),
+        )
+    }
+
     #[test]
     fn test_infer_edits_3() {
         assert_paired_edits(
@@ -728,6 +785,7 @@ mod tests {
             PlusNoop,
             Insertion,
             &*DEFAULT_TOKENIZATION_REGEXP,
+            align::IntraLineDiffAlgorithm::Myers,
             max_line_distance,
             0.0,
         );
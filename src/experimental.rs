@@ -0,0 +1,133 @@
+//! Experimental options: unstable settings that may change or be removed without notice. Each
+//! can be activated by --enable-experimental, in builds with the "experimental" Cargo feature, or
+//! by its environment variable. `delta --feature-flags experimental-features-list` lists them.
+use std::collections::HashMap;
+use std::process;
+
+use crate::env;
+
+pub struct ExperimentalFeature {
+    pub name: &'static str,
+    // The environment variable that activates the feature in any build.
+    pub env_var: &'static str,
+    // The kind of value that the feature takes, given as --enable-experimental <NAME>=<VALUE>.
+    pub value_name: Option<&'static str>,
+    pub description: &'static str,
+}
+
+pub const EXPERIMENTAL_FEATURES: [ExperimentalFeature; 1] = [ExperimentalFeature {
+    name: "max-line-distance-for-naively-paired-lines",
+    env_var: "DELTA_EXPERIMENTAL_MAX_LINE_DISTANCE_FOR_NAIVELY_PAIRED_LINES",
+    value_name: Some("FLOAT"),
+    description: "In a group of changed lines with as many removed as added lines, the maximum \
+                  distance between a removed and an added line for them to be inferred to be \
+                  homologous, when larger than --max-line-distance.",
+}];
+
+/// Whether this build of delta honors --enable-experimental.
+pub const IS_EXPERIMENTAL_BUILD: bool = cfg!(feature = "experimental");
+
+/// Parse the "<NAME>[=<VALUE>]" values of --enable-experimental into a map from feature name to
+/// value (the empty string for a feature taking no value). A build without the "experimental"
+/// feature accepts the option but warns that it has no effect.
+pub fn parse_enabled_features(values: &[String]) -> HashMap<&'static str, String> {
+    let mut enabled = HashMap::new();
+    for value in values {
+        let (name, feature_value) = match value.split_once('=') {
+            Some((name, feature_value)) => (name, Some(feature_value)),
+            None => (value.as_str(), None),
+        };
+        let feature = EXPERIMENTAL_FEATURES
+            .iter()
+            .find(|feature| feature.name == name)
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "Invalid value for --enable-experimental option: {} \
+                     (use --feature-flags experimental-features-list to see the valid values)",
+                    name
+                );
+                process::exit(1);
+            });
+        let feature_value = match (feature.value_name, feature_value) {
+            (Some(_), Some(feature_value)) => feature_value,
+            (None, None) => "",
+            (Some(value_name), None) => {
+                eprintln!(
+                    "Invalid value for --enable-experimental option: {} \
+                     (the value must have the form {}=<{}>)",
+                    value, name, value_name
+                );
+                process::exit(1);
+            }
+            (None, Some(_)) => {
+                eprintln!(
+                    "Invalid value for --enable-experimental option: {} \
+                     (the feature does not take a value)",
+                    value
+                );
+                process::exit(1);
+            }
+        };
+        if IS_EXPERIMENTAL_BUILD {
+            enabled.insert(feature.name, feature_value.to_string());
+        } else {
+            eprintln!(
+                "Warning: --enable-experimental {} has no effect, since delta was built without \
+                 the \"experimental\" feature. The environment variable {} may be used instead.",
+                value, feature.env_var
+            );
+        }
+    }
+    enabled
+}
+
+/// Return the value of the experimental feature `name` if it is activated, by
+/// --enable-experimental or by its environment variable.
+pub fn get_feature_value(enabled: &HashMap<&'static str, String>, name: &str) -> Option<String> {
+    enabled.get(name).cloned().or_else(|| {
+        EXPERIMENTAL_FEATURES
+            .iter()
+            .find(|feature| feature.name == name)
+            .and_then(|feature| env::get_env_var(feature.env_var))
+    })
+}
+
+/// Return the listing of the experimental features displayed by --feature-flags
+/// experimental-features-list.
+pub fn format_experimental_features_list() -> String {
+    let mut listing = String::new();
+    for feature in EXPERIMENTAL_FEATURES.iter() {
+        let usage = match feature.value_name {
+            Some(value_name) => format!("{}=<{}>", feature.name, value_name),
+            None => feature.name.to_string(),
+        };
+        listing.push_str(&format!(
+            "{}\n    {}\n    Environment variable: {}\n",
+            usage, feature.description, feature.env_var
+        ));
+    }
+    if !IS_EXPERIMENTAL_BUILD {
+        listing.push_str(
+            "\nThis build of delta was not compiled with the \"experimental\" feature, so \
+             --enable-experimental has no effect; the environment variables may be used instead.\n",
+        );
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_feature_value() {
+        let name = "max-line-distance-for-naively-paired-lines";
+        let enabled = parse_enabled_features(&[format!("{}=0.3", name)]);
+        if IS_EXPERIMENTAL_BUILD {
+            assert_eq!(get_feature_value(&enabled, name), Some("0.3".to_string()));
+        } else {
+            assert!(enabled.is_empty());
+        }
+        assert!(format_experimental_features_list().starts_with(&format!("{}=<FLOAT>", name)));
+    }
+}
@@ -0,0 +1,288 @@
+//! Support for running delta as git's external diff program, i.e. with GIT_EXTERNAL_DIFF=delta.
+//! In that case git invokes delta once per changed file, with the seven arguments
+//!
+//! ```text
+//! path old-file old-hex old-mode new-file new-hex new-mode
+//! ```
+//!
+//! or, for a renamed or copied file, the nine arguments
+//!
+//! ```text
+//! path old-file old-hex old-mode new-file new-hex new-mode new-path metainfo
+//! ```
+//!
+//! where metainfo holds the extended header lines ("similarity index", "rename from", etc.),
+//! instead of writing a diff to delta's standard input. The old and new file are temporary
+//! copies (or /dev/null if the file was added or deleted), so delta runs `diff -u` on them itself
+//! and precedes the output with the header that git would have written.
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+
+const NULL_FILE: &str = "/dev/null";
+
+/// The arguments with which git invokes an external diff program.
+#[derive(Debug, PartialEq)]
+pub struct ExternalDiffArgs {
+    pub path: String,
+    pub old_file: PathBuf,
+    pub old_hex: String,
+    pub old_mode: String,
+    pub new_file: PathBuf,
+    pub new_hex: String,
+    pub new_mode: String,
+    // The path of the file after a rename or copy; otherwise the same as `path`.
+    pub new_path: String,
+    // For a renamed or copied file, the extended header lines written by git.
+    pub metainfo: Option<String>,
+}
+
+/// If the command line ends with the seven or nine arguments passed by git to an external diff
+/// program, return the remaining command line (the program name and any delta options) together
+/// with the external diff arguments.
+pub fn parse_external_diff_args(args: &[OsString]) -> Option<(Vec<OsString>, ExternalDiffArgs)> {
+    try_parse_external_diff_args(args, 9).or_else(|| try_parse_external_diff_args(args, 7))
+}
+
+fn try_parse_external_diff_args(
+    args: &[OsString],
+    n_external_args: usize,
+) -> Option<(Vec<OsString>, ExternalDiffArgs)> {
+    if args.len() <= n_external_args {
+        return None;
+    }
+    let (delta_args, external_args) = args.split_at(args.len() - n_external_args);
+    let as_str = |i: usize| external_args[i].to_str();
+    let (old_hex, old_mode, new_hex, new_mode) = (as_str(2)?, as_str(3)?, as_str(5)?, as_str(6)?);
+    if !(is_hex(old_hex) && is_mode(old_mode) && is_hex(new_hex) && is_mode(new_mode)) {
+        return None;
+    }
+    let path = external_args[0].to_string_lossy().into_owned();
+    let (new_path, metainfo) = if n_external_args == 9 {
+        (
+            external_args[7].to_string_lossy().into_owned(),
+            Some(external_args[8].to_string_lossy().into_owned()),
+        )
+    } else {
+        (path.clone(), None)
+    };
+    Some((
+        delta_args.to_vec(),
+        ExternalDiffArgs {
+            path,
+            old_file: PathBuf::from(&external_args[1]),
+            old_hex: old_hex.to_string(),
+            old_mode: old_mode.to_string(),
+            new_file: PathBuf::from(&external_args[4]),
+            new_hex: new_hex.to_string(),
+            new_mode: new_mode.to_string(),
+            new_path,
+            metainfo,
+        },
+    ))
+}
+
+/// Return the diff of the old and new file as git would have written it: a `diff --git` header
+/// followed by the output of `diff -u`. If diff fails, exit with its status.
+pub fn get_external_diff(args: &ExternalDiffArgs) -> std::io::Result<Vec<u8>> {
+    let mut output = make_git_diff_header(args).into_bytes();
+    let (old_label, new_label) = (
+        make_label(&args.old_mode, "a/", &args.path),
+        make_label(&args.new_mode, "b/", &args.new_path),
+    );
+    // diff exits with status 1 when the files differ, and with status 2 on trouble.
+    let diff_output = process::Command::new(PathBuf::from("diff"))
+        .args(["-u", "--label", &old_label, "--label", &new_label])
+        .arg(&args.old_file)
+        .arg(&args.new_file)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !matches!(diff_output.status.code(), Some(0) | Some(1)) {
+        // diff has written the cause of the failure to stderr.
+        process::exit(diff_output.status.code().unwrap_or(2));
+    }
+    output.extend(diff_output.stdout);
+    Ok(output)
+}
+
+/// Return the `diff --git` line and extended header lines describing the change.
+fn make_git_diff_header(args: &ExternalDiffArgs) -> String {
+    let mut header = format!("diff --git a/{} b/{}\n", args.path, args.new_path);
+    let mut index_line_mode = None;
+    if args.old_mode == "." {
+        header.push_str(&format!("new file mode {}\n", args.new_mode));
+    } else if args.new_mode == "." {
+        header.push_str(&format!("deleted file mode {}\n", args.old_mode));
+    } else if args.old_mode != args.new_mode {
+        header.push_str(&format!("old mode {}\n", args.old_mode));
+        header.push_str(&format!("new mode {}\n", args.new_mode));
+    } else {
+        index_line_mode = Some(&args.old_mode);
+    }
+    // The metainfo written by git includes the index line.
+    if let Some(metainfo) = &args.metainfo {
+        header.push_str(metainfo);
+        if !metainfo.is_empty() && !metainfo.ends_with('\n') {
+            header.push('\n');
+        }
+    } else if args.old_hex != args.new_hex {
+        header.push_str(&format!(
+            "index {}..{}{}\n",
+            abbreviate_hex(&args.old_hex),
+            abbreviate_hex(&args.new_hex),
+            index_line_mode.map_or("".to_string(), |mode| format!(" {}", mode))
+        ));
+    }
+    header
+}
+
+/// The file name shown in the "---" or "+++" line: /dev/null if the file does not exist on that
+/// side of the diff.
+fn make_label(mode: &str, prefix: &str, path: &str) -> String {
+    if mode == "." {
+        NULL_FILE.to_string()
+    } else {
+        format!("{}{}", prefix, path)
+    }
+}
+
+/// Git passes "." for the object name of a file that does not exist.
+fn abbreviate_hex(hex: &str) -> &str {
+    if hex == "." {
+        "0000000"
+    } else {
+        &hex[..hex.len().min(7)]
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    s == "." || (s.len() >= 7 && s.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_mode(s: &str) -> bool {
+    s == "." || (s.len() == 6 && s.chars().all(|c| ('0'..='7').contains(&c)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, remove_file};
+
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils;
+
+    const OLD_HEX: &str = "8b137891791fe96927ad78e64b0aad7bded08bdc";
+    const NEW_HEX: &str = "ce013625030ba8dba906f756967f9e9ca394464a";
+
+    fn make_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_parse_external_diff_args() {
+        let args = make_args(&[
+            "delta",
+            "--side-by-side",
+            "src/a.rs",
+            "/tmp/old_a.rs",
+            OLD_HEX,
+            "100644",
+            "src/a.rs",
+            NEW_HEX,
+            "100644",
+        ]);
+        let (delta_args, external_diff_args) = parse_external_diff_args(&args).unwrap();
+        assert_eq!(delta_args, make_args(&["delta", "--side-by-side"]));
+        assert_eq!(external_diff_args.path, "src/a.rs");
+        assert_eq!(external_diff_args.old_file, PathBuf::from("/tmp/old_a.rs"));
+        assert_eq!(external_diff_args.new_hex, NEW_HEX);
+
+        let args = make_args(&["delta", "a", "/dev/null", ".", ".", "a", NEW_HEX, "100755"]);
+        assert!(parse_external_diff_args(&args).is_some());
+
+        assert!(parse_external_diff_args(&make_args(&["delta", "a.txt", "b.txt"])).is_none());
+        let args = make_args(&["delta", "a", "b", "c", "d", "e", "f", "g"]);
+        assert!(parse_external_diff_args(&args).is_none());
+    }
+
+    #[test]
+    fn test_parse_external_diff_args_for_rename() {
+        let metainfo = "similarity index 90%\nrename from a.rs\nrename to b.rs\n";
+        let args = make_args(&[
+            "delta",
+            "a.rs",
+            "/tmp/old_a.rs",
+            OLD_HEX,
+            "100644",
+            "b.rs",
+            NEW_HEX,
+            "100644",
+            "b.rs",
+            metainfo,
+        ]);
+        let (delta_args, external_diff_args) = parse_external_diff_args(&args).unwrap();
+        assert_eq!(delta_args, make_args(&["delta"]));
+        assert_eq!(external_diff_args.path, "a.rs");
+        assert_eq!(external_diff_args.new_file, PathBuf::from("b.rs"));
+        assert_eq!(external_diff_args.new_path, "b.rs");
+        assert_eq!(external_diff_args.metainfo.as_deref(), Some(metainfo));
+        assert_eq!(
+            make_git_diff_header(&external_diff_args),
+            format!("diff --git a/a.rs b/b.rs\n{}", metainfo)
+        );
+    }
+
+    #[test]
+    fn test_make_git_diff_header() {
+        let mut args = ExternalDiffArgs {
+            path: "a.py".to_string(),
+            old_file: PathBuf::from("/tmp/old_a.py"),
+            old_hex: OLD_HEX.to_string(),
+            old_mode: "100644".to_string(),
+            new_file: PathBuf::from("a.py"),
+            new_hex: NEW_HEX.to_string(),
+            new_mode: "100644".to_string(),
+            new_path: "a.py".to_string(),
+            metainfo: None,
+        };
+        assert_eq!(
+            make_git_diff_header(&args),
+            "diff --git a/a.py b/a.py\nindex 8b13789..ce01362 100644\n"
+        );
+        args.old_hex = ".".to_string();
+        args.old_mode = ".".to_string();
+        assert_eq!(
+            make_git_diff_header(&args),
+            "diff --git a/a.py b/a.py\nnew file mode 100644\nindex 0000000..ce01362\n"
+        );
+    }
+
+    #[test]
+    fn test_external_diff() {
+        let (old_file, new_file) = (
+            "delta__test_external_diff.old",
+            "delta__test_external_diff.new",
+        );
+        fs::write(old_file, "def f():\n    return 1\n").unwrap();
+        fs::write(new_file, "def f():\n    return 2\n").unwrap();
+        let args = make_args(&[
+            "delta", "a.py", old_file, OLD_HEX, "100644", new_file, NEW_HEX, "100644",
+        ]);
+        let (_, external_diff_args) = parse_external_diff_args(&args).unwrap();
+        let input = get_external_diff(&external_diff_args).unwrap();
+        remove_file(old_file).unwrap();
+        remove_file(new_file).unwrap();
+
+        let input = String::from_utf8(input).unwrap();
+        assert!(input.starts_with(
+            "diff --git a/a.py b/a.py\nindex 8b13789..ce01362 100644\n--- a/a.py\n+++ b/a.py\n"
+        ));
+        let config = integration_test_utils::make_config(&[]);
+        let output = integration_test_utils::run_delta(&input, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\na.py\n"));
+        assert!(output.contains("    return 1\n"));
+        assert!(output.contains("    return 2\n"));
+    }
+}
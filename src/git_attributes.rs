@@ -0,0 +1,98 @@
+//! Lookup of the delta-style git attribute of the files in a diff, for --per-file-git-attributes.
+//! A .gitattributes line such as `*.md delta-style=markdown-preset` causes the files that it
+//! matches to be displayed with the named preset activated.
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::parse;
+
+pub const DELTA_STYLE_ATTRIBUTE: &str = "delta-style";
+
+/// Return the root of the working tree of the repository of the current directory, relative to
+/// which the file paths in git's diff output are given.
+pub fn get_repo_root() -> Option<PathBuf> {
+    let dir = std::env::current_dir().ok()?;
+    let repo = git2::Repository::discover(dir).ok()?;
+    repo.workdir().map(Path::to_path_buf)
+}
+
+/// Given a line starting a file's diff, e.g. "diff --git a/README.md b/README.md" or
+/// "diff --cc README.md", return the path of the file.
+pub fn get_file_path_from_diff_line(line: &str) -> Option<&str> {
+    if line.starts_with("diff --git ") {
+        parse::get_file_path_from_diff_git_line(line)
+    } else {
+        line.strip_prefix("diff --cc ")
+            .or_else(|| line.strip_prefix("diff --combined "))
+    }
+}
+
+/// Return the value of the delta-style attribute of `path`, as reported by `git check-attr`:
+/// e.g. "unspecified" if no .gitattributes line sets it. The empty string is returned if git
+/// could not be run.
+pub fn get_delta_style_attribute(path: &str, repo_root: Option<&Path>) -> String {
+    let mut command = process::Command::new("git");
+    command.args(["check-attr", DELTA_STYLE_ATTRIBUTE, "--", path]);
+    if let Some(repo_root) = repo_root {
+        command.current_dir(repo_root);
+    }
+    match command.stderr(process::Stdio::null()).output() {
+        Ok(output) if output.status.success() => {
+            parse_check_attr_output(&String::from_utf8_lossy(&output.stdout))
+                .unwrap_or("")
+                .to_string()
+        }
+        _ => "".to_string(),
+    }
+}
+
+/// Given output of `git check-attr` like "README.md: delta-style: markdown-preset", return
+/// "markdown-preset".
+fn parse_check_attr_output(output: &str) -> Option<&str> {
+    let line = output.lines().next()?;
+    let separator = format!(": {}: ", DELTA_STYLE_ATTRIBUTE);
+    let i = line.rfind(&separator)?;
+    Some(&line[i + separator.len()..])
+}
+
+/// Return the name of the preset given by a delta-style attribute value, if it names one, as
+/// opposed to being unspecified, or set or unset without a value.
+pub fn get_preset_from_attribute(value: &str) -> Option<&str> {
+    match value {
+        "" | "unspecified" | "set" | "unset" => None,
+        preset => Some(preset),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_check_attr_output() {
+        assert_eq!(
+            parse_check_attr_output("docs/a: b.md: delta-style: markdown-preset\n"),
+            Some("markdown-preset")
+        );
+        assert_eq!(
+            parse_check_attr_output("src/main.rs: delta-style: unspecified\n"),
+            Some("unspecified")
+        );
+        assert_eq!(parse_check_attr_output(""), None);
+        assert_eq!(get_preset_from_attribute("unspecified"), None);
+        assert_eq!(get_preset_from_attribute("sql"), Some("sql"));
+    }
+
+    #[test]
+    fn test_get_file_path_from_diff_line() {
+        assert_eq!(
+            get_file_path_from_diff_line("diff --git a/README.md b/README.md"),
+            Some("README.md")
+        );
+        assert_eq!(
+            get_file_path_from_diff_line("diff --cc src/main.rs"),
+            Some("src/main.rs")
+        );
+        assert_eq!(get_file_path_from_diff_line("commit 94907c0"), None);
+    }
+}
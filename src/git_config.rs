@@ -4,9 +4,14 @@ use std::path::Path;
 use std::process;
 
 use git2;
+use structopt::clap;
+
+use crate::config_file::ConfigFile;
 
 pub struct GitConfig {
-    config: git2::Config,
+    config: Option<git2::Config>,
+    // The delta config file (--config), whose values take precedence over git config.
+    config_file: Option<ConfigFile>,
 }
 
 impl GitConfig {
@@ -19,7 +24,10 @@ impl GitConfig {
                             eprintln!("Failed to read git config: {}", err);
                             process::exit(1)
                         });
-                        Some(Self { config })
+                        Some(Self {
+                            config: Some(config),
+                            config_file: None,
+                        })
                     }
                     Err(_) => None,
                 },
@@ -29,19 +37,46 @@ impl GitConfig {
         }
     }
 
+    /// As `try_create`, but under --no-gitconfig return None without reading git config at all,
+    /// so that e.g. an unreadable git config file is not an error.
+    pub fn try_create_unless_disabled(arg_matches: &clap::ArgMatches) -> Option<Self> {
+        if arg_matches.is_present("no-gitconfig") {
+            None
+        } else {
+            Self::try_create()
+        }
+    }
+
     #[cfg(test)]
     pub fn from_path(path: &Path) -> Self {
         Self {
-            config: git2::Config::open(path).unwrap(),
+            config: Some(git2::Config::open(path).unwrap()),
+            config_file: None,
         }
     }
 
+    /// Create a config holding only the values in the delta config file.
+    pub fn from_config_file(config_file: ConfigFile) -> Self {
+        Self {
+            config: None,
+            config_file: Some(config_file),
+        }
+    }
+
+    pub fn set_config_file(&mut self, config_file: ConfigFile) {
+        self.config_file = Some(config_file);
+    }
+
     pub fn get<T>(&self, key: &str) -> Option<T>
     where
         T: GitConfigGet,
     {
         T::git_config_get(key, self)
     }
+
+    fn get_from_config_file(&self, key: &str) -> Option<&str> {
+        self.config_file.as_ref()?.get(key)
+    }
 }
 
 pub trait GitConfigGet {
@@ -52,18 +87,41 @@ pub trait GitConfigGet {
 
 impl GitConfigGet for String {
     fn git_config_get(key: &str, git_config: &GitConfig) -> Option<Self> {
-        git_config.config.get_string(key).ok()
+        match git_config.get_from_config_file(key) {
+            Some(value) => Some(value.to_string()),
+            None => git_config.config.as_ref()?.get_string(key).ok(),
+        }
     }
 }
 
 impl GitConfigGet for bool {
     fn git_config_get(key: &str, git_config: &GitConfig) -> Option<Self> {
-        git_config.config.get_bool(key).ok()
+        match git_config.get_from_config_file(key) {
+            Some(value) => value.parse().ok(),
+            None => git_config.config.as_ref()?.get_bool(key).ok(),
+        }
     }
 }
 
 impl GitConfigGet for i64 {
     fn git_config_get(key: &str, git_config: &GitConfig) -> Option<Self> {
-        git_config.config.get_i64(key).ok()
+        match git_config.get_from_config_file(key) {
+            Some(value) => value.parse().ok(),
+            None => git_config.config.as_ref()?.get_i64(key).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::*;
+    use crate::cli;
+
+    #[test]
+    fn test_try_create_unless_disabled() {
+        let arg_matches = cli::Opt::clap().get_matches_from(["delta", "--no-gitconfig"]);
+        assert!(GitConfig::try_create_unless_disabled(&arg_matches).is_none());
     }
 }
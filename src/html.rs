@@ -0,0 +1,341 @@
+//! Conversion of delta's output to a self-contained HTML document, for --output-format=html.
+//!
+//! The diff is rendered as usual and the ANSI escape sequences in the output are then replaced by
+//! `<span>` elements. Each distinct style is given a CSS class, which is reused wherever the
+//! style occurs. Styles that are the value of a delta option (e.g. --plus-style) are given the
+//! option's name as their class name.
+use ansi_term::{Color, Style};
+
+use crate::config::Config;
+
+const CSI: &str = "\x1b[";
+
+/// Return an HTML document displaying `ansi_output`, the output that delta writes to a terminal.
+pub fn ansi_to_html(ansi_output: &str, config: &Config) -> String {
+    let mut classes = StyleClasses::new(config);
+    let mut body = String::new();
+    let mut style = Style::new();
+    let mut rest = ansi_output;
+    while !rest.is_empty() {
+        let (text, escape_sequence) = match rest.find(CSI) {
+            Some(i) => (&rest[..i], Some(&rest[i + CSI.len()..])),
+            None => (rest, None),
+        };
+        if !text.is_empty() {
+            match classes.get_class(style) {
+                Some(class) => body.push_str(&format!(
+                    "<span class=\"{}\">{}</span>",
+                    class,
+                    escape_html(text)
+                )),
+                None => body.push_str(&escape_html(text)),
+            }
+        }
+        // A sequence ends at its first alphabetic character. Only SGR ("m") sequences change the
+        // style; others, such as "erase in line", are dropped.
+        rest = match escape_sequence.and_then(|sequence| {
+            Some((sequence, sequence.find(|c: char| c.is_ascii_alphabetic())?))
+        }) {
+            Some((sequence, end)) => {
+                if sequence[end..].starts_with('m') {
+                    style = apply_sgr_parameters(style, &sequence[..end]);
+                }
+                &sequence[end + 1..]
+            }
+            None => "",
+        };
+    }
+
+    let mut css = String::new();
+    if let Some(theme) = config.syntax_theme.as_ref() {
+        let mut pre_css = Vec::new();
+        if let Some(color) = theme.settings.foreground {
+            pre_css.push(format!(
+                "color: #{:02x}{:02x}{:02x}",
+                color.r, color.g, color.b
+            ));
+        }
+        if let Some(color) = theme.settings.background {
+            pre_css.push(format!(
+                "background-color: #{:02x}{:02x}{:02x}",
+                color.r, color.g, color.b
+            ));
+        }
+        css.push_str(&format!("pre {{ {} }}\n", pre_css.join("; ")));
+    }
+    for (class, style) in classes.get_used_classes() {
+        css.push_str(&format!(
+            ".{} {{ {} }}\n",
+            class,
+            get_css_declarations(style)
+        ));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>delta</title>\n\
+         <style>\n\
+         {}\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <pre>{}</pre>\n\
+         </body>\n\
+         </html>\n",
+        css, body
+    )
+}
+
+/// The CSS classes of the styles in the output.
+struct StyleClasses {
+    // The class name and style of each named style, and whether it has been used.
+    named_classes: Vec<(String, Style, bool)>,
+    // The styles without a name, in order of first use; their class names are s0, s1, etc.
+    unnamed_styles: Vec<Style>,
+}
+
+impl StyleClasses {
+    fn new(config: &Config) -> Self {
+        let mut named_classes: Vec<(String, Style, bool)> = Vec::new();
+        for (name, style) in config.get_named_styles() {
+            if !named_classes
+                .iter()
+                .any(|(_, named_style, _)| *named_style == style.ansi_term_style)
+            {
+                named_classes.push((name.to_string(), style.ansi_term_style, false));
+            }
+        }
+        Self {
+            named_classes,
+            unnamed_styles: Vec::new(),
+        }
+    }
+
+    /// Return the class name of `style`, or None if text in this style needs no markup.
+    fn get_class(&mut self, style: Style) -> Option<String> {
+        if style == Style::new() {
+            return None;
+        }
+        if let Some((name, _, is_used)) = self
+            .named_classes
+            .iter_mut()
+            .find(|(_, named_style, _)| *named_style == style)
+        {
+            *is_used = true;
+            return Some(name.clone());
+        }
+        let i = match self.unnamed_styles.iter().position(|s| *s == style) {
+            Some(i) => i,
+            None => {
+                self.unnamed_styles.push(style);
+                self.unnamed_styles.len() - 1
+            }
+        };
+        Some(format!("s{}", i))
+    }
+
+    fn get_used_classes(&self) -> Vec<(String, Style)> {
+        self.named_classes
+            .iter()
+            .filter(|(_, _, is_used)| *is_used)
+            .map(|(name, style, _)| (name.clone(), *style))
+            .chain(
+                self.unnamed_styles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, style)| (format!("s{}", i), *style)),
+            )
+            .collect()
+    }
+}
+
+/// Return the style resulting from applying the parameters of an SGR ("Select Graphic
+/// Rendition") escape sequence, e.g. "1;38;5;4", to `style`.
+pub fn apply_sgr_parameters(mut style: Style, parameters: &str) -> Style {
+    let parameters: Vec<u8> = parameters
+        .split(';')
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let mut parameters = parameters.iter().copied();
+    while let Some(parameter) = parameters.next() {
+        match parameter {
+            0 => style = Style::new(),
+            1 => style.is_bold = true,
+            2 => style.is_dimmed = true,
+            3 => style.is_italic = true,
+            4 => style.is_underline = true,
+            5 => style.is_blink = true,
+            7 => style.is_reverse = true,
+            8 => style.is_hidden = true,
+            9 => style.is_strikethrough = true,
+            22 => {
+                style.is_bold = false;
+                style.is_dimmed = false;
+            }
+            23 => style.is_italic = false,
+            24 => style.is_underline = false,
+            25 => style.is_blink = false,
+            27 => style.is_reverse = false,
+            28 => style.is_hidden = false,
+            29 => style.is_strikethrough = false,
+            30..=37 => style.foreground = Some(get_basic_color(parameter - 30)),
+            38 => style.foreground = get_extended_color(&mut parameters),
+            39 => style.foreground = None,
+            40..=47 => style.background = Some(get_basic_color(parameter - 40)),
+            48 => style.background = get_extended_color(&mut parameters),
+            49 => style.background = None,
+            90..=97 => style.foreground = Some(Color::Fixed(parameter - 90 + 8)),
+            100..=107 => style.background = Some(Color::Fixed(parameter - 100 + 8)),
+            _ => {}
+        }
+    }
+    style
+}
+
+fn get_basic_color(n: u8) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Purple,
+        6 => Color::Cyan,
+        _ => Color::White,
+    }
+}
+
+/// Read the color following a 38 or 48 parameter: either "5;n" or "2;r;g;b".
+fn get_extended_color<I: Iterator<Item = u8>>(parameters: &mut I) -> Option<Color> {
+    match parameters.next()? {
+        5 => Some(Color::Fixed(parameters.next()?)),
+        2 => Some(Color::RGB(
+            parameters.next()?,
+            parameters.next()?,
+            parameters.next()?,
+        )),
+        _ => None,
+    }
+}
+
+fn get_css_declarations(style: Style) -> String {
+    let (mut foreground, mut background) = (style.foreground, style.background);
+    if style.is_reverse {
+        std::mem::swap(&mut foreground, &mut background);
+    }
+    let mut declarations = Vec::new();
+    if let Some(color) = foreground {
+        declarations.push(format!("color: {}", get_css_color(color)));
+    }
+    if let Some(color) = background {
+        declarations.push(format!("background-color: {}", get_css_color(color)));
+    }
+    if style.is_bold {
+        declarations.push("font-weight: bold".to_string());
+    }
+    if style.is_dimmed {
+        declarations.push("opacity: 0.5".to_string());
+    }
+    if style.is_italic {
+        declarations.push("font-style: italic".to_string());
+    }
+    let text_decorations: Vec<&str> = [
+        (style.is_underline, "underline"),
+        (style.is_strikethrough, "line-through"),
+        (style.is_blink, "blink"),
+    ]
+    .iter()
+    .filter(|(is_set, _)| *is_set)
+    .map(|(_, decoration)| *decoration)
+    .collect();
+    if !text_decorations.is_empty() {
+        declarations.push(format!("text-decoration: {}", text_decorations.join(" ")));
+    }
+    if style.is_hidden {
+        declarations.push("visibility: hidden".to_string());
+    }
+    declarations.join("; ")
+}
+
+fn get_css_color(color: Color) -> String {
+    let (r, g, b) = match color {
+        Color::RGB(r, g, b) => (r, g, b),
+        Color::Fixed(n) => ansi_colours::rgb_from_ansi256(n),
+        Color::Black => ansi_colours::rgb_from_ansi256(0),
+        Color::Red => ansi_colours::rgb_from_ansi256(1),
+        Color::Green => ansi_colours::rgb_from_ansi256(2),
+        Color::Yellow => ansi_colours::rgb_from_ansi256(3),
+        Color::Blue => ansi_colours::rgb_from_ansi256(4),
+        Color::Purple => ansi_colours::rgb_from_ansi256(5),
+        Color::Cyan => ansi_colours::rgb_from_ansi256(6),
+        Color::White => ansi_colours::rgb_from_ansi256(7),
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils;
+
+    #[test]
+    fn test_apply_sgr_parameters() {
+        assert_eq!(
+            apply_sgr_parameters(Style::new(), "1;38;5;4"),
+            Style::new().bold().fg(Color::Fixed(4))
+        );
+        assert_eq!(
+            apply_sgr_parameters(Style::new().italic(), "48;2;0;40;0;31"),
+            Style::new()
+                .italic()
+                .on(Color::RGB(0, 40, 0))
+                .fg(Color::Red)
+        );
+        assert_eq!(apply_sgr_parameters(Style::new().bold(), "0"), Style::new());
+        assert_eq!(apply_sgr_parameters(Style::new().bold(), ""), Style::new());
+    }
+
+    #[test]
+    fn test_get_css_declarations() {
+        assert_eq!(
+            get_css_declarations(Style::new().bold().underline().fg(Color::RGB(255, 0, 0))),
+            "color: #ff0000; font-weight: bold; text-decoration: underline"
+        );
+        assert_eq!(
+            get_css_declarations(Style::new().reverse().fg(Color::Fixed(16))),
+            "background-color: #000000"
+        );
+    }
+
+    #[test]
+    fn test_ansi_to_html() {
+        let config = integration_test_utils::make_config(&["--plus-style", "bold green"]);
+        let plus = config.plus_style.ansi_term_style;
+        let other = Style::new().fg(Color::Fixed(100));
+        let ansi_output = format!(
+            "{} {}\x1b[K\n{}\n<a & b>\n",
+            plus.paint("+x"),
+            other.paint("y"),
+            plus.paint("+z")
+        );
+        let html = ansi_to_html(&ansi_output, &config);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains(".plus-style { color: #00ce00; font-weight: bold }\n"));
+        assert!(html.contains(".s0 { color: #878700 }\n"));
+        assert!(html.contains(
+            "<pre><span class=\"plus-style\">+x</span> <span class=\"s0\">y</span>\n\
+             <span class=\"plus-style\">+z</span>\n\
+             &lt;a &amp; b&gt;\n</pre>"
+        ));
+        assert!(!html.contains(".minus-style"));
+    }
+}
@@ -0,0 +1,160 @@
+//! delta is a syntax-highlighting pager for git. In addition to the `delta` executable, this
+//! crate provides a library interface, so that other programs can render diffs as delta does
+//! without running it as a subprocess:
+//!
+//! ```
+//! let config = delta::config::Config::from_args(&["--no-gitconfig"], &mut None);
+//! let output = delta::render_diff("--- a.txt\n+++ b.txt\n@@ -1 +1 @@\n-a\n+b\n", &config);
+//! ```
+//!
+//! or, to consume the output one line at a time as the input is read, with [`DeltaIterator`].
+extern crate bitflags;
+
+#[macro_use]
+extern crate error_chain;
+
+mod align;
+pub mod bat;
+mod binary;
+pub mod check;
+pub mod cli;
+mod color;
+pub mod config;
+mod config_file;
+pub mod delta;
+mod diff_conversion;
+mod draw;
+mod edits;
+mod env;
+pub mod experimental;
+pub mod external_diff;
+mod git_attributes;
+pub mod git_config;
+pub mod html;
+mod line_numbers;
+mod paint;
+mod parse;
+pub mod post_process;
+mod preset;
+mod rewrite_options;
+mod set_options;
+pub mod style;
+pub mod syntax_theme;
+mod syntect_color;
+mod terminal;
+mod tests;
+mod utils;
+
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Write};
+use std::rc::Rc;
+
+use bytelines::ByteLines;
+
+mod errors {
+    error_chain! {
+        foreign_links {
+            Io(::std::io::Error);
+            SyntectError(::syntect::LoadingError);
+            ParseIntError(::std::num::ParseIntError);
+        }
+    }
+}
+
+/// Render `input` (e.g. the output of `git diff`) as delta would display it, and return the
+/// result, including ANSI escape sequences. The output does not depend on whether stdout is a
+/// terminal; to make it fully deterministic, create the config with explicit `--width` and
+/// `--24-bit-color` arguments.
+///
+/// # Panics
+///
+/// Panics if the config requests output files (--diff-split-output) that cannot be written.
+pub fn render_diff(input: &str, config: &config::Config) -> String {
+    let mut output: Vec<u8> = Vec::new();
+    delta::delta(
+        ByteLines::new(BufReader::new(input.as_bytes())),
+        &mut output,
+        config,
+    )
+    .unwrap_or_else(|err| panic!("Failed to render diff: {}", err));
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// An iterator over the lines of delta's rendering of `input`, without their trailing newlines.
+/// Each call to `next` reads only as much input as is needed to complete the next line of output.
+/// Note that delta holds back the lines of a run of removed and added lines until the run ends, in
+/// order to pair them for within-line edit highlighting.
+///
+/// # Panics
+///
+/// Panics if the config requests output files (--diff-split-output) that cannot be written.
+pub struct DeltaIterator<'a, I: BufRead> {
+    lines: ByteLines<I>,
+    // None once the input has been consumed.
+    state_machine: Option<delta::StateMachine<'a>>,
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl<'a, I: BufRead> DeltaIterator<'a, I> {
+    pub fn new(input: I, config: &'a config::Config) -> Self {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let state_machine =
+            delta::StateMachine::new(Box::new(SharedBuffer(Rc::clone(&output))), config)
+                .unwrap_or_else(|err| panic!("Failed to render diff: {}", err));
+        Self {
+            lines: ByteLines::new(input),
+            state_machine: Some(state_machine),
+            output,
+        }
+    }
+}
+
+impl<'a, I: BufRead> Iterator for DeltaIterator<'a, I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            {
+                let mut output = self.output.borrow_mut();
+                if let Some(i) = output.iter().position(|byte| *byte == b'\n') {
+                    let line: Vec<u8> = output.drain(..=i).collect();
+                    return Some(String::from_utf8_lossy(&line[..i]).into_owned());
+                }
+                if self.state_machine.is_none() {
+                    // The last line of output, if it has no trailing newline.
+                    if output.is_empty() {
+                        return None;
+                    }
+                    let line: Vec<u8> = output.drain(..).collect();
+                    return Some(String::from_utf8_lossy(&line).into_owned());
+                }
+            }
+            let state_machine = self.state_machine.as_mut().unwrap();
+            let is_reading = match self.lines.next() {
+                Some(Ok(raw_line_bytes)) => state_machine.handle_line(raw_line_bytes),
+                _ => Ok(false),
+            }
+            .unwrap_or_else(|err| panic!("Failed to render diff: {}", err));
+            if !is_reading {
+                self.state_machine
+                    .take()
+                    .unwrap()
+                    .finish()
+                    .unwrap_or_else(|err| panic!("Failed to render diff: {}", err));
+            }
+        }
+    }
+}
+
+/// A writer appending to a buffer that is shared with the DeltaIterator reading from it.
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Formatting of the line number columns (--number) using a single format string for both
+//! columns (--line-numbers-format).
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The part of the line number columns that a piece of formatted text belongs to, which
+/// determines its style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineNumbersPart {
+    MinusFormat,
+    MinusNumber,
+    PlusFormat,
+    PlusNumber,
+}
+
+lazy_static! {
+    static ref LINE_NUMBERS_PLACEHOLDER_REGEXP: Regex =
+        Regex::new(r"\{(?P<name>nm|np)(?::(?P<align>[<^>])?(?P<width>\d+))?\}").unwrap();
+}
+
+/// Substitute the minus and plus line numbers for the {nm} and {np} placeholders in `format`.
+/// A placeholder may specify an alignment and width as in Rust format strings, e.g. {nm:>3};
+/// the default is {nm:^4} or, given `right_align_width` (--number-right-align), right alignment
+/// in a field of that width. A missing line number is replaced by spaces. The text between the
+/// placeholders belongs to the minus column up to {np}, and to the plus column after it.
+pub fn format_line_numbers(
+    format: &str,
+    minus_number: Option<usize>,
+    plus_number: Option<usize>,
+    right_align_width: Option<usize>,
+) -> Vec<(LineNumbersPart, String)> {
+    let mut parts = Vec::new();
+    let mut format_part = LineNumbersPart::MinusFormat;
+    let mut offset = 0;
+    for captures in LINE_NUMBERS_PLACEHOLDER_REGEXP.captures_iter(format) {
+        let placeholder = captures.get(0).unwrap();
+        let (number_part, number, next_format_part) = match &captures["name"] {
+            "nm" => (
+                LineNumbersPart::MinusNumber,
+                minus_number,
+                LineNumbersPart::MinusFormat,
+            ),
+            _ => (
+                LineNumbersPart::PlusNumber,
+                plus_number,
+                LineNumbersPart::PlusFormat,
+            ),
+        };
+        if placeholder.start() > offset {
+            parts.push((format_part, format[offset..placeholder.start()].to_string()));
+        }
+        let width = captures
+            .name("width")
+            .and_then(|width| width.as_str().parse::<usize>().ok())
+            .or(right_align_width)
+            .unwrap_or(4);
+        let number = number.map(|n| n.to_string()).unwrap_or_default();
+        let align = captures.name("align").map(|align| align.as_str());
+        let number = match align.or(right_align_width.map(|_| ">")) {
+            Some("<") => format!("{:<width$}", number, width = width),
+            Some(">") => format!("{:>width$}", number, width = width),
+            _ => format!("{:^width$}", number, width = width),
+        };
+        parts.push((number_part, number));
+        format_part = next_format_part;
+        offset = placeholder.end();
+    }
+    if offset < format.len() {
+        parts.push((format_part, format[offset..].to_string()));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineNumbersPart::*;
+    use super::*;
+
+    #[test]
+    fn test_format_line_numbers() {
+        assert_eq!(
+            format_line_numbers("{nm:>3}│{np:>3}│", Some(8), Some(9), None),
+            vec![
+                (MinusNumber, "  8".to_string()),
+                (MinusFormat, "│".to_string()),
+                (PlusNumber, "  9".to_string()),
+                (PlusFormat, "│".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_line_numbers_missing_number_and_default_width() {
+        assert_eq!(
+            format_line_numbers("[{nm}|{np:<3}]", None, Some(10), None),
+            vec![
+                (MinusFormat, "[".to_string()),
+                (MinusNumber, "    ".to_string()),
+                (MinusFormat, "|".to_string()),
+                (PlusNumber, "10 ".to_string()),
+                (PlusFormat, "]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_line_numbers_right_aligned() {
+        assert_eq!(
+            format_line_numbers("{nm}⋮{np:^4}│", Some(9), Some(10), Some(3)),
+            vec![
+                (MinusNumber, "  9".to_string()),
+                (MinusFormat, "⋮".to_string()),
+                (PlusNumber, " 10 ".to_string()),
+                (PlusFormat, "│".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_line_numbers_without_placeholders() {
+        assert_eq!(
+            format_line_numbers("{x} ", Some(1), Some(1), None),
+            vec![(MinusFormat, "{x} ".to_string())]
+        );
+    }
+}
@@ -1,69 +1,76 @@
-extern crate bitflags;
-
-#[macro_use]
-extern crate error_chain;
-
-mod align;
-mod bat;
-mod cli;
-mod color;
-mod config;
-mod delta;
-mod draw;
-mod edits;
-mod env;
-mod git_config;
-mod paint;
-mod parse;
-mod preset;
-mod rewrite_options;
-mod set_options;
-mod style;
-mod syntax_theme;
-mod syntect_color;
-mod tests;
-
-use std::io::{self, ErrorKind, Read, Write};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Cursor, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use ansi_term::{self, Color};
 use atty;
-use bytelines::ByteLinesReader;
-use structopt::StructOpt;
-
-use crate::bat::assets::{list_languages, HighlightingAssets};
-use crate::bat::output::{OutputType, PagingMode};
-use crate::delta::delta;
-
-mod errors {
-    error_chain! {
-        foreign_links {
-            Io(::std::io::Error);
-            SyntectError(::syntect::LoadingError);
-            ParseIntError(::std::num::ParseIntError);
-        }
-    }
-}
+use bytelines::{ByteLines, ByteLinesReader};
+use structopt::{clap, StructOpt};
+
+use delta::bat::assets::{list_languages, HighlightingAssets};
+use delta::bat::output::{OutputType, PagingMode};
+use delta::config::OutputFormat;
+use delta::delta::{delta, delta_with_reload};
+use delta::external_diff::{self, ExternalDiffArgs};
+use delta::{check, cli, config, experimental, git_config, html, post_process, syntax_theme};
 
 fn main() -> std::io::Result<()> {
-    let config = config::Config::from_arg_matches(
-        cli::Opt::clap().get_matches(),
-        &mut git_config::GitConfig::try_create(),
-    );
+    // Under GIT_EXTERNAL_DIFF=delta, git passes seven arguments describing the change, which are
+    // not delta options.
+    let args: Vec<_> = env::args_os().collect();
+    let (args, external_diff_args) = match external_diff::parse_external_diff_args(&args) {
+        Some((args, external_diff_args)) => (args, Some(external_diff_args)),
+        None => (args, None),
+    };
+    let arg_matches = cli::Opt::clap().get_matches_from(args);
+    let config = make_config(&arg_matches);
 
-    if config.list_languages {
+    if let Some(external_diff_args) = external_diff_args {
+        return external_diff_mode(&external_diff_args, &config);
+    } else if config.debug_config_diff {
+        show_config_diff(&config, arg_matches);
+        process::exit(0);
+    } else if config.list_experimental_features {
+        print!("{}", experimental::format_experimental_features_list());
+        process::exit(0);
+    } else if config.list_languages {
         list_languages()?;
         process::exit(0);
     } else if config.list_syntax_theme_names {
-        list_syntax_theme_names()?;
+        list_syntax_theme_names(&config)?;
         process::exit(0);
     } else if config.list_syntax_themes {
-        list_syntax_themes()?;
+        list_syntax_themes(&config)?;
         process::exit(0);
     } else if config.show_background_colors {
         show_background_colors(&config);
         process::exit(0);
+    } else if config.show_colors {
+        show_colors(&config);
+        process::exit(0);
+    } else if let Some(path) = &config.check_file {
+        check_diff_file(path);
+    } else if let Some((old_file, new_file)) = &config.from_two_files {
+        return diff(Some(old_file), Some(new_file), &config);
+    } else if let Some(diff_args) = &config.diff_args {
+        return git_diff(diff_args, &config);
+    } else if let Some(path) = config
+        .minus_file
+        .as_ref()
+        .filter(|_| config.merged_diff || config.rebase_mode)
+    {
+        let file = File::open(path).unwrap_or_else(|err| {
+            eprintln!("Failed to open {}: {}", path.display(), err);
+            process::exit(1);
+        });
+        return display(
+            io::BufReader::with_capacity(config.io_buffer_size_kb * 1024, file).byte_lines(),
+            &config,
+        );
     } else if atty::is(atty::Stream::Stdin) {
         return diff(
             config.minus_file.as_ref(),
@@ -72,51 +79,231 @@ fn main() -> std::io::Result<()> {
         );
     }
 
-    let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
-    let mut writer = output_type.handle().unwrap();
+    // A long-running delta, e.g. one kept alive by an editor plugin, rereads its configuration
+    // on SIGHUP.
+    install_sighup_handler();
+    // Under --per-file-git-attributes, a file whose delta-style git attribute names a preset is
+    // displayed with a config in which that preset is activated. The config made for each preset
+    // is kept for the following files, until a reload discards them. Configs made here live
+    // until delta exits.
+    let mut file_preset: Option<String> = None;
+    let mut configs: HashMap<Option<String>, &config::Config> = HashMap::new();
+    configs.insert(None, &config);
+    let mut reload_config = |preset: Option<&str>| {
+        let is_reload_requested = RELOAD_REQUESTED.swap(false, Ordering::SeqCst);
+        if !is_reload_requested && preset == file_preset.as_deref() {
+            return None;
+        }
+        if is_reload_requested {
+            configs.clear();
+        }
+        file_preset = preset.map(str::to_string);
+        let config: &config::Config = configs.entry(file_preset.clone()).or_insert_with(|| {
+            Box::leak(Box::new(make_config_with_file_preset(&arg_matches, preset)))
+        });
+        Some(config)
+    };
+    let stdin = io::BufReader::with_capacity(config.io_buffer_size_kb * 1024, io::stdin().lock());
+    display_with_reload(stdin.byte_lines(), &config, &mut reload_config)
+}
+
+fn make_config(arg_matches: &clap::ArgMatches<'static>) -> config::Config<'static> {
+    make_config_with_file_preset(arg_matches, None)
+}
+
+fn make_config_with_file_preset(
+    arg_matches: &clap::ArgMatches<'static>,
+    file_preset: Option<&str>,
+) -> config::Config<'static> {
+    let mut git_config = git_config::GitConfig::try_create_unless_disabled(arg_matches);
+    config::Config::from_arg_matches_with_file_preset(
+        arg_matches.clone(),
+        &mut git_config,
+        file_preset,
+    )
+}
+
+/// Report the problems found in the diff in `path` under --check, and exit with status 1 if
+/// there are any.
+fn check_diff_file(path: &Path) -> ! {
+    let input = fs::read(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        process::exit(1);
+    });
+    let errors = check::check_diff(&String::from_utf8_lossy(&input));
+    for error in &errors {
+        eprintln!("{}: {}", path.display(), error);
+    }
+    process::exit(if errors.is_empty() { 0 } else { 1 })
+}
+
+/// Set by the SIGHUP handler, and cleared when the configuration has been reread.
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+fn install_sighup_handler() {
+    extern "C" fn handle_sighup(_signal: libc::c_int) {
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+    }
+    unsafe {
+        libc::signal(
+            libc::SIGHUP,
+            handle_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn install_sighup_handler() {}
+
+/// Render the diff and write it to stdout, or to the pager.
+fn display<I: BufRead>(lines: ByteLines<I>, config: &config::Config) -> std::io::Result<()> {
+    display_with_reload(lines, config, &mut |_| None)
+}
 
-    if let Err(error) = delta(io::stdin().lock().byte_lines(), &mut writer, &config) {
-        match error.kind() {
+/// As `display`, but with the configuration replaced when `reload_config` returns a new one (see
+/// delta::delta_with_reload).
+fn display_with_reload<'c, I: BufRead>(
+    lines: ByteLines<I>,
+    config: &'c config::Config<'c>,
+    reload_config: &mut dyn FnMut(Option<&str>) -> Option<&'c config::Config<'c>>,
+) -> std::io::Result<()> {
+    let result = if config.pager_quit_if_smaller_than.is_some() {
+        // Whether to use the pager depends on the length of the output, so it is rendered first.
+        let mut output = Vec::new();
+        render_output(lines, &mut output, config, reload_config).and_then(|status| {
+            let n_output_lines = output.iter().filter(|byte| **byte == b'\n').count();
+            let mut output_type = OutputType::from_mode_for_output_size(
+                config.paging_mode,
+                None,
+                n_output_lines,
+                config,
+            )
+            .unwrap();
+            output_type
+                .handle()
+                .unwrap()
+                .write_all(&output)
+                .map(|_| status)
+        })
+    } else {
+        let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+        let writer = output_type.handle().unwrap();
+        render_output(lines, writer, config, reload_config)
+    };
+    match result {
+        // Delta exits with the status of a failed --post-process-cmd.
+        Ok(Some(status)) if !status.success() => process::exit(status.code().unwrap_or(1)),
+        Ok(_) => {}
+        Err(error) => match error.kind() {
             ErrorKind::BrokenPipe => process::exit(0),
             _ => eprintln!("{}", error),
-        }
+        },
     };
     Ok(())
 }
 
+/// Render the diff, passing it through the command given by --post-process-cmd if there is one.
+/// Return the exit status of the command.
+fn render_output<'c, I: BufRead>(
+    lines: ByteLines<I>,
+    writer: &mut dyn Write,
+    config: &'c config::Config<'c>,
+    reload_config: &mut dyn FnMut(Option<&str>) -> Option<&'c config::Config<'c>>,
+) -> std::io::Result<Option<process::ExitStatus>> {
+    match config.post_process_cmd.as_deref() {
+        Some(post_process_cmd) => {
+            post_process::write_post_processed_output(post_process_cmd, writer, |writer| {
+                write_output(lines, writer, config, reload_config)
+            })
+            .map(Some)
+        }
+        None => write_output(lines, writer, config, reload_config).map(|_| None),
+    }
+}
+
+/// Render the diff in the format given by --output-format.
+fn write_output<'c, I: BufRead>(
+    lines: ByteLines<I>,
+    writer: &mut dyn Write,
+    config: &'c config::Config<'c>,
+    reload_config: &mut dyn FnMut(Option<&str>) -> Option<&'c config::Config<'c>>,
+) -> std::io::Result<()> {
+    match config.output_format {
+        OutputFormat::Terminal => delta_with_reload(lines, writer, config, reload_config),
+        OutputFormat::Html => {
+            let mut output = Vec::new();
+            delta_with_reload(lines, &mut output, config, reload_config).and_then(|_| {
+                let output = String::from_utf8_lossy(&output);
+                write!(writer, "{}", html::ansi_to_html(&output, config))
+            })
+        }
+    }
+}
+
 /// Run `diff -u` on the files provided on the command line and display the output.
 fn diff(
     minus_file: Option<&PathBuf>,
     plus_file: Option<&PathBuf>,
     config: &config::Config,
 ) -> std::io::Result<()> {
-    use std::io::BufReader;
     let die = || {
         eprintln!("Usage: delta minus_file plus_file");
         process::exit(1);
     };
-    let diff_process = process::Command::new(PathBuf::from("diff"))
-        .arg("-u")
-        .args(&[
-            minus_file.unwrap_or_else(die),
-            plus_file.unwrap_or_else(die),
-        ])
-        .stdout(process::Stdio::piped())
-        .spawn();
-
-    let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
-    let mut writer = output_type.handle().unwrap();
-    if let Err(error) = delta(
-        BufReader::new(diff_process.unwrap().stdout.unwrap()).byte_lines(),
-        &mut writer,
-        &config,
-    ) {
-        match error.kind() {
-            ErrorKind::BrokenPipe => process::exit(0),
-            _ => eprintln!("{}", error),
+    let (minus_file, plus_file) = (
+        minus_file.unwrap_or_else(die),
+        plus_file.unwrap_or_else(die),
+    );
+    for path in &[minus_file, plus_file] {
+        if let Err(err) = fs::metadata(path) {
+            eprintln!("Failed to open {}: {}", path.display(), err);
+            process::exit(1);
         }
-    };
-    Ok(())
+    }
+    let mut diff_command = process::Command::new(PathBuf::from("diff"));
+    diff_command.arg("-u").args([minus_file, plus_file]);
+    display_command_output(diff_command, config)
+}
+
+/// Display the change that git describes when it invokes delta as an external diff program.
+fn external_diff_mode(args: &ExternalDiffArgs, config: &config::Config) -> std::io::Result<()> {
+    let input = external_diff::get_external_diff(args).unwrap_or_else(|err| {
+        eprintln!("Failed to run diff: {}", err);
+        process::exit(1);
+    });
+    display(Cursor::new(input).byte_lines(), config)
+}
+
+/// Run `git diff` with the arguments given by --diff-args and display the output.
+fn git_diff(diff_args: &[String], config: &config::Config) -> std::io::Result<()> {
+    let mut git_command = process::Command::new(PathBuf::from("git"));
+    git_command
+        .arg("diff")
+        .arg(if config.indent_heuristic {
+            "--indent-heuristic"
+        } else {
+            "--no-indent-heuristic"
+        })
+        .args(diff_args);
+    display_command_output(git_command, config)
+}
+
+/// Run a command producing a diff and display its output.
+fn display_command_output(
+    mut command: process::Command,
+    config: &config::Config,
+) -> std::io::Result<()> {
+    use std::io::BufReader;
+    let diff_process = command.stdout(process::Stdio::piped()).spawn();
+    display(
+        BufReader::with_capacity(
+            config.io_buffer_size_kb * 1024,
+            diff_process.unwrap().stdout.unwrap(),
+        )
+        .byte_lines(),
+        config,
+    )
 }
 
 fn show_background_colors(config: &config::Config) {
@@ -136,15 +323,59 @@ fn show_background_colors(config: &config::Config) {
     )
 }
 
+/// Print the settings that git config changes, compared to the config obtained without it.
+fn show_config_diff(config: &config::Config, arg_matches: clap::ArgMatches) {
+    let config_without_git_config = config::Config::from_arg_matches(arg_matches, &mut None);
+    for diff in config_without_git_config.diff(config) {
+        println!("{}", diff.field);
+        println!("    without git config: {}", diff.left);
+        println!("    with git config:    {}", diff.right);
+    }
+}
+
+fn show_colors(config: &config::Config) {
+    for (name, style) in config.get_named_styles() {
+        let line = format!(
+            "{}{}  foreground {}  background {}",
+            style.ansi_term_style.paint(name),
+            " ".repeat(26 - name.len()),
+            color_swatch(style.ansi_term_style.foreground),
+            color_swatch(style.ansi_term_style.background),
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+/// A block painted in `color`, followed by the color's name or code.
+fn color_swatch(color: Option<Color>) -> String {
+    match color {
+        Some(color) => {
+            let description = match color {
+                Color::RGB(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+                Color::Fixed(n) => n.to_string(),
+                color => format!("{:?}", color).to_lowercase(),
+            };
+            format!(
+                "{} {:<7}",
+                ansi_term::Style::new().on(color).paint("   "),
+                description
+            )
+        }
+        None => format!("{:<11}", "none"),
+    }
+}
+
 fn get_painted_rgb_string(color: Color) -> String {
     color.paint(format!("{:?}", color)).to_string()
 }
 
-fn list_syntax_themes() -> std::io::Result<()> {
+fn list_syntax_themes(config: &config::Config) -> std::io::Result<()> {
     use bytelines::ByteLines;
     use std::io::BufReader;
     let opt = cli::Opt::from_args();
-    let input = if !atty::is(atty::Stream::Stdin) {
+    let input = if let Some(path) = config.theme_preview_diff_path.as_deref() {
+        read_theme_preview_diff(path)
+    } else if !atty::is(atty::Stream::Stdin) {
         let mut buf = Vec::new();
         io::stdin().lock().read_to_end(&mut buf)?;
         buf
@@ -171,7 +402,7 @@ index f38589a..0f1bb83 100644
     let mut stdout = stdout.lock();
     let style = ansi_term::Style::new().bold();
 
-    let assets = HighlightingAssets::new();
+    let assets = make_highlighting_assets(config);
 
     for (syntax_theme, _) in assets.theme_set.themes.iter() {
         if opt.light && !syntax_theme::is_light_theme(syntax_theme)
@@ -181,17 +412,18 @@ index f38589a..0f1bb83 100644
         }
 
         writeln!(stdout, "\n\nTheme: {}\n", style.paint(syntax_theme))?;
-        let config = config::Config::from_args(
-            &[
-                "--syntax-theme",
-                syntax_theme,
-                "--file-style",
-                "omit",
-                "--hunk-header-style",
-                "omit",
-            ],
-            &mut None,
-        );
+        let mut args = vec![
+            "--syntax-theme",
+            syntax_theme,
+            "--file-style",
+            "omit",
+            "--hunk-header-style",
+            "omit",
+        ];
+        if let Some(theme_set_dir) = config.theme_set_dir.as_deref() {
+            args.extend(&["--theme-set-dir", theme_set_dir]);
+        }
+        let config = config::Config::from_args(&args, &mut None);
         let mut output_type =
             OutputType::from_mode(PagingMode::QuitIfOneScreen, None, &config).unwrap();
         let mut writer = output_type.handle().unwrap();
@@ -210,8 +442,39 @@ index f38589a..0f1bb83 100644
     Ok(())
 }
 
-pub fn list_syntax_theme_names() -> std::io::Result<()> {
-    let assets = HighlightingAssets::new();
+/// Read the diff given by --theme-preview-diff, exiting with an error if it is not a well-formed
+/// diff (see check::check_diff), so that the themes are not demonstrated on some other input.
+fn read_theme_preview_diff(path: &Path) -> Vec<u8> {
+    let input = fs::read(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        process::exit(1);
+    });
+    let errors = check::check_diff(&String::from_utf8_lossy(&input));
+    if !errors.is_empty() {
+        eprintln!(
+            "Invalid value for --theme-preview-diff option: {} is not a well-formed diff",
+            path.display()
+        );
+        for error in &errors {
+            eprintln!("{}: {}", path.display(), error);
+        }
+        process::exit(1);
+    }
+    input
+}
+
+/// The built-in syntax themes, together with any loaded from --theme-set-dir.
+fn make_highlighting_assets(config: &config::Config) -> HighlightingAssets {
+    let mut assets = HighlightingAssets::new();
+    if let Some(theme_set_dir) = config.theme_set_dir.as_deref() {
+        // The themes were loaded successfully when the config was created.
+        syntax_theme::add_themes_from_dir(&mut assets.theme_set, Path::new(theme_set_dir)).ok();
+    }
+    assets
+}
+
+pub fn list_syntax_theme_names(config: &config::Config) -> std::io::Result<()> {
+    let assets = make_highlighting_assets(config);
     let themes = &assets.theme_set.themes;
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
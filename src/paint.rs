@@ -1,15 +1,19 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::collections::HashSet;
 use std::io::Write;
+use std::iter;
 
 use ansi_term;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::Style as SyntectStyle;
-use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use unicode_width::UnicodeWidthStr;
 
 use crate::config;
 use crate::delta::State;
 use crate::edits;
+use crate::line_numbers::{self, LineNumbersPart};
 use crate::paint::superimpose_style_sections::superimpose_style_sections;
 use crate::style::Style;
 
@@ -19,35 +23,88 @@ pub const ANSI_SGR_RESET: &str = "\x1b[0m";
 pub struct Painter<'a> {
     pub minus_lines: Vec<String>,
     pub plus_lines: Vec<String>,
-    pub writer: &'a mut dyn Write,
+    // Under --show-whitespace-errors, whether the indentation of each buffered plus line contains
+    // both spaces and tabs. Tabs have been expanded in the buffered lines themselves.
+    pub plus_lines_indentation_is_mixed: Vec<bool>,
+    // Under --empty-line-context, the current run of blank unchanged lines.
+    pub blank_zero_lines: Vec<String>,
+    // Under --highlight-copied-lines, the lines of the current hunk that occur both as removed and
+    // as added lines, as prepared for painting.
+    pub moved_lines: HashSet<String>,
+    // Under --color-moved, the style of each buffered line that git marked as moved.
+    pub minus_lines_color_moved_styles: Vec<Option<Style>>,
+    pub plus_lines_color_moved_styles: Vec<Option<Style>>,
+    pub writer: Box<dyn Write + 'a>,
     pub syntax: &'a SyntaxReference,
     pub highlighter: HighlightLines<'a>,
+    // Under --show-syntax-errors, the state of a second parser of the syntax definition, which
+    // reads the same lines as the highlighter.
+    pub syntax_error_state: Option<(ParseState, ScopeStack)>,
     pub config: &'a config::Config<'a>,
     pub output_buffer: String,
     pub minus_line_number: usize,
     pub plus_line_number: usize,
+    // Under --number-right-align, the number of digits of the largest line number in the current
+    // hunk.
+    pub line_number_width: usize,
+    pub minus_file_is_null: bool,
+    pub plus_file_is_null: bool,
+    // The number of bytes of hunk lines seen so far in the current file, for --max-file-size.
+    pub file_hunk_bytes: u64,
+    // Under --minus-ruler and --plus-ruler, whether removed and added lines have been painted in
+    // the current hunk.
+    pub hunk_has_painted_minus_lines: bool,
+    pub hunk_has_painted_plus_lines: bool,
 }
 
 impl<'a> Painter<'a> {
-    pub fn new(writer: &'a mut dyn Write, config: &'a config::Config) -> Self {
+    pub fn new(writer: Box<dyn Write + 'a>, config: &'a config::Config) -> Self {
         let default_syntax = Self::get_syntax(&config.syntax_set, None);
         // TODO: Avoid doing this.
         let dummy_highlighter = HighlightLines::new(default_syntax, &config.syntax_dummy_theme);
         Self {
             minus_lines: Vec::new(),
             plus_lines: Vec::new(),
+            plus_lines_indentation_is_mixed: Vec::new(),
+            blank_zero_lines: Vec::new(),
+            moved_lines: HashSet::new(),
+            minus_lines_color_moved_styles: Vec::new(),
+            plus_lines_color_moved_styles: Vec::new(),
             output_buffer: String::new(),
             syntax: default_syntax,
             highlighter: dummy_highlighter,
+            syntax_error_state: None,
             writer,
             config,
             minus_line_number: 0,
             plus_line_number: 0,
+            line_number_width: 4,
+            minus_file_is_null: false,
+            plus_file_is_null: false,
+            file_hunk_bytes: 0,
+            hunk_has_painted_minus_lines: false,
+            hunk_has_painted_plus_lines: false,
         }
     }
 
+    /// Paint the following lines with `config`. The syntax is reset, as at the start of the
+    /// input, until the next file sets it.
+    pub fn set_config(&mut self, config: &'a config::Config<'a>) {
+        self.config = config;
+        self.syntax = Self::get_syntax(&config.syntax_set, None);
+        self.highlighter = HighlightLines::new(self.syntax, &config.syntax_dummy_theme);
+        self.syntax_error_state = None;
+    }
+
     pub fn set_syntax(&mut self, extension: Option<&str>) {
-        self.syntax = Painter::get_syntax(&self.config.syntax_set, extension);
+        self.syntax = match self.config.language.as_deref() {
+            Some(language) => self
+                .config
+                .syntax_set
+                .find_syntax_by_token(language)
+                .unwrap_or_else(|| Painter::get_syntax(&self.config.syntax_set, None)),
+            None => Painter::get_syntax(&self.config.syntax_set, extension),
+        };
     }
 
     fn get_syntax(syntax_set: &'a SyntaxSet, extension: Option<&str>) -> &'a SyntaxReference {
@@ -60,23 +117,138 @@ impl<'a> Painter<'a> {
         if let Some(ref syntax_theme) = self.config.syntax_theme {
             self.highlighter = HighlightLines::new(self.syntax, &syntax_theme)
         };
+        if self.config.show_syntax_errors {
+            self.syntax_error_state = Some((ParseState::new(self.syntax), ScopeStack::new()));
+        }
+    }
+
+    /// Whether the current file has exceeded --max-file-size, so that its remaining lines should
+    /// be painted without syntax highlighting or edit inference.
+    pub fn exceeds_max_file_size(&self) -> bool {
+        match self.config.max_file_size {
+            Some(max_file_size) => self.file_hunk_bytes > max_file_size,
+            None => false,
+        }
     }
 
     pub fn paint_buffered_lines(&mut self) {
-        let minus_line_syntax_style_sections = Self::get_syntax_style_sections_for_lines(
-            &self.minus_lines,
-            &State::HunkMinus,
-            &mut self.highlighter,
-            self.config,
+        self.paint_blank_zero_lines();
+        if self.config.show_token_types {
+            self.paint_buffered_lines_token_types();
+            return;
+        }
+        let (
+            mut minus_line_syntax_style_sections,
+            mut plus_line_syntax_style_sections,
+            (mut minus_line_diff_style_sections, mut plus_line_diff_style_sections),
+        ) = if self.exceeds_max_file_size() {
+            (
+                Self::get_plain_style_sections(&self.minus_lines, self.config.null_syntect_style),
+                Self::get_plain_style_sections(&self.plus_lines, self.config.null_syntect_style),
+                (
+                    Self::get_plain_style_sections(&self.minus_lines, self.config.minus_style),
+                    Self::get_plain_style_sections(&self.plus_lines, self.config.plus_style),
+                ),
+            )
+        } else {
+            (
+                Self::get_syntax_style_sections_for_lines(
+                    &self.minus_lines,
+                    &State::HunkMinus,
+                    &mut self.highlighter,
+                    self.config,
+                ),
+                Self::get_syntax_style_sections_for_lines(
+                    &self.plus_lines,
+                    &State::HunkPlus,
+                    &mut self.highlighter,
+                    self.config,
+                ),
+                Self::get_diff_style_sections(
+                    &self.minus_lines,
+                    &self.plus_lines,
+                    self.config.get_tokenization_regex(&self.syntax.name),
+                    self.config,
+                ),
+            )
+        };
+        let (minus_syntax_errors, plus_syntax_errors) = if self.exceeds_max_file_size() {
+            (Vec::new(), Vec::new())
+        } else {
+            (
+                Self::get_syntax_errors(
+                    &self.minus_lines,
+                    &State::HunkMinus,
+                    &mut self.syntax_error_state,
+                    self.syntax,
+                    self.config,
+                ),
+                Self::get_syntax_errors(
+                    &self.plus_lines,
+                    &State::HunkPlus,
+                    &mut self.syntax_error_state,
+                    self.syntax,
+                    self.config,
+                ),
+            )
+        };
+        if !self.moved_lines.is_empty() {
+            Self::set_moved_styles(
+                &mut minus_line_diff_style_sections,
+                &self.minus_lines,
+                &self.moved_lines,
+                self.config.moved_style,
+            );
+            Self::set_moved_styles(
+                &mut plus_line_diff_style_sections,
+                &self.plus_lines,
+                &self.moved_lines,
+                self.config.moved_style,
+            );
+        }
+        if self.config.color_moved {
+            Self::set_color_moved_styles(
+                &mut minus_line_diff_style_sections,
+                &self.minus_lines,
+                &self.minus_lines_color_moved_styles,
+            );
+            Self::set_color_moved_styles(
+                &mut plus_line_diff_style_sections,
+                &self.plus_lines,
+                &self.plus_lines_color_moved_styles,
+            );
+        }
+        let plus_line_diff_style_sections = match &self.config.plus_emphasis_regex {
+            Some(regex) => Self::set_emphasis_regex_styles(
+                plus_line_diff_style_sections,
+                regex,
+                self.config.plus_emph_style,
+            ),
+            None => plus_line_diff_style_sections,
+        };
+        let mut plus_line_diff_style_sections = if self.config.show_whitespace_errors {
+            Self::set_whitespace_error_styles(
+                plus_line_diff_style_sections,
+                &self.plus_lines_indentation_is_mixed,
+                self.config.whitespace_error_style,
+            )
+        } else {
+            plus_line_diff_style_sections
+        };
+        Self::set_empty_line_markers(
+            &mut minus_line_syntax_style_sections,
+            &mut minus_line_diff_style_sections,
+            &self.config.minus_empty_line_marker,
+            self.config.minus_empty_line_marker_style,
+            self.config.null_syntect_style,
         );
-        let plus_line_syntax_style_sections = Self::get_syntax_style_sections_for_lines(
-            &self.plus_lines,
-            &State::HunkPlus,
-            &mut self.highlighter,
-            self.config,
+        Self::set_empty_line_markers(
+            &mut plus_line_syntax_style_sections,
+            &mut plus_line_diff_style_sections,
+            &self.config.plus_empty_line_marker,
+            self.config.plus_empty_line_marker_style,
+            self.config.null_syntect_style,
         );
-        let (minus_line_diff_style_sections, plus_line_diff_style_sections) =
-            Self::get_diff_style_sections(&self.minus_lines, &self.plus_lines, self.config);
 
         let mut minus_line_numbers = Vec::new();
         let mut plus_line_numbers = Vec::new();
@@ -88,35 +260,235 @@ impl<'a> Painter<'a> {
             plus_line_numbers.push(Some((None, Some(self.plus_line_number))));
             self.plus_line_number += 1;
         }
+        // Under --emit-null-for-missing-files, the lines of an added or removed file are marked
+        // with the null file indicator.
+        let null_file_marker = |other_file_is_null: bool, line_marker| {
+            if self.config.emit_null_for_missing_files && other_file_is_null {
+                (
+                    self.config.null_file_indicator.as_str(),
+                    Some(self.config.null_style),
+                )
+            } else {
+                (line_marker, None)
+            }
+        };
+        let (minus_line_marker, minus_line_marker_style) =
+            null_file_marker(self.plus_file_is_null, self.config.minus_line_marker);
+        let (plus_line_marker, plus_line_marker_style) =
+            null_file_marker(self.minus_file_is_null, self.config.plus_line_marker);
         // TODO: lines and style sections contain identical line text
         if !self.minus_lines.is_empty() {
+            if self.config.minus_ruler && self.hunk_has_painted_minus_lines {
+                Self::paint_ruler(
+                    &self.minus_lines,
+                    self.config.minus_emph_style,
+                    &mut self.output_buffer,
+                    self.config,
+                );
+            }
+            self.hunk_has_painted_minus_lines = true;
+            let start = self.output_buffer.len();
             Painter::paint_lines(
                 minus_line_syntax_style_sections,
                 minus_line_diff_style_sections,
                 minus_line_numbers,
+                self.line_number_width,
                 &mut self.output_buffer,
                 self.config,
-                self.config.minus_line_marker,
+                minus_line_marker,
+                minus_line_marker_style,
                 self.config.minus_style,
                 self.config.minus_non_emph_style,
                 None,
             );
+            mark_syntax_errors(&mut self.output_buffer, start, &minus_syntax_errors);
         }
         if !self.plus_lines.is_empty() {
+            if self.config.plus_ruler && self.hunk_has_painted_plus_lines {
+                Self::paint_ruler(
+                    &self.plus_lines,
+                    self.config.plus_emph_style,
+                    &mut self.output_buffer,
+                    self.config,
+                );
+            }
+            self.hunk_has_painted_plus_lines = true;
+            let start = self.output_buffer.len();
             Painter::paint_lines(
                 plus_line_syntax_style_sections,
                 plus_line_diff_style_sections,
                 plus_line_numbers,
+                self.line_number_width,
                 &mut self.output_buffer,
                 self.config,
-                self.config.plus_line_marker,
+                plus_line_marker,
+                plus_line_marker_style,
                 self.config.plus_style,
                 self.config.plus_non_emph_style,
                 None,
             );
+            mark_syntax_errors(&mut self.output_buffer, start, &plus_syntax_errors);
+        }
+        self.minus_lines.clear();
+        self.plus_lines.clear();
+        self.plus_lines_indentation_is_mixed.clear();
+        self.minus_lines_color_moved_styles.clear();
+        self.plus_lines_color_moved_styles.clear();
+    }
+
+    /// Write a horizontal rule separating a group of removed or added lines from the previous
+    /// group of the same kind in the hunk. Under --width=variable, the rule is as wide as the
+    /// longest line of the group.
+    fn paint_ruler(
+        lines: &[String],
+        style: Style,
+        output_buffer: &mut String,
+        config: &config::Config,
+    ) {
+        let width = match config.decorations_width {
+            config::Width::Fixed(width) => width,
+            config::Width::Variable => lines
+                .iter()
+                .map(|line| UnicodeWidthStr::width(line.as_str()))
+                .max()
+                .unwrap_or(0),
+        };
+        output_buffer.push_str(&format!(
+            "{}\n",
+            style.ansi_term_style.paint("─".repeat(width))
+        ));
+    }
+
+    /// Paint an unchanged line, which has been prepared for painting.
+    pub fn paint_zero_line(&mut self, line: String) {
+        let state = State::HunkZero;
+        let lines = vec![line];
+        let (syntax_style_sections, syntax_errors) = if self.exceeds_max_file_size() {
+            (
+                Self::get_plain_style_sections(&lines, self.config.null_syntect_style),
+                Vec::new(),
+            )
+        } else {
+            (
+                Self::get_syntax_style_sections_for_lines(
+                    &lines,
+                    &state,
+                    &mut self.highlighter,
+                    self.config,
+                ),
+                Self::get_syntax_errors(
+                    &lines,
+                    &state,
+                    &mut self.syntax_error_state,
+                    self.syntax,
+                    self.config,
+                ),
+            )
+        };
+        let zero_style = self
+            .config
+            .context_line_patterns
+            .iter()
+            .find(|(regex, _)| regex.is_match(&lines[0]))
+            .map(|(_, style)| *style)
+            .unwrap_or(self.config.context_style);
+        let diff_style_sections = vec![(zero_style, lines[0].as_str())];
+
+        let line_numbers = if self.config.show_line_numbers_for_context {
+            (Some(self.minus_line_number), Some(self.plus_line_number))
+        } else {
+            (None, None)
+        };
+        let start = self.output_buffer.len();
+        Self::paint_lines(
+            syntax_style_sections,
+            vec![diff_style_sections],
+            vec![Some(line_numbers)],
+            self.line_number_width,
+            &mut self.output_buffer,
+            self.config,
+            " ",
+            None,
+            zero_style,
+            zero_style,
+            None,
+        );
+        mark_syntax_errors(&mut self.output_buffer, start, &syntax_errors);
+        self.minus_line_number += 1;
+        self.plus_line_number += 1;
+    }
+
+    /// Paint the current run of blank unchanged lines. Under --empty-line-context, a run longer
+    /// than the given number of lines is painted as a single line in the --ellipsis-style.
+    pub fn paint_blank_zero_lines(&mut self) {
+        let n_lines = self.blank_zero_lines.len();
+        if self
+            .config
+            .empty_line_context
+            .map_or(false, |n| n_lines > n)
+        {
+            let text = if n_lines == 1 {
+                "⋮ (1 blank line)".to_string()
+            } else {
+                format!("⋮ ({} blank lines)", n_lines)
+            };
+            self.output_buffer.push_str(&format!(
+                "{}\n",
+                self.config.ellipsis_style.ansi_term_style.paint(text)
+            ));
+            self.minus_line_number += n_lines;
+            self.plus_line_number += n_lines;
+            self.blank_zero_lines.clear();
+        } else {
+            for line in std::mem::take(&mut self.blank_zero_lines) {
+                self.paint_zero_line(line);
+            }
+        }
+    }
+
+    /// Debugging aid for the tokenization regex: write the buffered lines with each token
+    /// surrounded by |...|, instead of the usual within-line edit highlighting.
+    fn paint_buffered_lines_token_types(&mut self) {
+        if self.minus_lines.is_empty() && self.plus_lines.is_empty() {
+            return;
+        }
+        let tokenization_regex = self.config.get_tokenization_regex(&self.syntax.name);
+        self.output_buffer
+            .push_str(&format!("word-diff-regex: {}\n", tokenization_regex));
+        for (lines, style, marker, line_number) in [
+            (
+                &self.minus_lines,
+                self.config.minus_style,
+                self.config.minus_line_marker,
+                &mut self.minus_line_number,
+            ),
+            (
+                &self.plus_lines,
+                self.config.plus_style,
+                self.config.plus_line_marker,
+                &mut self.plus_line_number,
+            ),
+        ] {
+            for line in lines {
+                // Skip the space substituted for the -/+ character by delta::prepare.
+                let line = line.trim_end_matches('\n').get(1..).unwrap_or("");
+                let tokens = edits::tokenize(line, tokenization_regex)
+                    .into_iter()
+                    .filter(|token| !token.is_empty())
+                    .map(|token| format!("|{}|", token))
+                    .collect::<String>();
+                self.output_buffer.push_str(&format!(
+                    "{}\n",
+                    style.ansi_term_style.paint(format!("{}{}", marker, tokens))
+                ));
+                *line_number += 1;
+            }
         }
         self.minus_lines.clear();
         self.plus_lines.clear();
+        self.plus_lines_indentation_is_mixed.clear();
+        self.minus_lines_color_moved_styles.clear();
+        self.plus_lines_color_moved_styles.clear();
     }
 
     /// Superimpose background styles and foreground syntax
@@ -125,12 +497,14 @@ impl<'a> Painter<'a> {
         syntax_style_sections: Vec<Vec<(SyntectStyle, &str)>>,
         diff_style_sections: Vec<Vec<(Style, &str)>>,
         line_number_sections: Vec<Option<(Option<usize>, Option<usize>)>>,
+        line_number_width: usize, // the width of the line numbers under --number-right-align
         output_buffer: &mut String,
         config: &config::Config,
         prefix: &str,
-        style: Style,          // style for right fill if line contains no emph sections
-        non_emph_style: Style, // style for right fill if line contains emph sections
-        background_color_extends_to_terminal_width: Option<bool>,
+        prefix_style: Option<Style>, // if None, the prefix takes the style of the start of the line
+        style: Style,                // style for right fill if line contains no emph sections
+        non_emph_style: Style,       // style for right fill if line contains emph sections
+        line_fill_method: Option<config::LineFillMethod>,
     ) {
         // There's some unfortunate hackery going on here for two reasons:
         //
@@ -148,43 +522,96 @@ impl<'a> Painter<'a> {
         {
             let non_emph_style = if style_sections_contain_more_than_one_style(diff_sections) {
                 non_emph_style // line contains an emph section
+            } else if config.ignore_whitespace_change
+                && diff_sections.first().map(|(style, _)| *style)
+                    == Some(config.whitespace_change_style)
+            {
+                config.whitespace_change_style // line differs from another only in whitespace
             } else {
                 style
             };
 
             let mut handled_prefix = false;
-            let mut ansi_strings = if config.show_line_numbers && line_numbers.is_some() {
+            let mut line_number_strings = if config.show_line_numbers && line_numbers.is_some() {
                 let (minus, plus) = line_numbers.unwrap();
-                let (minus_before, minus_number, minus_after) =
-                    get_line_number_components(minus, &config.number_minus_format);
-                let (plus_before, plus_number, plus_after) =
-                    get_line_number_components(plus, &config.number_plus_format);
-                vec![
-                    config
-                        .number_minus_format_style
-                        .ansi_term_style
-                        .paint(minus_before),
-                    config
-                        .number_minus_style
-                        .ansi_term_style
-                        .paint(minus_number),
-                    config
-                        .number_minus_format_style
-                        .ansi_term_style
-                        .paint(minus_after),
-                    config
-                        .number_plus_format_style
-                        .ansi_term_style
-                        .paint(plus_before),
-                    config.number_plus_style.ansi_term_style.paint(plus_number),
-                    config
-                        .number_plus_format_style
-                        .ansi_term_style
-                        .paint(plus_after),
-                ]
+                let right_align_width = if config.number_right_align {
+                    Some(line_number_width)
+                } else {
+                    None
+                };
+                if let Some(line_numbers_format) = &config.line_numbers_format {
+                    line_numbers::format_line_numbers(
+                        line_numbers_format,
+                        minus,
+                        plus,
+                        right_align_width,
+                    )
+                    .into_iter()
+                    .map(|(part, text)| {
+                        let style = match part {
+                            LineNumbersPart::MinusFormat => config.number_minus_format_style,
+                            LineNumbersPart::MinusNumber => config.number_minus_style,
+                            LineNumbersPart::PlusFormat => config.number_plus_format_style,
+                            LineNumbersPart::PlusNumber => config.number_plus_style,
+                        };
+                        style.ansi_term_style.paint(text)
+                    })
+                    .collect()
+                } else {
+                    let (minus_before, minus_number, minus_after) = get_line_number_components(
+                        minus,
+                        &config.number_minus_format,
+                        right_align_width,
+                    );
+                    let (plus_before, plus_number, plus_after) = get_line_number_components(
+                        plus,
+                        &config.number_plus_format,
+                        right_align_width,
+                    );
+                    vec![
+                        config
+                            .number_minus_format_style
+                            .ansi_term_style
+                            .paint(minus_before),
+                        config
+                            .number_minus_style
+                            .ansi_term_style
+                            .paint(minus_number),
+                        config
+                            .number_minus_format_style
+                            .ansi_term_style
+                            .paint(minus_after),
+                        config
+                            .number_separator_style
+                            .ansi_term_style
+                            .paint(config.number_separator.clone()),
+                        config
+                            .number_plus_format_style
+                            .ansi_term_style
+                            .paint(plus_before),
+                        config.number_plus_style.ansi_term_style.paint(plus_number),
+                        config
+                            .number_plus_format_style
+                            .ansi_term_style
+                            .paint(plus_after),
+                    ]
+                }
             } else {
                 Vec::new()
             };
+            if let Some(background) = config.number_gutter_background {
+                for line_number_string in line_number_strings.iter_mut() {
+                    line_number_string
+                        .style_ref_mut()
+                        .background
+                        .get_or_insert(background);
+                }
+            }
+            let (mut ansi_strings, mut right_gutter) = match config.number_column_placement {
+                config::NumberColumnPlacement::Left => (line_number_strings, Vec::new()),
+                config::NumberColumnPlacement::Right => (Vec::new(), line_number_strings),
+            };
+            let mut text_width = 0;
             for (section_style, mut text) in superimpose_style_sections(
                 syntax_sections,
                 diff_sections,
@@ -193,28 +620,55 @@ impl<'a> Painter<'a> {
             ) {
                 if !handled_prefix {
                     if prefix != "" {
-                        ansi_strings.push(section_style.ansi_term_style.paint(prefix));
+                        ansi_strings.push(
+                            prefix_style
+                                .unwrap_or(section_style)
+                                .ansi_term_style
+                                .paint(prefix),
+                        );
+                        text_width += UnicodeWidthStr::width(prefix);
                         if text.len() > 0 {
                             text.remove(0);
                         }
                     }
                     handled_prefix = true;
                 }
+                text_width += UnicodeWidthStr::width(text.as_str());
                 ansi_strings.push(section_style.ansi_term_style.paint(text));
             }
+            if !right_gutter.is_empty() {
+                // The line numbers follow the line content. With a fixed width, pad the content
+                // (in the right-fill style) so that the line numbers form a column at the right
+                // edge.
+                if let config::Width::Fixed(width) = config.decorations_width {
+                    let gutter_width: usize = right_gutter
+                        .iter()
+                        .map(|s| UnicodeWidthStr::width(&s[..]))
+                        .sum();
+                    let padding = width.saturating_sub(text_width + gutter_width);
+                    ansi_strings.push(non_emph_style.ansi_term_style.paint(" ".repeat(padding)));
+                }
+                ansi_strings.append(&mut right_gutter);
+                output_buffer.push_str(&ansi_term::ANSIStrings(&ansi_strings).to_string());
+                output_buffer.push('\n');
+                continue;
+            }
             // Set style for the right-fill.
             let mut have_background_for_right_fill = false;
+            let line_fill_method = line_fill_method.unwrap_or(config.line_fill_method);
             if non_emph_style.ansi_term_style.background.is_some() {
-                ansi_strings.push(non_emph_style.ansi_term_style.paint(""));
+                match (line_fill_method, &config.decorations_width) {
+                    (config::LineFillMethod::Spaces, config::Width::Fixed(width)) => {
+                        let padding = width.saturating_sub(text_width);
+                        ansi_strings
+                            .push(non_emph_style.ansi_term_style.paint(" ".repeat(padding)));
+                    }
+                    _ => ansi_strings.push(non_emph_style.ansi_term_style.paint("")),
+                }
                 have_background_for_right_fill = true;
             }
             let line = &mut ansi_term::ANSIStrings(&ansi_strings).to_string();
-            let background_color_extends_to_terminal_width =
-                match background_color_extends_to_terminal_width {
-                    Some(boolean) => boolean,
-                    None => config.background_color_extends_to_terminal_width,
-                };
-            if background_color_extends_to_terminal_width && have_background_for_right_fill {
+            if line_fill_method == config::LineFillMethod::Ansi && have_background_for_right_fill {
                 // HACK: How to properly incorporate the ANSI_CSI_ERASE_IN_LINE into ansi_strings?
                 if line
                     .to_lowercase()
@@ -245,13 +699,21 @@ impl<'a> Painter<'a> {
         }
         match state {
             State::HunkMinus => {
-                config.minus_style.is_syntax_highlighted
-                    || config.minus_emph_style.is_syntax_highlighted
+                config.syntax_highlight_minus
+                    && (config.minus_style.is_syntax_highlighted
+                        || config.minus_emph_style.is_syntax_highlighted)
+            }
+            State::HunkZero => {
+                config.context_style.is_syntax_highlighted
+                    || config
+                        .context_line_patterns
+                        .iter()
+                        .any(|(_, style)| style.is_syntax_highlighted)
             }
-            State::HunkZero => config.zero_style.is_syntax_highlighted,
             State::HunkPlus => {
-                config.plus_style.is_syntax_highlighted
-                    || config.plus_emph_style.is_syntax_highlighted
+                config.syntax_highlight_plus
+                    && (config.plus_style.is_syntax_highlighted
+                        || config.plus_emph_style.is_syntax_highlighted)
             }
             State::HunkHeader => true,
             _ => panic!(
@@ -279,10 +741,50 @@ impl<'a> Painter<'a> {
         line_sections
     }
 
+    /// Under --show-syntax-errors, return whether each line is one that the syntax definition
+    /// failed to tokenize, i.e. a non-blank line in which it matched no scope other than the scope
+    /// of the whole file. The lines are read by the parser only if they are syntax highlighted.
+    fn get_syntax_errors(
+        lines: &[String],
+        state: &State,
+        syntax_error_state: &mut Option<(ParseState, ScopeStack)>,
+        syntax: &SyntaxReference,
+        config: &config::Config,
+    ) -> Vec<bool> {
+        let (parse_state, scope_stack) = match syntax_error_state {
+            Some((parse_state, scope_stack))
+                if Painter::should_compute_syntax_highlighting(state, config) =>
+            {
+                (parse_state, scope_stack)
+            }
+            _ => return Vec::new(),
+        };
+        lines
+            .iter()
+            .map(|line| {
+                let mut max_depth = scope_stack.len();
+                for (_, op) in parse_state.parse_line(line, &config.syntax_set) {
+                    scope_stack.apply(&op);
+                    max_depth = max_depth.max(scope_stack.len());
+                }
+                max_depth <= 1 && !line.trim().is_empty() && syntax.name != "Plain Text"
+            })
+            .collect()
+    }
+
+    /// Style each line as a single section in `style`.
+    pub fn get_plain_style_sections<S: Copy>(lines: &[String], style: S) -> Vec<Vec<(S, &str)>> {
+        lines
+            .iter()
+            .map(|line| vec![(style, line.as_str())])
+            .collect()
+    }
+
     /// Set background styles to represent diff for minus and plus lines in buffer.
     fn get_diff_style_sections<'b>(
         minus_lines: &'b Vec<String>,
         plus_lines: &'b Vec<String>,
+        tokenization_regex: &Regex,
         config: &config::Config,
     ) -> (Vec<Vec<(Style, &'b str)>>, Vec<Vec<(Style, &'b str)>>) {
         let mut diff_sections = edits::infer_edits(
@@ -292,7 +794,8 @@ impl<'a> Painter<'a> {
             config.minus_emph_style,
             config.plus_style,
             config.plus_emph_style,
-            &config.tokenization_regex,
+            tokenization_regex,
+            config.intra_line_diff_algorithm,
             config.max_line_distance,
             config.max_line_distance_for_naively_paired_lines,
         );
@@ -302,9 +805,157 @@ impl<'a> Painter<'a> {
         if config.plus_non_emph_style != config.plus_emph_style {
             Self::set_non_emph_styles(&mut diff_sections.1, config.plus_non_emph_style);
         }
+        if config.ignore_whitespace_change {
+            Self::set_whitespace_change_styles(
+                &mut diff_sections.0,
+                &mut diff_sections.1,
+                minus_lines,
+                plus_lines,
+                tokenization_regex,
+                config,
+            );
+        }
         diff_sections
     }
 
+    /// Paint each line that is one of `moved_lines` (see --highlight-copied-lines) in the moved
+    /// style.
+    fn set_moved_styles<'b>(
+        diff_sections: &mut [Vec<(Style, &'b str)>],
+        lines: &'b [String],
+        moved_lines: &HashSet<String>,
+        moved_style: Style,
+    ) {
+        for (line_sections, line) in diff_sections.iter_mut().zip(lines) {
+            if moved_lines.contains(line) {
+                *line_sections = vec![(moved_style, line.as_str())];
+            }
+        }
+    }
+
+    /// Paint each line that git marked as moved (see --color-moved) in its moved style.
+    fn set_color_moved_styles<'b>(
+        diff_sections: &mut [Vec<(Style, &'b str)>],
+        lines: &'b [String],
+        color_moved_styles: &[Option<Style>],
+    ) {
+        for ((line_sections, line), style) in
+            diff_sections.iter_mut().zip(lines).zip(color_moved_styles)
+        {
+            if let Some(style) = style {
+                *line_sections = vec![(*style, line.as_str())];
+            }
+        }
+    }
+
+    /// Display `marker`, in `marker_style`, as the content of each line that is empty (see
+    /// --minus-empty-line-marker and --plus-empty-line-marker). The leading space standing for
+    /// the -/+ character and the newline keep the style of the line.
+    fn set_empty_line_markers<'b>(
+        syntax_sections: &mut [Vec<(SyntectStyle, &'b str)>],
+        diff_sections: &mut [Vec<(Style, &'b str)>],
+        marker: &'b str,
+        marker_style: Style,
+        null_syntect_style: SyntectStyle,
+    ) {
+        if marker.is_empty() {
+            return;
+        }
+        for (line_syntax_sections, line_diff_sections) in
+            syntax_sections.iter_mut().zip(diff_sections.iter_mut())
+        {
+            let line: String = line_diff_sections.iter().map(|(_, text)| *text).collect();
+            if line != " \n" {
+                continue;
+            }
+            let style = line_diff_sections[0].0;
+            *line_syntax_sections = vec![
+                (null_syntect_style, " "),
+                (null_syntect_style, marker),
+                (null_syntect_style, "\n"),
+            ];
+            *line_diff_sections = vec![(style, " "), (marker_style, marker), (style, "\n")];
+        }
+    }
+
+    /// Paint the matches of `regex` (see --plus-emphasis-regexp) in each line in `emph_style`. The
+    /// regex is matched against the line without its leading -/+/space character.
+    fn set_emphasis_regex_styles<'b>(
+        style_sections: Vec<Vec<(Style, &'b str)>>,
+        regex: &Regex,
+        emph_style: Style,
+    ) -> Vec<Vec<(Style, &'b str)>> {
+        style_sections
+            .into_iter()
+            .map(|line_sections| {
+                let line: String = line_sections.iter().map(|(_, text)| *text).collect();
+                let match_ranges: Vec<(usize, usize)> = regex
+                    .find_iter(line.get(1..).unwrap_or(""))
+                    .filter(|m| m.start() < m.end())
+                    .map(|m| (m.start() + 1, m.end() + 1))
+                    .collect();
+                set_style_in_ranges(line_sections, &match_ranges, emph_style)
+            })
+            .collect()
+    }
+
+    /// Paint the whitespace errors in each plus line (see --show-whitespace-errors) in the
+    /// whitespace error style.
+    fn set_whitespace_error_styles<'b>(
+        style_sections: Vec<Vec<(Style, &'b str)>>,
+        indentation_is_mixed: &[bool],
+        whitespace_error_style: Style,
+    ) -> Vec<Vec<(Style, &'b str)>> {
+        style_sections
+            .into_iter()
+            .enumerate()
+            .map(|(i, line_sections)| {
+                let line: String = line_sections.iter().map(|(_, text)| *text).collect();
+                let error_ranges = get_whitespace_error_ranges(
+                    &line,
+                    indentation_is_mixed.get(i).copied().unwrap_or(false),
+                );
+                set_style_in_ranges(line_sections, &error_ranges, whitespace_error_style)
+            })
+            .collect()
+    }
+
+    /// Paint the removed and added lines that differ only in whitespace (see
+    /// --ignore-whitespace-change) in the whitespace change style. A removed line is paired with
+    /// the first following added line having the same tokens, ignoring whitespace tokens.
+    fn set_whitespace_change_styles<'b>(
+        minus_diff_sections: &mut [Vec<(Style, &'b str)>],
+        plus_diff_sections: &mut [Vec<(Style, &'b str)>],
+        minus_lines: &'b [String],
+        plus_lines: &'b [String],
+        tokenization_regex: &Regex,
+        config: &config::Config,
+    ) {
+        let non_whitespace_tokens = |line: &'b str| -> Vec<&'b str> {
+            edits::tokenize(line, tokenization_regex)
+                .into_iter()
+                .filter(|token| !token.trim().is_empty())
+                .collect()
+        };
+        let plus_tokens: Vec<Vec<&str>> = plus_lines
+            .iter()
+            .map(|line| non_whitespace_tokens(line))
+            .collect();
+        let mut next_plus_line = 0;
+        for (i, minus_line) in minus_lines.iter().enumerate() {
+            let minus_tokens = non_whitespace_tokens(minus_line);
+            if let Some(j) = (next_plus_line..plus_lines.len())
+                .find(|j| plus_tokens[*j] == minus_tokens && plus_lines[*j] != *minus_line)
+            {
+                minus_diff_sections[i] =
+                    vec![(config.whitespace_change_style, minus_line.as_str())];
+                plus_diff_sections[j] =
+                    vec![(config.whitespace_change_style, plus_lines[j].as_str())];
+                next_plus_line = j + 1;
+            }
+        }
+    }
+
     fn set_non_emph_styles(style_sections: &mut Vec<Vec<(Style, &str)>>, non_emph_style: Style) {
         for line_sections in style_sections {
             // If there multiple diff styles in the line, then the line must have some inferred
@@ -321,6 +972,78 @@ impl<'a> Painter<'a> {
     }
 }
 
+/// Prepend the syntax error marker (see --show-syntax-errors) to each line written to
+/// `output_buffer` since `start` whose entry in `is_syntax_error` is true.
+fn mark_syntax_errors(output_buffer: &mut String, start: usize, is_syntax_error: &[bool]) {
+    if !is_syntax_error.contains(&true) {
+        return;
+    }
+    let marker = ansi_term::Color::Fixed(9).paint("[!]").to_string();
+    let mut marked_lines = String::new();
+    for (line, is_syntax_error) in output_buffer[start..]
+        .split_inclusive('\n')
+        .zip(is_syntax_error.iter().chain(iter::repeat(&false)))
+    {
+        if *is_syntax_error {
+            marked_lines.push_str(&marker);
+            marked_lines.push(' ');
+        }
+        marked_lines.push_str(line);
+    }
+    output_buffer.truncate(start);
+    output_buffer.push_str(&marked_lines);
+}
+
+/// Return the byte ranges of the whitespace errors in a line prepared by delta::prepare: the
+/// trailing whitespace, and the indentation if `indentation_is_mixed`.
+fn get_whitespace_error_ranges(line: &str, indentation_is_mixed: bool) -> Vec<(usize, usize)> {
+    let is_whitespace = |c: char| c == ' ' || c == '\t';
+    // Skip the space that stands in for the -/+/space character.
+    let content_end = line.trim_end_matches('\n').len();
+    let content = line.get(1..content_end).unwrap_or("");
+    let trailing_start = 1 + content.trim_end_matches(is_whitespace).len();
+    let indentation_end = 1 + content.len() - content.trim_start_matches(is_whitespace).len();
+    let mut ranges = Vec::new();
+    if indentation_is_mixed && indentation_end > 1 && indentation_end < trailing_start {
+        ranges.push((1, indentation_end));
+    }
+    if trailing_start < content_end {
+        ranges.push((trailing_start, content_end));
+    }
+    ranges
+}
+
+/// Split the style sections of a line at the boundaries of `ranges`, which are sorted and do
+/// not overlap, and give the parts within the ranges `style`.
+fn set_style_in_ranges<'b>(
+    sections: Vec<(Style, &'b str)>,
+    ranges: &[(usize, usize)],
+    style: Style,
+) -> Vec<(Style, &'b str)> {
+    let mut styled_sections = Vec::with_capacity(sections.len() + ranges.len());
+    let mut offset = 0;
+    for (section_style, text) in sections {
+        let end = offset + text.len();
+        let mut position = offset;
+        for &(range_start, range_end) in ranges {
+            let (start, stop) = (range_start.max(position), range_end.min(end));
+            if start >= stop {
+                continue;
+            }
+            if start > position {
+                styled_sections.push((section_style, &text[position - offset..start - offset]));
+            }
+            styled_sections.push((style, &text[start - offset..stop - offset]));
+            position = stop;
+        }
+        if position < end {
+            styled_sections.push((section_style, &text[position - offset..]));
+        }
+        offset = end;
+    }
+    styled_sections
+}
+
 // edits::annotate doesn't return "coalesced" annotations (see comment there), so we can't assume
 // that `sections.len() > 1 <=> (multiple styles)`.
 fn style_sections_contain_more_than_one_style(sections: &Vec<(Style, &str)>) -> bool {
@@ -561,16 +1284,21 @@ lazy_static! {
         Regex::new(r"(?P<before>.*)(?P<ln>%ln)(?P<after>.*)").unwrap();
 }
 
-fn format_line_number(line_number: Option<usize>) -> String {
-    match line_number {
-        Some(x) => format!("{:^4}", x),
-        None => format!("    "),
+/// Format a line number centered in a field of width 4 or, under --number-right-align, right
+/// aligned in a field of width `right_align_width`.
+fn format_line_number(line_number: Option<usize>, right_align_width: Option<usize>) -> String {
+    match (line_number, right_align_width) {
+        (Some(x), Some(width)) => format!("{:>width$}", x, width = width),
+        (None, Some(width)) => " ".repeat(width),
+        (Some(x), None) => format!("{:^4}", x),
+        (None, None) => format!("    "),
     }
 }
 
 fn get_line_number_components(
     number: Option<usize>,
     number_format: &str,
+    right_align_width: Option<usize>,
 ) -> (String, String, String) {
     let captures = match LINE_NUMBER_REGEXP.captures(number_format) {
         Some(captures) => captures,
@@ -586,7 +1314,7 @@ fn get_line_number_components(
     };
     (
         before.to_string(),
-        format_line_number(number),
+        format_line_number(number, right_align_width),
         after.to_string(),
     )
 }
@@ -2,7 +2,7 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::path::Path;
 
-use crate::config::Config;
+use crate::config::{Config, DiffStatRenameFormat};
 
 // https://git-scm.com/docs/git-config#Documentation/git-config.txt-diffmnemonicPrefix
 const DIFF_PREFIXES: [&str; 6] = ["a", "b", "c", "i", "o", "w"];
@@ -43,6 +43,24 @@ pub fn get_file_path_from_file_meta_line(line: &str, git_diff_name: bool) -> Str
     .to_string()
 }
 
+/// Given input like
+/// "diff --git a/src/main.rs b/src/main.rs"
+/// Return "src/main.rs", or None if the two sides name different files, as for a rename.
+pub fn get_file_path_from_diff_git_line(line: &str) -> Option<&str> {
+    let paths = line.strip_prefix("diff --git ")?;
+    if paths.len() % 2 == 0 {
+        return None;
+    }
+    let middle = paths.len() / 2;
+    let minus_file = paths.get(..middle)?.get(2..)?;
+    let plus_file = paths.get(middle + 1..)?.get(2..)?;
+    if minus_file == plus_file {
+        Some(plus_file)
+    } else {
+        None
+    }
+}
+
 pub fn get_file_extension_from_file_meta_line_file_path(path: &str) -> Option<&str> {
     if path.is_empty() || path == "/dev/null" {
         None
@@ -55,6 +73,8 @@ pub fn get_file_change_description_from_file_paths(
     minus_file: &str,
     plus_file: &str,
     comparing: bool,
+    rename_score: Option<&str>,
+    is_copy: bool,
     config: &Config,
 ) -> String {
     if comparing {
@@ -80,8 +100,13 @@ pub fn get_file_change_description_from_file_paths(
                 format!("{}{}", format_label(&config.file_added_label), plus_file)
             }
             (minus_file, plus_file) => format!(
-                "{}{} ⟶   {}",
-                format_label(&config.file_renamed_label),
+                "{}{}{} ⟶   {}",
+                format_label(if is_copy {
+                    &config.file_copied_label
+                } else {
+                    &config.file_renamed_label
+                }),
+                format_rename_score(rename_score),
                 minus_file,
                 plus_file
             ),
@@ -89,6 +114,83 @@ pub fn get_file_change_description_from_file_paths(
     }
 }
 
+lazy_static! {
+    static ref DIFF_STAT_LINE_REGEXP: Regex =
+        Regex::new(r"^ \S.*? +\| (?:\d+(?: \+*-*)?|Bin .*)$").unwrap();
+}
+
+/// Whether the line has the form of a diff stat line, e.g. " src/delta.rs | 2 +-".
+pub fn is_diff_stat_line(line: &str) -> bool {
+    DIFF_STAT_LINE_REGEXP.is_match(line)
+}
+
+lazy_static! {
+    static ref DIFF_STAT_RENAME_LINE_REGEXP: Regex =
+        Regex::new(r"^ (?P<path>.+ => .+?)(?P<padding> *) \| (?P<changes>.*)$").unwrap();
+}
+
+lazy_static! {
+    static ref DIFF_STAT_RENAME_PATH_REGEXP: Regex =
+        Regex::new(r"^(?P<prefix>.*)\{(?P<old>.*) => (?P<new>.*)\}(?P<suffix>.*)$").unwrap();
+}
+
+/// Given a diff stat line for a renamed file, e.g.
+/// " src/{old.rs => new.rs} | 2 +-"
+/// return the line with the file name rewritten according to `format`, or None if the line is
+/// not a diff stat line for a renamed file.
+pub fn rewrite_diff_stat_rename_line(line: &str, format: DiffStatRenameFormat) -> Option<String> {
+    if format == DiffStatRenameFormat::Git {
+        return None;
+    }
+    let captures = DIFF_STAT_RENAME_LINE_REGEXP.captures(line)?;
+    let path = captures.name("path")?.as_str();
+    let (prefix, old, new, suffix) = match DIFF_STAT_RENAME_PATH_REGEXP.captures(path) {
+        Some(parts) => (
+            parts.name("prefix")?.as_str(),
+            parts.name("old")?.as_str(),
+            parts.name("new")?.as_str(),
+            parts.name("suffix")?.as_str(),
+        ),
+        None => {
+            let mut parts = path.splitn(2, " => ");
+            ("", parts.next()?, parts.next()?, "")
+        }
+    };
+    // Git writes e.g. "{ => dir}/file" for a file moved into a directory.
+    let join = |middle: &str| {
+        format!("{}{}{}", prefix, middle, suffix)
+            .replace("//", "/")
+            .trim_start_matches('/')
+            .to_string()
+    };
+    let file_name = match format {
+        DiffStatRenameFormat::Short => join(new),
+        DiffStatRenameFormat::Full => format!("{} → {}", join(old), join(new)),
+        DiffStatRenameFormat::Arrow => format!("{}{{{},{}}}{}", prefix, old, new, suffix),
+        DiffStatRenameFormat::Git => unreachable!(),
+    };
+    // Keep the "|" column aligned with the other lines of the diff stat where possible.
+    let width = path.chars().count() + captures.name("padding")?.as_str().len();
+    Some(format!(
+        " {:<width$} | {}",
+        file_name,
+        captures.name("changes")?.as_str(),
+        width = width
+    ))
+}
+
+/// Given a "similarity index N%" line from a git diff file header, return the percentage.
+pub fn parse_similarity_index_line(line: &str) -> Option<&str> {
+    line.strip_prefix("similarity index ").map(str::trim)
+}
+
+pub fn format_rename_score(rename_score: Option<&str>) -> String {
+    match rename_score {
+        Some(score) => format!("({}) ", score),
+        None => "".to_string(),
+    }
+}
+
 lazy_static! {
     static ref HUNK_METADATA_REGEXP: Regex =
         Regex::new(r"@+ (?P<lns>([-+]\d+(?:,\d+)? ){2,4})@+(?P<cf>.*\s?)").unwrap();
@@ -121,6 +223,207 @@ pub fn parse_hunk_metadata(line: &str) -> (&str, Vec<usize>) {
     return (code_fragment, line_numbers);
 }
 
+lazy_static! {
+    static ref LINE_NUMBER_RANGE_REGEXP: Regex = Regex::new(r"[-+](\d+)(?:,(\d+))?").unwrap();
+}
+
+/// Given input like
+/// "@@ -74,15 +74,14 @@ pub fn delta("
+/// Return a vector of (start, count) line number ranges, e.g. [(74, 15), (74, 14)]. The count
+/// defaults to 1 when it is omitted.
+pub fn parse_hunk_line_number_ranges(line: &str) -> Vec<(usize, usize)> {
+    let caps = HUNK_METADATA_REGEXP.captures(line).unwrap();
+    LINE_NUMBER_RANGE_REGEXP
+        .captures_iter(caps.name("lns").unwrap().as_str())
+        .map(|range| {
+            let start = range[1].parse::<usize>().unwrap();
+            let count = range
+                .get(2)
+                .map_or(1, |c| c.as_str().parse::<usize>().unwrap());
+            (start, count)
+        })
+        .collect()
+}
+
+/// Whether the line is a hunk header that parse_hunk_metadata and parse_hunk_line_number_ranges
+/// can parse, with one line number range per '@' character, e.g. "@@ -74,15 +74,14 @@" or
+/// "@@@ -1,2 -1,2 +1,3 @@@".
+pub fn is_valid_hunk_header_line(line: &str) -> bool {
+    let at_count = line.chars().take_while(|c| *c == '@').count();
+    HUNK_METADATA_REGEXP
+        .captures(line)
+        .filter(|caps| caps.get(0).unwrap().start() == 0)
+        .map_or(false, |caps| {
+            LINE_NUMBER_RANGE_REGEXP
+                .find_iter(caps.name("lns").unwrap().as_str())
+                .count()
+                == at_count
+        })
+}
+
+/// Whether the line can belong to a hunk that was not preceded by a "@@" hunk header.
+pub fn is_headerless_hunk_line(line: &str) -> bool {
+    !line.starts_with("--- ")
+        && !line.starts_with("+++ ")
+        && matches!(
+            line.chars().next(),
+            Some('-') | Some('+') | Some(' ') | Some('\\')
+        )
+}
+
+/// Return a hunk header for hunk lines that were not preceded by one, assuming that the hunk
+/// starts at the first line of the file, e.g. "@@ -1,3 +1,4 @@".
+pub fn make_hunk_header(lines: &[&str]) -> String {
+    let count = |marker: char| {
+        lines
+            .iter()
+            .filter(|line| line.starts_with(marker) || line.starts_with(' '))
+            .count()
+    };
+    let (minus_count, plus_count) = (count('-'), count('+'));
+    format!(
+        "@@ -{},{} +{},{} @@",
+        if minus_count == 0 { 0 } else { 1 },
+        minus_count,
+        if plus_count == 0 { 0 } else { 1 },
+        plus_count
+    )
+}
+
+lazy_static! {
+    static ref STASH_HEADER_REGEXP: Regex =
+        Regex::new(r"^(?P<ref>stash@\{\d+\})(?::\s*(?P<msg>.*?))?\s*$").unwrap();
+}
+
+/// Given input like
+/// "stash@{0}: WIP on master: 7417e1c baseline"
+/// Return ("stash@{0}", "WIP on master: 7417e1c baseline"). Return None if the line is not a
+/// stash entry header.
+pub fn parse_stash_header_line(line: &str) -> Option<(&str, &str)> {
+    let caps = STASH_HEADER_REGEXP.captures(line)?;
+    let stash_ref = caps.name("ref").unwrap().as_str();
+    let message = caps.name("msg").map(|m| m.as_str()).unwrap_or("");
+    Some((stash_ref, message))
+}
+
+lazy_static! {
+    static ref SUBMODULE_HEADER_REGEXP: Regex =
+        Regex::new(r"^Submodule (?P<path>.+) (?P<range>[0-9a-f]+\.\.\.?[0-9a-f]+)(?P<suffix>.*)$")
+            .unwrap();
+}
+
+/// Given input like
+/// "Submodule vendor/lib 1a2b3c4..5d6e7f8 (rewind):"
+/// Return ("vendor/lib", "1a2b3c4..5d6e7f8", " (rewind):"). Return None if the line does not
+/// describe a change of the submodule's commit, e.g. "Submodule x contains modified content".
+pub fn parse_submodule_header_line(line: &str) -> Option<(&str, &str, &str)> {
+    let caps = SUBMODULE_HEADER_REGEXP.captures(line)?;
+    Some((
+        caps.name("path").unwrap().as_str(),
+        caps.name("range").unwrap().as_str(),
+        caps.name("suffix").unwrap().as_str(),
+    ))
+}
+
+lazy_static! {
+    static ref COMMIT_HEADER_FIELD_REGEXP: Regex = Regex::new(r"^[A-Za-z]+:").unwrap();
+}
+
+/// The fields of a commit header block in git log output, e.g.
+/// ```text
+/// commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e
+/// Author: Dan Davison <dandavison7@gmail.com>
+/// Date:   Thu May 14 11:13:17 2020 -0400
+///
+///     rustfmt
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct CommitHeader {
+    pub sha: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl CommitHeader {
+    /// Start a commit header block, given its "commit <sha>" line.
+    pub fn new(commit_line: &str) -> Self {
+        Self {
+            sha: commit_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or("")
+                .to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a subsequent line of the commit header block. Return false if the line is not part of
+    /// a commit header block, e.g. because it is --stat output that follows it.
+    pub fn push_line(&mut self, line: &str) -> bool {
+        if let Some(author) = line.strip_prefix("Author:") {
+            self.author = author.trim().to_string();
+        } else if let Some(date) = line.strip_prefix("Date:") {
+            self.date = date.trim().to_string();
+        } else if let Some(message_line) = line.strip_prefix("    ") {
+            if self.subject.is_empty() {
+                self.subject = message_line.trim_end().to_string();
+            } else {
+                self.body.push_str(message_line);
+                self.body.push('\n');
+            }
+        } else if line.is_empty() {
+            if !self.subject.is_empty() {
+                self.body.push('\n');
+            }
+        } else if !COMMIT_HEADER_FIELD_REGEXP.is_match(line) {
+            // Other fields, e.g. "Merge:" or "CommitDate:", are accepted but not used.
+            return false;
+        }
+        true
+    }
+
+    /// Substitute the commit fields into a --commit-format template. The template may contain
+    /// the placeholders {sha}, {author}, {date}, {subject} and {body}, optionally with a maximum
+    /// length, e.g. {sha:.7}. The escape sequence \n denotes a newline.
+    pub fn format(&self, template: &str) -> String {
+        let template = template.replace("\\n", "\n");
+        let mut formatted = String::new();
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            formatted.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+            let mut spec = rest[1..end].splitn(2, ":.");
+            let name = spec.next().unwrap();
+            let max_length = spec.next().map(|n| n.parse::<usize>());
+            let value = match name {
+                "sha" => Some(&self.sha),
+                "author" => Some(&self.author),
+                "date" => Some(&self.date),
+                "subject" => Some(&self.subject),
+                "body" => Some(&self.body),
+                _ => None,
+            };
+            match (value, max_length) {
+                (Some(value), None) => formatted.push_str(value.trim_matches('\n')),
+                (Some(value), Some(Ok(n))) => {
+                    formatted.extend(value.trim_matches('\n').chars().take(n));
+                }
+                // Not a valid placeholder: keep it as literal text.
+                _ => formatted.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        formatted.push_str(rest);
+        formatted
+    }
+}
+
 /// Attempt to parse input as a file path and return extension as a &str.
 fn get_extension(s: &str) -> Option<&str> {
     let path = Path::new(s);
@@ -268,6 +571,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_stash_header_line() {
+        assert_eq!(
+            parse_stash_header_line("stash@{0}: WIP on master: 7417e1c baseline"),
+            Some(("stash@{0}", "WIP on master: 7417e1c baseline"))
+        );
+        assert_eq!(
+            parse_stash_header_line("stash@{12}: On main: my stash  "),
+            Some(("stash@{12}", "On main: my stash"))
+        );
+        assert_eq!(
+            parse_stash_header_line("stash@{1}"),
+            Some(("stash@{1}", ""))
+        );
+        assert_eq!(parse_stash_header_line("stash@{x}: WIP on master"), None);
+        assert_eq!(parse_stash_header_line("commit 7417e1c"), None);
+    }
+
+    #[test]
+    fn test_parse_submodule_header_line() {
+        assert_eq!(
+            parse_submodule_header_line("Submodule vendor/lib 1a2b3c4..5d6e7f8:"),
+            Some(("vendor/lib", "1a2b3c4..5d6e7f8", ":"))
+        );
+        assert_eq!(
+            parse_submodule_header_line("Submodule my lib 0000000...5d6e7f8 (new submodule)"),
+            Some(("my lib", "0000000...5d6e7f8", " (new submodule)"))
+        );
+        assert_eq!(
+            parse_submodule_header_line("Submodule vendor/lib contains modified content"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_commit_header() {
+        let mut commit_header =
+            CommitHeader::new("commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e (HEAD -> master)");
+        for line in &[
+            "Author: Dan Davison <dandavison7@gmail.com>",
+            "Date:   Thu May 14 11:13:17 2020 -0400",
+            "",
+            "    rustfmt",
+            "",
+            "    More details.",
+            "",
+        ] {
+            assert!(commit_header.push_line(line));
+        }
+        assert!(!commit_header.push_line(" src/align.rs | 5 +----"));
+        assert_eq!(
+            commit_header,
+            CommitHeader {
+                sha: "94907c0f136f46dc46ffae2dc92dca9af7eb7c2e".to_string(),
+                author: "Dan Davison <dandavison7@gmail.com>".to_string(),
+                date: "Thu May 14 11:13:17 2020 -0400".to_string(),
+                subject: "rustfmt".to_string(),
+                body: "\nMore details.\n\n".to_string(),
+            }
+        );
+        assert_eq!(
+            commit_header.format("{sha:.7} {author}\\n  {subject} {unknown} {date:.3}"),
+            "94907c0 Dan Davison <dandavison7@gmail.com>\n  rustfmt {unknown} Thu"
+        );
+        assert_eq!(commit_header.format("[{body}]"), "[More details.]");
+    }
+
     #[test]
     fn test_parse_hunk_metadata() {
         let parsed = parse_hunk_metadata("@@ -74,15 +75,14 @@ pub fn delta(\n");
@@ -298,6 +668,118 @@ mod tests {
         assert_eq!(line_numbers[1], 1,);
     }
 
+    #[test]
+    fn test_parse_hunk_line_number_ranges() {
+        assert_eq!(
+            parse_hunk_line_number_ranges("@@ -74,15 +75,14 @@ pub fn delta(\n"),
+            vec![(74, 15), (75, 14)]
+        );
+        assert_eq!(
+            parse_hunk_line_number_ranges("@@ -1 +1 @@"),
+            vec![(1, 1), (1, 1)]
+        );
+        assert_eq!(
+            parse_hunk_line_number_ranges("@@@ -293,11 -358,15 +358,16 @@@ dependencies ="),
+            vec![(293, 11), (358, 15), (358, 16)]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_hunk_header_line() {
+        assert!(is_valid_hunk_header_line(
+            "@@ -74,15 +75,14 @@ pub fn delta("
+        ));
+        assert!(is_valid_hunk_header_line("@@ -0,0 +1 @@"));
+        assert!(is_valid_hunk_header_line(
+            "@@@ -293,11 -358,15 +358,16 @@@ dependencies ="
+        ));
+        assert!(!is_valid_hunk_header_line("@@ -74,15 @@"));
+        assert!(!is_valid_hunk_header_line("@@@ -74,15 +75,14 @@@"));
+        assert!(!is_valid_hunk_header_line("@@ -x +1 @@"));
+    }
+
+    #[test]
+    fn test_is_headerless_hunk_line() {
+        assert!(is_headerless_hunk_line("-x = 1"));
+        assert!(is_headerless_hunk_line("+x = 2"));
+        assert!(is_headerless_hunk_line(" y = 3"));
+        assert!(is_headerless_hunk_line("\\ No newline at end of file"));
+        assert!(!is_headerless_hunk_line("--- a/src/main.rs"));
+        assert!(!is_headerless_hunk_line("+++ b/src/main.rs"));
+        assert!(!is_headerless_hunk_line("@@ -1 +1 @@"));
+        assert!(!is_headerless_hunk_line("diff --git a/x b/x"));
+    }
+
+    #[test]
+    fn test_make_hunk_header() {
+        assert_eq!(
+            make_hunk_header(&[" a", "-b", "+c", "+d", "\\ No newline at end of file"]),
+            "@@ -1,2 +1,3 @@"
+        );
+        assert_eq!(make_hunk_header(&["+a", "+b"]), "@@ -0,0 +1,2 @@");
+        assert_eq!(make_hunk_header(&["-a"]), "@@ -1,1 +0,0 @@");
+    }
+
+    #[test]
+    fn test_get_file_path_from_diff_git_line() {
+        assert_eq!(
+            get_file_path_from_diff_git_line("diff --git a/src/main.rs b/src/main.rs"),
+            Some("src/main.rs")
+        );
+        assert_eq!(
+            get_file_path_from_diff_git_line("diff --git a/with space b/with space"),
+            Some("with space")
+        );
+        assert_eq!(
+            get_file_path_from_diff_git_line("diff --git a/a.py b/b.py"),
+            None
+        );
+        assert_eq!(get_file_path_from_diff_git_line("diff -u a b"), None);
+    }
+
+    #[test]
+    fn test_is_diff_stat_line() {
+        assert!(is_diff_stat_line(" src/delta.rs | 2 +-"));
+        assert!(is_diff_stat_line(" src/{old.rs => new.rs} | 0"));
+        assert!(is_diff_stat_line(" x.bin        | Bin 0 -> 12 bytes"));
+        assert!(!is_diff_stat_line("    Move a => b | see the notes"));
+        assert!(!is_diff_stat_line(" a => b | see the notes"));
+        assert!(!is_diff_stat_line(" 1 file changed, 2 insertions(+)"));
+    }
+
+    #[test]
+    fn test_rewrite_diff_stat_rename_line() {
+        let line = " src/{old.rs => new.rs} | 2 +-";
+        assert_eq!(
+            rewrite_diff_stat_rename_line(line, DiffStatRenameFormat::Git),
+            None
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(line, DiffStatRenameFormat::Short),
+            Some(" src/new.rs             | 2 +-".to_string())
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(line, DiffStatRenameFormat::Full),
+            Some(" src/old.rs → src/new.rs | 2 +-".to_string())
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(line, DiffStatRenameFormat::Arrow),
+            Some(" src/{old.rs,new.rs}    | 2 +-".to_string())
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(" a.py => b.py     | 0", DiffStatRenameFormat::Full),
+            Some(" a.py → b.py      | 0".to_string())
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(" { => src}/a.py | 0", DiffStatRenameFormat::Full),
+            Some(" a.py → src/a.py | 0".to_string())
+        );
+        assert_eq!(
+            rewrite_diff_stat_rename_line(" src/a.py | 2 +-", DiffStatRenameFormat::Full),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_hunk_metadata_merge() {
         let parsed = parse_hunk_metadata("@@@ -293,11 -358,15 +358,16 @@@ dependencies =");
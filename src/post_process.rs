@@ -0,0 +1,172 @@
+//! Support for --post-process-cmd: delta's output is written to the standard input of a command,
+//! and the command's output is displayed instead.
+use std::io::{self, ErrorKind, Read, Write};
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+
+/// Run `post_process_cmd`, with `render` writing delta's output to its standard input, and copy
+/// the command's output to `writer`. Return the exit status of the command. If the command exits
+/// without reading all of its input, the rest of delta's output is discarded.
+pub fn write_post_processed_output(
+    post_process_cmd: &[String],
+    writer: &mut dyn Write,
+    render: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<process::ExitStatus> {
+    let mut child = process::Command::new(&post_process_cmd[0])
+        .args(&post_process_cmd[1..])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to run --post-process-cmd {}: {}",
+                post_process_cmd.join(" "),
+                err
+            );
+            process::exit(1);
+        });
+    let (child_stdin, mut child_stdout) =
+        (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+    // The command's output is read in a separate thread, so that the command cannot block on a
+    // full stdout pipe while delta is blocked writing to its stdin.
+    let (sender, receiver) = mpsc::channel();
+    let reader = thread::spawn(move || -> io::Result<()> {
+        let mut buffer = [0; 8192];
+        loop {
+            let n_bytes = child_stdout.read(&mut buffer)?;
+            if n_bytes == 0 || sender.send(buffer[..n_bytes].to_vec()).is_err() {
+                return Ok(());
+            }
+        }
+    });
+    let mut input = CommandInput {
+        child_stdin,
+        child_output: &receiver,
+        writer,
+    };
+    let render_result = render(&mut input);
+    let CommandInput {
+        child_stdin,
+        writer,
+        ..
+    } = input;
+    // Closing stdin lets the command see the end of its input.
+    drop(child_stdin);
+    let write_result = receiver
+        .iter()
+        .try_for_each(|output| writer.write_all(&output));
+    let status = child.wait()?;
+    write_result?;
+    reader.join().unwrap()?;
+    match render_result {
+        // The command exited without reading all of its input.
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(status),
+        result => result.map(|_| status),
+    }
+}
+
+/// The standard input of the post-processing command. After each write, the output that the
+/// command has produced so far is copied to `writer`.
+struct CommandInput<'r, 'w> {
+    child_stdin: process::ChildStdin,
+    child_output: &'r mpsc::Receiver<Vec<u8>>,
+    writer: &'w mut dyn Write,
+}
+
+impl CommandInput<'_, '_> {
+    fn write_child_output(&mut self) -> io::Result<()> {
+        while let Ok(output) = self.child_output.try_recv() {
+            self.writer.write_all(&output)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for CommandInput<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n_bytes = self.child_stdin.write(buf)?;
+        self.write_child_output()?;
+        Ok(n_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.child_stdin.flush()?;
+        self.write_child_output()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use bytelines::ByteLines;
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::delta::delta;
+    use crate::tests::integration_test_utils::integration_test_utils;
+
+    fn make_cmd(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_write_post_processed_output() {
+        let input = "\
+diff --git a/a.py b/a.py
+index 8b13789..ce01362 100644
+--- a/a.py
++++ b/a.py
+@@ -1 +1 @@
+-x = 1
++x = 2
+";
+        let config = integration_test_utils::make_config(&[]);
+        let mut output = Vec::new();
+        let status =
+            write_post_processed_output(&make_cmd(&["tr", "x", "y"]), &mut output, |writer| {
+                delta(
+                    ByteLines::new(BufReader::new(input.as_bytes())),
+                    writer,
+                    &config,
+                )
+            })
+            .unwrap();
+        assert!(status.success());
+        let output = String::from_utf8(output).unwrap();
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\na.py\n"));
+        assert!(output.contains("y = 1"));
+        assert!(output.contains("y = 2"));
+        assert!(!output.contains("x = "));
+    }
+
+    #[test]
+    fn test_write_post_processed_output_larger_than_pipe_buffer() {
+        let line = "a".repeat(99) + "\n";
+        let mut output = Vec::new();
+        let status = write_post_processed_output(&make_cmd(&["cat"]), &mut output, |writer| {
+            for _ in 0..10000 {
+                writer.write_all(line.as_bytes())?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert!(status.success());
+        assert_eq!(output.len(), 10000 * line.len());
+    }
+
+    #[test]
+    fn test_write_post_processed_output_exit_status() {
+        let mut output = Vec::new();
+        let status = write_post_processed_output(
+            &make_cmd(&["sh", "-c", "cat; exit 3"]),
+            &mut output,
+            |writer| writer.write_all(b"a\n"),
+        )
+        .unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(output, b"a\n");
+    }
+}
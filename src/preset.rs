@@ -218,6 +218,33 @@ mod tests {
         remove_file(git_config_path).unwrap();
     }
 
+    #[test]
+    fn test_git_config_section() {
+        let git_config_contents = b"
+[delta]
+    minus-style = blue
+    plus-style = blue
+
+[delta \"my-profile\"]
+    minus-style = red
+";
+        let git_config_path = "delta__test_git_config_section.gitconfig";
+
+        let config = make_config(
+            &["--git-config-section", "my-profile"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(config.minus_style, make_style("red"));
+        // The [delta] section is not used.
+        assert_ne!(config.plus_style, make_style("blue"));
+
+        let config = make_config(&[], Some(git_config_contents), Some(git_config_path));
+        assert_eq!(config.minus_style, make_style("blue"));
+
+        remove_file(git_config_path).unwrap();
+    }
+
     #[test]
     fn test_preset() {
         let git_config_contents = b"
@@ -295,6 +322,35 @@ mod tests {
         remove_file(git_config_path).unwrap();
     }
 
+    #[test]
+    fn test_features_activate_presets() {
+        let git_config_contents = b"
+[delta]
+    minus-style = blue
+    presets = my-preset-2
+
+[delta \"my-preset-1\"]
+    minus-style = green
+    plus-style = green
+
+[delta \"my-preset-2\"]
+    minus-style = yellow
+";
+        let git_config_path = "delta__test_features_activate_presets.gitconfig";
+
+        let config = make_config(
+            &["--features", "my-preset-1 navigate"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        // The presets set in git config take precedence over those activated as features.
+        assert_eq!(config.minus_style, make_style("yellow"));
+        assert_eq!(config.plus_style, make_style("green"));
+        assert!(config.navigate);
+
+        remove_file(git_config_path).unwrap();
+    }
+
     #[test]
     fn test_invalid_presets() {
         let git_config_contents = b"
@@ -429,6 +485,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_diff_so_fancy_compat() {
+        let config = make_config(&["--diff-so-fancy-compat"], None, None);
+
+        assert_eq!(
+            config.file_style.ansi_term_style,
+            make_style("bold cyan").ansi_term_style
+        );
+        assert_eq!(
+            config.file_style.decoration_style,
+            make_decoration_style("bold cyan ul ol")
+        );
+        assert_eq!(
+            config.hunk_header_style.ansi_term_style,
+            make_style("bold cyan").ansi_term_style
+        );
+        assert_eq!(
+            config.hunk_header_style.decoration_style,
+            make_decoration_style("none")
+        );
+
+        assert_eq!(config.minus_style, make_style("bold red"));
+        assert_eq!(config.minus_emph_style, make_emph_style("bold red 52"));
+        assert_eq!(config.zero_style, make_style("normal"));
+        assert_eq!(config.plus_style, make_style("bold green"));
+        assert_eq!(config.plus_emph_style, make_emph_style("bold green 22"));
+    }
+
     #[test]
     fn test_diff_so_fancy_respects_git_config() {
         let git_config_contents = b"
@@ -529,6 +613,21 @@ mod tests {
         remove_file(git_config_path).unwrap();
     }
 
+    #[test]
+    fn test_show_stats() {
+        let git_config_contents = b"
+[delta]
+    show-stats = true
+";
+        let git_config_path = "delta__test_show_stats.gitconfig";
+
+        // The command line flag is --stats, but the option name is that of its clap argument.
+        assert!(!make_config(&[], None, None).show_stats);
+        assert!(make_config(&[], Some(git_config_contents), Some(git_config_path)).show_stats);
+
+        remove_file(git_config_path).unwrap();
+    }
+
     fn make_style(s: &str) -> Style {
         _make_style(s, false)
     }
@@ -558,7 +657,13 @@ mod tests {
         path: Option<&str>,
     ) -> config::Config<'a> {
         let args: Vec<&str> = itertools::chain(
-            &["/dev/null", "/dev/null", "--24-bit-color", "always"],
+            &[
+                "/dev/null",
+                "/dev/null",
+                "--24-bit-color",
+                "always",
+                "--test-no-pager",
+            ],
             args,
         )
         .map(|s| *s)
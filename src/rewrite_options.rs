@@ -7,17 +7,122 @@ use std::process;
 use structopt::clap;
 
 use crate::cli;
-use crate::config::user_supplied_option;
+use crate::config::{self, user_supplied_option};
+
+/// The built-in features that can be activated by --features (or DELTA_FEATURES), with the option
+/// values that each sets. Other feature names are names of presets.
+const FEATURES: &[(&str, &[(&str, &str)])] = &[
+    ("color-only", &[("color-only", "true")]),
+    ("diff-color-words", &[("diff-color-words", "true")]),
+    ("line-numbers", &[("number", "true")]),
+    ("navigate", &[("navigate", "true")]),
+    (
+        "no-decorations",
+        &[
+            ("commit-decoration-style", "none"),
+            ("file-decoration-style", "none"),
+            ("hunk-header-decoration-style", "none"),
+        ],
+    ),
+];
+
+pub fn is_builtin_feature(name: &str) -> bool {
+    FEATURES.iter().any(|(feature, _)| *feature == name)
+}
 
 pub fn apply_rewrite_rules(opt: &mut cli::Opt, arg_matches: &clap::ArgMatches) {
+    rewrite_options_to_implement_features(opt, arg_matches);
+    rewrite_options_to_implement_diff_highlight_compat(opt, arg_matches);
+    rewrite_options_to_implement_diff_so_fancy_compat(opt, arg_matches);
     rewrite_style_strings_to_honor_deprecated_minus_plus_options(opt);
+    rewrite_options_to_implement_syntax_highlight_removed_and_added(opt);
+    rewrite_options_to_implement_show_line_numbers_for_context(opt);
+    rewrite_options_to_implement_indent_heuristic(opt);
     rewrite_options_to_implement_deprecated_commit_and_file_style_box_option(opt);
     rewrite_options_to_implement_deprecated_hunk_style_option(opt);
+    rewrite_options_to_implement_hunk_header_decoration_style_precedence(opt, arg_matches);
     rewrite_options_to_implement_deprecated_theme_option(opt, arg_matches);
+    rewrite_options_to_implement_commit_and_file_style_none(opt);
+    rewrite_options_to_implement_unicode_symbols(opt, arg_matches);
     rewrite_options_to_implement_color_only(opt);
+    rewrite_options_to_implement_diff_color_words(opt, arg_matches);
     rewrite_options_to_implement_navigate(opt, arg_matches);
 }
 
+/// Implement the built-in features named by --features. An option set on the command line takes
+/// precedence over a feature.
+fn rewrite_options_to_implement_features(opt: &mut cli::Opt, arg_matches: &clap::ArgMatches) {
+    let features = match opt.features.as_ref() {
+        Some(features) => features.to_lowercase(),
+        None => return,
+    };
+    for feature in features.split_whitespace() {
+        if let Some((_, options)) = FEATURES.iter().find(|(name, _)| *name == feature) {
+            for (option_name, value) in options.iter() {
+                if !user_supplied_option(option_name, arg_matches) {
+                    set_feature_option(opt, option_name, value);
+                }
+            }
+        }
+    }
+}
+
+fn set_feature_option(opt: &mut cli::Opt, option_name: &str, value: &str) {
+    match option_name {
+        "color-only" => opt.color_only = value == "true",
+        "diff-color-words" => opt.diff_color_words = value == "true",
+        "navigate" => opt.navigate = value == "true",
+        "number" => opt.show_line_numbers = value == "true",
+        "commit-decoration-style" => opt.commit_decoration_style = value.to_string(),
+        "file-decoration-style" => opt.file_decoration_style = value.to_string(),
+        "hunk-header-decoration-style" => opt.hunk_header_decoration_style = value.to_string(),
+        _ => config::unreachable(&format!("Unknown option in feature: {}.", option_name)),
+    }
+}
+
+/// Implement --syntax-highlight-removed and --syntax-highlight-added. These are command-line
+/// flags, so they take precedence over the --no-* variants when those are set in git config.
+fn rewrite_options_to_implement_syntax_highlight_removed_and_added(opt: &mut cli::Opt) {
+    if opt.syntax_highlight_removed {
+        opt.no_syntax_highlight_removed = false;
+        if opt.minus_style == "normal auto" {
+            opt.minus_style = "syntax auto".to_string();
+        }
+        if opt.minus_emph_style == "normal auto" {
+            opt.minus_emph_style = "syntax auto".to_string();
+        }
+    }
+    if opt.syntax_highlight_added {
+        opt.no_syntax_highlight_added = false;
+    }
+}
+
+/// Implement --show-line-numbers-for-context, which as a command-line flag takes precedence over
+/// --no-show-line-numbers-for-context set in git config.
+fn rewrite_options_to_implement_show_line_numbers_for_context(opt: &mut cli::Opt) {
+    if opt.show_line_numbers_for_context {
+        opt.no_show_line_numbers_for_context = false;
+    }
+}
+
+/// Implement --indent-heuristic, which as a command-line flag takes precedence over
+/// --no-indent-heuristic set in git config.
+fn rewrite_options_to_implement_indent_heuristic(opt: &mut cli::Opt) {
+    if opt.indent_heuristic {
+        opt.no_indent_heuristic = false;
+    }
+}
+
+/// Implement --commit-style none and --file-style none, which omit the commit or file header
+/// entirely (the same as the special style 'omit') rather than writing it without color.
+fn rewrite_options_to_implement_commit_and_file_style_none(opt: &mut cli::Opt) {
+    for style in [&mut opt.commit_style, &mut opt.file_style] {
+        if style.trim() == "none" {
+            *style = "omit".to_string();
+        }
+    }
+}
+
 /// Implement --color-only
 fn rewrite_options_to_implement_color_only(opt: &mut cli::Opt) {
     if opt.color_only {
@@ -29,6 +134,102 @@ fn rewrite_options_to_implement_color_only(opt: &mut cli::Opt) {
         opt.file_decoration_style = "none".to_string();
         opt.hunk_header_style = "raw".to_string();
         opt.hunk_header_decoration_style = "none".to_string();
+        opt.minus_empty_line_marker = "".to_string();
+        opt.plus_empty_line_marker = "".to_string();
+    }
+}
+
+/// Implement --unicode-symbols
+fn rewrite_options_to_implement_unicode_symbols(
+    opt: &mut cli::Opt,
+    arg_matches: &clap::ArgMatches,
+) {
+    if opt.unicode_symbols {
+        for (option_name, field, value) in [
+            ("file-added-label", &mut opt.file_added_label, "✚"),
+            ("file-removed-label", &mut opt.file_removed_label, "✕"),
+            ("file-renamed-label", &mut opt.file_renamed_label, "→"),
+            ("file-copied-label", &mut opt.file_copied_label, "⊕"),
+        ] {
+            if !user_supplied_option(option_name, arg_matches) {
+                *field = value.to_string();
+            }
+        }
+    }
+}
+
+/// Implement --diff-highlight-compat
+fn rewrite_options_to_implement_diff_highlight_compat(
+    opt: &mut cli::Opt,
+    arg_matches: &clap::ArgMatches,
+) {
+    if opt.diff_highlight_compat {
+        for (option_name, field, value) in [
+            ("minus-style", &mut opt.minus_style, "red 52"),
+            ("minus-emph-style", &mut opt.minus_emph_style, "red bold 52"),
+            ("plus-style", &mut opt.plus_style, "green 22"),
+            ("plus-emph-style", &mut opt.plus_emph_style, "green bold 22"),
+        ] {
+            if !user_supplied_option(option_name, arg_matches) {
+                *field = value.to_string();
+            }
+        }
+    }
+}
+
+/// Implement --diff-so-fancy-compat
+fn rewrite_options_to_implement_diff_so_fancy_compat(
+    opt: &mut cli::Opt,
+    arg_matches: &clap::ArgMatches,
+) {
+    if opt.diff_so_fancy_compat {
+        for (option_name, field, value) in [
+            ("file-style", &mut opt.file_style, "bold cyan"),
+            (
+                "file-decoration-style",
+                &mut opt.file_decoration_style,
+                "bold cyan ul ol",
+            ),
+            ("hunk-header-style", &mut opt.hunk_header_style, "bold cyan"),
+            (
+                "hunk-header-decoration-style",
+                &mut opt.hunk_header_decoration_style,
+                "none",
+            ),
+            ("minus-style", &mut opt.minus_style, "bold red"),
+            ("minus-emph-style", &mut opt.minus_emph_style, "bold red 52"),
+            ("zero-style", &mut opt.zero_style, "normal"),
+            ("plus-style", &mut opt.plus_style, "bold green"),
+            ("plus-emph-style", &mut opt.plus_emph_style, "bold green 22"),
+        ] {
+            if !user_supplied_option(option_name, arg_matches) {
+                *field = value.to_string();
+            }
+        }
+    }
+}
+
+/// Implement --diff-color-words
+fn rewrite_options_to_implement_diff_color_words(
+    opt: &mut cli::Opt,
+    arg_matches: &clap::ArgMatches,
+) {
+    if opt.diff_color_words {
+        for (option_name, field, value) in [
+            (
+                "tokenization-regex",
+                &mut opt.tokenization_regex,
+                "[^[:space:]]+",
+            ),
+            ("minus-style", &mut opt.minus_style, "red"),
+            ("minus-emph-style", &mut opt.minus_emph_style, "red bold"),
+            ("plus-style", &mut opt.plus_style, "green"),
+            ("plus-emph-style", &mut opt.plus_emph_style, "green bold"),
+        ] {
+            if !user_supplied_option(option_name, arg_matches) {
+                *field = value.to_string();
+            }
+        }
     }
 }
 
@@ -142,6 +343,29 @@ fn rewrite_options_to_implement_deprecated_hunk_style_option(opt: &mut cli::Opt)
     }
 }
 
+/// A --hunk-header-decoration-style given on the command line takes precedence over the
+/// decoration attributes (box, underline, overline) of --hunk-header-style, which are removed.
+/// Otherwise, they replace the kind of decoration, e.g. --hunk-header-style 'syntax underline'
+/// means an underline in the color of the decoration style.
+fn rewrite_options_to_implement_hunk_header_decoration_style_precedence(
+    opt: &mut cli::Opt,
+    arg_matches: &clap::ArgMatches,
+) {
+    if user_supplied_option("hunk-header-decoration-style", arg_matches) {
+        opt.hunk_header_style = opt
+            .hunk_header_style
+            .split_whitespace()
+            .filter(|word| {
+                !matches!(
+                    word.to_lowercase().trim_matches(|c| c == '"' || c == '\''),
+                    "box" | "underline" | "overline"
+                )
+            })
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
+}
+
 fn _get_rewritten_commit_file_hunk_header_style_string(
     style_default_pair: (&str, Option<&str>),
     deprecated_args_style_pair: (Option<&str>, Option<&str>),
@@ -243,6 +467,154 @@ mod tests {
         assert_eq!(opt.hunk_header_decoration_style, "underline");
     }
 
+    #[test]
+    fn test_hunk_header_decoration_style_precedence() {
+        let args = ["delta", "--hunk-header-style", "syntax Box bold"];
+        let arg_matches = cli::Opt::clap().get_matches_from(args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.hunk_header_style, "syntax Box bold");
+
+        // The decoration style takes precedence even when given with its default value.
+        let args = [
+            "delta",
+            "--hunk-header-style",
+            "syntax Box bold",
+            "--hunk-header-decoration-style",
+            "blue box",
+        ];
+        let arg_matches = cli::Opt::clap().get_matches_from(args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.hunk_header_style, "syntax bold");
+        assert_eq!(opt.hunk_header_decoration_style, "blue box");
+    }
+
+    #[test]
+    fn test_features() {
+        let args = ["delta", "--features", "Navigate no-decorations my-preset"];
+        let arg_matches = cli::Opt::clap().get_matches_from(args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert!(opt.navigate);
+        assert_eq!(opt.file_modified_label, "Δ");
+        assert_eq!(opt.commit_decoration_style, "none");
+        assert_eq!(opt.file_decoration_style, "none");
+        assert_eq!(opt.hunk_header_decoration_style, "none");
+
+        // Options given on the command line take precedence over features.
+        let args = [
+            "delta",
+            "--features",
+            "no-decorations",
+            "--file-decoration-style",
+            "box",
+        ];
+        let arg_matches = cli::Opt::clap().get_matches_from(args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.commit_decoration_style, "none");
+        assert_eq!(opt.file_decoration_style, "box");
+    }
+
+    #[test]
+    fn test_file_style_none() {
+        let mut opt = cli::Opt::from_iter(Vec::<OsString>::new());
+        opt.file_style = "none".to_string();
+        apply_rewrite_rules(&mut opt, &clap::ArgMatches::new());
+        assert_eq!(opt.file_style, "omit");
+    }
+
+    #[test]
+    fn test_commit_style_none() {
+        let mut opt = cli::Opt::from_iter(Vec::<OsString>::new());
+        opt.commit_style = "none".to_string();
+        apply_rewrite_rules(&mut opt, &clap::ArgMatches::new());
+        assert_eq!(opt.commit_style, "omit");
+    }
+
+    #[test]
+    fn test_diff_color_words() {
+        let mut opt = cli::Opt::from_iter(Vec::<OsString>::new());
+        opt.diff_color_words = true;
+        apply_rewrite_rules(&mut opt, &clap::ArgMatches::new());
+        assert_eq!(opt.tokenization_regex, "[^[:space:]]+");
+        assert_eq!(opt.minus_style, "red");
+        assert_eq!(opt.minus_emph_style, "red bold");
+        assert_eq!(opt.plus_style, "green");
+        assert_eq!(opt.plus_emph_style, "green bold");
+    }
+
+    #[test]
+    fn test_diff_color_words_respects_user_supplied_options() {
+        let args = vec!["delta", "--diff-color-words", "--plus-emph-style", "blue"];
+        let arg_matches = cli::Opt::clap().get_matches_from(&args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.minus_emph_style, "red bold");
+        assert_eq!(opt.plus_emph_style, "blue");
+    }
+
+    #[test]
+    fn test_diff_highlight_compat() {
+        let args = vec!["delta", "--diff-highlight-compat", "--minus-style", "red"];
+        let arg_matches = cli::Opt::clap().get_matches_from(&args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.minus_style, "red");
+        assert_eq!(opt.minus_emph_style, "red bold 52");
+        assert_eq!(opt.plus_style, "green 22");
+        assert_eq!(opt.plus_emph_style, "green bold 22");
+    }
+
+    #[test]
+    fn test_unicode_symbols() {
+        let args = vec!["delta", "--unicode-symbols", "--file-added-label", "new:"];
+        let arg_matches = cli::Opt::clap().get_matches_from(args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.file_added_label, "new:");
+        assert_eq!(opt.file_removed_label, "✕");
+        assert_eq!(opt.file_renamed_label, "→");
+        assert_eq!(opt.file_copied_label, "⊕");
+    }
+
+    #[test]
+    fn test_diff_so_fancy_compat() {
+        let mut opt = cli::Opt::from_iter(Vec::<OsString>::new());
+        opt.diff_so_fancy_compat = true;
+        apply_rewrite_rules(&mut opt, &clap::ArgMatches::new());
+        assert_eq!(opt.file_style, "bold cyan");
+        assert_eq!(opt.file_decoration_style, "bold cyan ul ol");
+        assert_eq!(opt.hunk_header_style, "bold cyan");
+        assert_eq!(opt.hunk_header_decoration_style, "none");
+        assert_eq!(opt.minus_style, "bold red");
+        assert_eq!(opt.minus_emph_style, "bold red 52");
+        assert_eq!(opt.zero_style, "normal");
+        assert_eq!(opt.plus_style, "bold green");
+        assert_eq!(opt.plus_emph_style, "bold green 22");
+    }
+
+    #[test]
+    fn test_diff_so_fancy_compat_respects_user_supplied_options() {
+        let args = vec![
+            "delta",
+            "--diff-so-fancy-compat",
+            "--hunk-header-style",
+            "magenta",
+            "--plus-style",
+            "green",
+        ];
+        let arg_matches = cli::Opt::clap().get_matches_from(&args);
+        let mut opt = cli::Opt::from_clap(&arg_matches);
+        apply_rewrite_rules(&mut opt, &arg_matches);
+        assert_eq!(opt.file_style, "bold cyan");
+        assert_eq!(opt.hunk_header_style, "magenta");
+        assert_eq!(opt.minus_style, "bold red");
+        assert_eq!(opt.plus_style, "green");
+        assert_eq!(opt.plus_emph_style, "bold green 22");
+    }
+
     #[test]
     fn test_deprecated_hunk_style_is_not_rewritten() {
         let mut opt = cli::Opt::from_iter(Vec::<OsString>::new());
@@ -2,8 +2,10 @@ use std::collections::HashMap;
 use structopt::clap;
 
 use crate::cli;
+use crate::config_file::ConfigFile;
 use crate::git_config::{self, GitConfigGet};
 use crate::preset::{self, GetValueFunctionFromBuiltinPreset};
+use crate::rewrite_options;
 
 // A type T implementing this trait gains a static method allowing an option value of type T to be
 // sought, obeying delta's standard rules for looking up option values. It is implemented for T in
@@ -16,7 +18,8 @@ trait GetOptionValue {
     //    1.1 The value of n under p interpreted as a user-supplied preset (i.e. git config value
     //        delta.$p.$n)
     //    1.2 The value for n under p interpreted as a builtin preset
-    // 3. The value for n in the main git config section for delta (i.e. git config value delta.$n)
+    // 3. The value for n in the main git config section for delta (i.e. git config value delta.$n,
+    //    or delta.$s.$n under --git-config-section $s)
     fn get_option_value(
         option_name: &str,
         builtin_presets: &HashMap<String, preset::BuiltinPreset<String>>,
@@ -42,7 +45,11 @@ trait GetOptionValue {
             }
         }
         if let Some(git_config) = git_config {
-            if let Some(value) = git_config.get::<Self>(&format!("delta.{}", option_name)) {
+            let section = match &opt.git_config_section {
+                Some(section) => format!("delta.{}", section),
+                None => "delta".to_string(),
+            };
+            if let Some(value) = git_config.get::<Self>(&format!("{}.{}", section, option_name)) {
                 return Some(value);
             }
         }
@@ -158,26 +165,114 @@ mod set_options {
             )*
 	    };
     }
+
+    macro_rules! set_options__option_usize {
+	    ([$( ($option_name:expr, $field_ident:ident) ),* ],
+         $opt:expr, $arg_matches:expr, $git_config:expr) => {
+            let builtin_presets = $crate::preset::make_builtin_presets(); // TODO: move up the stack
+            $(
+                 if !$crate::config::user_supplied_option($option_name, $arg_matches) {
+                    if let Some(value) = i64::get_option_value($option_name, &builtin_presets, $opt, $git_config) {
+                        $opt.$field_ident = Some(value as usize);
+                    }
+                };
+            )*
+	    };
+    }
+
+    macro_rules! set_options__option_u64 {
+	    ([$( ($option_name:expr, $field_ident:ident) ),* ],
+         $opt:expr, $arg_matches:expr, $git_config:expr) => {
+            let builtin_presets = $crate::preset::make_builtin_presets(); // TODO: move up the stack
+            $(
+                 if !$crate::config::user_supplied_option($option_name, $arg_matches) {
+                    if let Some(value) = i64::get_option_value($option_name, &builtin_presets, $opt, $git_config) {
+                        $opt.$field_ident = Some(value as u64);
+                    }
+                };
+            )*
+	    };
+    }
 }
 
 pub fn set_options(
     opt: &mut cli::Opt,
     git_config: &mut Option<git_config::GitConfig>,
     arg_matches: &clap::ArgMatches,
+    file_preset: Option<&str>,
 ) {
-    if opt.no_gitconfig {
+    if let Some(config_file_path) = opt.config_file.as_ref() {
+        let config_file = ConfigFile::from_path(config_file_path);
+        match git_config {
+            Some(git_config) if !opt.no_gitconfig => git_config.set_config_file(config_file),
+            _ => *git_config = Some(git_config::GitConfig::from_config_file(config_file)),
+        }
+    } else if opt.no_gitconfig {
         return;
     }
     // --presets must be set first
     set_options__option_string!([("presets", presets)], opt, arg_matches, git_config);
+    // Features that are not built in are presets, activated before any others.
+    if let Some(features) = opt.features.as_ref() {
+        let feature_presets = features
+            .split_whitespace()
+            .filter(|feature| !rewrite_options::is_builtin_feature(&feature.to_lowercase()))
+            .chain(opt.presets.iter().map(String::as_str))
+            .collect::<Vec<&str>>()
+            .join(" ");
+        opt.presets = Some(feature_presets).filter(|presets| !presets.is_empty());
+    }
+    if let Some(file_preset) = file_preset {
+        opt.presets = Some(match opt.presets.take() {
+            Some(presets) => format!("{} {}", presets, file_preset),
+            None => file_preset.to_string(),
+        });
+    }
     set_options__bool!(
         [
             ("light", light),
             ("dark", dark),
+            ("auto-dark-light", auto_dark_light),
             ("navigate", navigate),
+            ("per-file-git-attributes", per_file_git_attributes),
+            ("navigate-include-decorations", navigate_include_decorations),
+            ("diff-header-only", diff_header_only),
+            ("hunk-header-always", hunk_header_always),
+            (
+                "hunk-header-include-file-path",
+                hunk_header_include_file_path
+            ),
+            ("emit-null-for-missing-files", emit_null_for_missing_files),
+            ("no-syntax-highlight-removed", no_syntax_highlight_removed),
+            ("no-syntax-highlight-added", no_syntax_highlight_added),
+            ("collapse-renames", collapse_renames),
+            ("show-rename-score", show_rename_score),
+            ("minus-ruler", minus_ruler),
+            ("plus-ruler", plus_ruler),
+            ("submodule-diff-output", submodule_diff_output),
+            ("redact-secrets", redact_secrets),
+            ("tabs-to-spaces", tabs_to_spaces),
+            ("strip-trailing-cr", strip_trailing_cr),
+            ("show-whitespace-errors", show_whitespace_errors),
+            ("ignore-whitespace-change", ignore_whitespace_change),
+            ("highlight-copied-lines", highlight_copied_lines),
+            ("color-moved", color_moved),
+            ("show-stats", show_stats),
             ("color-only", color_only),
+            ("diff-color-words", diff_color_words),
+            ("diff-highlight-compat", diff_highlight_compat),
+            ("unicode-symbols", unicode_symbols),
+            ("diff-so-fancy-compat", diff_so_fancy_compat),
             ("keep-plus-minus-markers", keep_plus_minus_markers),
-            ("number", show_line_numbers)
+            ("line-numbers-for-hunks-only", line_numbers_for_hunks_only),
+            ("no-indent-heuristic", no_indent_heuristic),
+            ("number", show_line_numbers),
+            (
+                "no-show-line-numbers-for-context",
+                no_show_line_numbers_for_context
+            ),
+            ("number-right-align", number_right_align),
+            ("highlight-line-numbers", highlight_line_numbers)
         ],
         opt,
         arg_matches,
@@ -191,9 +286,13 @@ pub fn set_options(
     );
     set_options__string!(
         [
+            ("box-style", box_style),
             ("commit-decoration-style", commit_decoration_style),
             ("commit-style", commit_style),
+            ("diff-stat-file-rename-format", diff_stat_file_rename_format),
+            ("ellipsis-style", ellipsis_style),
             ("file-added-label", file_added_label),
+            ("file-copied-label", file_copied_label),
             ("file-decoration-style", file_decoration_style),
             ("file-modified-label", file_modified_label),
             ("file-removed-label", file_removed_label),
@@ -201,24 +300,45 @@ pub fn set_options(
             ("file-style", file_style),
             ("hunk-header-decoration-style", hunk_header_decoration_style),
             ("hunk-header-style", hunk_header_style),
+            ("intra-line-diff-algorithm", intra_line_diff_algorithm),
+            ("line-fill-method", line_fill_method),
             // Hack: minus-style must come before minus-*emph-style because the latter default
             // dynamically to the value of the former.
             ("minus-style", minus_style),
             ("minus-emph-style", minus_emph_style),
             ("minus-non-emph-style", minus_non_emph_style),
+            ("minus-empty-line-marker", minus_empty_line_marker),
+            (
+                "minus-empty-line-marker-style",
+                minus_empty_line_marker_style
+            ),
+            ("moved-style", moved_style),
+            ("color-moved-style", color_moved_style),
+            ("color-moved-alternate-style", color_moved_alternate_style),
+            ("null-file-indicator", null_file_indicator),
+            ("number-column-placement", number_column_placement),
             ("number-minus-format", number_minus_format),
             ("number-minus-format-style", number_minus_format_style),
             ("number-minus-style", number_minus_style),
             ("number-plus-format", number_plus_format),
             ("number-plus-format-style", number_plus_format_style),
             ("number-plus-style", number_plus_style),
+            ("number-separator", number_separator),
+            ("number-separator-style", number_separator_style),
+            (
+                "number-gutter-background-color",
+                number_gutter_background_color
+            ),
             ("paging-mode", paging_mode),
             // Hack: plus-style must come before plus-*emph-style because the latter default
             // dynamically to the value of the former.
             ("plus-style", plus_style),
             ("plus-emph-style", plus_emph_style),
             ("plus-non-emph-style", plus_non_emph_style),
+            ("plus-empty-line-marker", plus_empty_line_marker),
+            ("plus-empty-line-marker-style", plus_empty_line_marker_style),
             ("true-color", true_color),
+            ("whitespace-error-style", whitespace_error_style),
             ("word-diff-regex", tokenization_regex),
             ("zero-style", zero_style)
         ],
@@ -227,10 +347,73 @@ pub fn set_options(
         git_config
     );
     set_options__option_string!(
-        [("syntax_theme", syntax_theme), ("width", width)],
+        [
+            ("commit-format", commit_format),
+            (
+                "commit-style-by-author-regexp",
+                commit_style_by_author_regexp
+            ),
+            ("context-style", context_style),
+            ("decoration-style-fallback", decoration_style_fallback),
+            ("diff-filter", diff_filter),
+            ("file-path-truncation-prefix", file_path_truncation_prefix),
+            ("file-renamed-new-style", file_renamed_new_style),
+            ("file-renamed-old-style", file_renamed_old_style),
+            ("line-numbers-format", line_numbers_format),
+            ("navigate-regex", navigate_regex),
+            ("pager-args", pager_args),
+            ("pager-less-options", pager_less_options),
+            ("plus-emphasis-regexp", plus_emphasis_regexp),
+            ("post-process-cmd", post_process_cmd),
+            ("stash-style", stash_style),
+            ("syntax_theme", syntax_theme),
+            ("theme-set-dir", theme_set_dir),
+            (
+                "tokenization-regex-by-language",
+                tokenization_regex_by_language
+            ),
+            ("true-color-fallback-map", true_color_fallback_map),
+            ("whitespace-change-style", whitespace_change_style),
+            ("width", width)
+        ],
+        opt,
+        arg_matches,
+        git_config
+    );
+    set_options__usize!(
+        [
+            ("conflict-marker-size", conflict_marker_size),
+            ("decoration-fallback-width", decoration_fallback_width),
+            ("decrement-leading-spaces", decrement_leading_spaces),
+            ("io-buffer-size", io_buffer_size),
+            ("tabs", tab_width),
+            ("width-minus-left-margin", width_minus_left_margin),
+            ("width-minus-padding", width_minus_padding)
+        ],
+        opt,
+        arg_matches,
+        git_config
+    );
+    set_options__option_usize!(
+        [
+            ("empty-line-context", empty_line_context),
+            (
+                "hunk-header-function-hint-max-length",
+                hunk_header_function_hint_max_length
+            ),
+            ("pager-quit-if-smaller-than", pager_quit_if_smaller_than)
+        ],
+        opt,
+        arg_matches,
+        git_config
+    );
+    set_options__option_u64!(
+        [
+            ("binary-hex-dump-max-bytes", binary_hex_dump_max_bytes),
+            ("max-file-size", max_file_size)
+        ],
         opt,
         arg_matches,
         git_config
     );
-    set_options__usize!([("tabs", tab_width)], opt, arg_matches, git_config);
 }
@@ -37,6 +37,12 @@ bitflags! {
     }
 }
 
+impl Default for Style {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Style {
     pub fn new() -> Self {
         Self {
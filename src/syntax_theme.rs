@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use syntect::highlighting::ThemeSet;
 
 const LIGHT_THEMES: [&str; 5] = [
@@ -15,6 +17,29 @@ pub fn is_light_theme(theme: &str) -> bool {
     LIGHT_THEMES.contains(&theme)
 }
 
+/// Add the themes in the .tmTheme files in `dir` (for --theme-set-dir) to `theme_set`. Each theme
+/// is named after its file, without the extension. A theme with the same name as one already in
+/// the set replaces it, with a warning.
+pub fn add_themes_from_dir(theme_set: &mut ThemeSet, dir: &Path) -> Result<(), String> {
+    let theme_paths = ThemeSet::discover_theme_paths(dir).map_err(|err| err.to_string())?;
+    for path in theme_paths {
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let theme =
+            ThemeSet::get_theme(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if theme_set.themes.insert(name.clone(), theme).is_some() {
+            eprintln!(
+                "Warning: the syntax theme {} in {} replaces the built-in theme of that name.",
+                name,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
 pub fn is_no_syntax_highlighting_theme_name(theme_name: &str) -> bool {
     theme_name.to_lowercase() == "none"
 }
@@ -0,0 +1,140 @@
+//! Detection of the terminal's background color, for --auto-dark-light. The terminal is sent the
+//! OSC 11 query `ESC ] 11 ; ? BEL`, to which terminals that support it reply with their
+//! background color in the form `ESC ] 11 ; rgb:RRRR/GGGG/BBBB BEL` (or terminated by `ESC \`).
+use std::time::Duration;
+
+const OSC_11_QUERY: &[u8] = b"\x1b]11;?\x07";
+
+/// How long to wait for the terminal to answer.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Return whether the terminal's background is light, or None if it does not report its
+/// background color in time.
+pub fn is_light_background() -> Option<bool> {
+    let response = query_terminal(OSC_11_QUERY)?;
+    let (r, g, b) = parse_osc_11_response(&String::from_utf8_lossy(&response))?;
+    Some(get_perceived_luminance(r, g, b) > 0.5)
+}
+
+/// Return the red, green and blue components, each between 0 and 1, of the color reported in an
+/// answer to the OSC 11 query.
+fn parse_osc_11_response(response: &str) -> Option<(f64, f64, f64)> {
+    let start = response.find("]11;rgb:")? + "]11;rgb:".len();
+    let color = response[start..].trim_end_matches(&['\x07', '\x1b', '\\'][..]);
+    let components: Vec<f64> = color
+        .split('/')
+        .map(parse_color_component)
+        .collect::<Option<_>>()?;
+    match components.as_slice() {
+        [r, g, b] => Some((*r, *g, *b)),
+        _ => None,
+    }
+}
+
+/// Parse a component of 1 to 4 hex digits, scaled to the range 0 to 1.
+fn parse_color_component(component: &str) -> Option<f64> {
+    if component.is_empty() || component.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(component, 16).ok()?;
+    let max = (1u32 << (4 * component.len())) - 1;
+    Some(value as f64 / max as f64)
+}
+
+fn get_perceived_luminance(r: f64, g: f64, b: f64) -> f64 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Write `query` to the terminal and return its answer, read with the terminal in non-canonical
+/// mode so that the answer is not echoed and need not end with a newline.
+#[cfg(unix)]
+fn query_terminal(query: &[u8]) -> Option<Vec<u8>> {
+    use std::fs::OpenOptions;
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+    let mut original_attributes: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original_attributes) } != 0 {
+        return None;
+    }
+    let mut attributes = original_attributes;
+    attributes.c_lflag &= !(libc::ICANON | libc::ECHO);
+    attributes.c_cc[libc::VMIN] = 0;
+    attributes.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &attributes) } != 0 {
+        return None;
+    }
+    let response = read_response(&mut tty, query);
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original_attributes) };
+    response
+}
+
+#[cfg(unix)]
+fn read_response(tty: &mut std::fs::File, query: &[u8]) -> Option<Vec<u8>> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    tty.write_all(query).ok()?;
+    tty.flush().ok()?;
+    let deadline = Instant::now() + TIMEOUT;
+    let mut response = Vec::new();
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        let mut poll_fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll_fd, 1, remaining.as_millis() as libc::c_int) } <= 0 {
+            return None;
+        }
+        let mut buffer = [0; 64];
+        match tty.read(&mut buffer).ok()? {
+            0 => return None,
+            n => response.extend(&buffer[..n]),
+        }
+        if response.ends_with(b"\x07") || response.ends_with(b"\x1b\\") {
+            return Some(response);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn query_terminal(_query: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osc_11_response() {
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:ffff/8080/0000\x07"),
+            Some((1.0, 0x8080 as f64 / 0xffff as f64, 0.0))
+        );
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:f/ff/fff\x1b\\"),
+            Some((1.0, 1.0, 1.0))
+        );
+        assert_eq!(parse_osc_11_response("\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:ffff/ffff/gggg\x07"),
+            None
+        );
+        assert_eq!(parse_osc_11_response("\x1b[?1;2c"), None);
+    }
+
+    #[test]
+    fn test_get_perceived_luminance() {
+        assert!(get_perceived_luminance(1.0, 1.0, 0.9) > 0.5);
+        assert!(get_perceived_luminance(0.0, 0.17, 0.21) < 0.5);
+    }
+}
@@ -95,7 +95,7 @@ pub mod ansi_test_utils {
     pub fn paint_line(line: &str, language_extension: &str, config: &Config) -> String {
         let mut output_buffer = String::new();
         let mut unused_writer = Vec::<u8>::new();
-        let mut painter = paint::Painter::new(&mut unused_writer, config);
+        let mut painter = paint::Painter::new(Box::new(&mut unused_writer), config);
         let syntax_highlighted_style = Style {
             is_syntax_highlighted: true,
             ..Style::new()
@@ -108,9 +108,11 @@ pub mod ansi_test_utils {
             vec![syntax_style_sections],
             vec![vec![(syntax_highlighted_style, lines[0])]],
             vec![None],
+            0,
             &mut output_buffer,
             config,
             "",
+            None,
             config.null_style,
             config.null_style,
             None,
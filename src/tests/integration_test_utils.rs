@@ -17,6 +17,7 @@ pub mod integration_test_utils {
             args.push(arg);
         }
         args.push("--no-gitconfig");
+        args.push("--test-no-pager");
         config::Config::from_args(&args, &mut None)
     }
 
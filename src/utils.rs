@@ -0,0 +1,179 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Expand tabs as spaces, so that the text following each tab starts at the next tab stop, i.e.
+/// the next column that is a multiple of `tab_width`. `line` yields the graphemes of the line.
+/// tab_width = 0 means do not replace tabs.
+pub fn expand_tabs_to_tab_stops<'a, I>(line: I, tab_width: usize) -> String
+where
+    I: Iterator<Item = &'a str>,
+{
+    if tab_width == 0 {
+        return line.collect::<String>();
+    }
+    let mut expanded = String::new();
+    let mut column = 0;
+    for grapheme in line {
+        if grapheme == "\t" {
+            let n_spaces = tab_width - column % tab_width;
+            expanded.push_str(&" ".repeat(n_spaces));
+            column += n_spaces;
+        } else {
+            expanded.push_str(grapheme);
+            column += UnicodeWidthStr::width(grapheme);
+        }
+    }
+    expanded
+}
+
+/// Translate a glob pattern into an anchored regular expression. '*' matches any sequence of
+/// characters, including '/', as in git's default pathspec matching, so that "*.lock" matches
+/// lock files in all directories. '?' matches any single character, and "[...]" matches a
+/// character class, negated by a leading '!'.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' if chars.as_str().contains(']') => {
+                regex.push('[');
+                if chars.as_str().starts_with('!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Return `s` shortened to `max_width` columns followed by `suffix`, if it is wider than that.
+/// ANSI escape sequences in `s` take no columns, and are never split: those following the point
+/// of truncation are kept, so that a style reset at the end of `s` still takes effect.
+pub fn truncate_to_width(s: &str, max_width: usize, suffix: &str) -> String {
+    let mut truncated = String::new();
+    let mut width = 0;
+    let mut is_truncated = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            truncated.push(c);
+            match chars.next() {
+                // CSI sequence, e.g. "\x1b[1;31m": parameters, then a final byte in '@'..='~'.
+                Some('[') => {
+                    truncated.push('[');
+                    for c in chars.by_ref() {
+                        truncated.push(c);
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC sequence, e.g. a hyperlink: terminated by BEL or by ESC \.
+                Some(']') => {
+                    truncated.push(']');
+                    while let Some(c) = chars.next() {
+                        truncated.push(c);
+                        if c == '\x07' {
+                            break;
+                        } else if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            truncated.push(chars.next().unwrap());
+                            break;
+                        }
+                    }
+                }
+                Some(c) => truncated.push(c),
+                None => {}
+            }
+            continue;
+        }
+        if is_truncated {
+            continue;
+        }
+        let char_width = UnicodeWidthChar::width(c).unwrap_or(0);
+        if width + char_width > max_width {
+            truncated.push_str(suffix);
+            is_truncated = true;
+            continue;
+        }
+        width += char_width;
+        truncated.push(c);
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    use super::*;
+
+    fn expand(line: &str, tab_width: usize) -> String {
+        expand_tabs_to_tab_stops(line.graphemes(true), tab_width)
+    }
+
+    #[test]
+    fn test_expand_tabs_to_tab_stops() {
+        assert_eq!(expand("\tx", 4), "    x");
+        assert_eq!(expand("a\tx", 4), "a   x");
+        assert_eq!(expand("abcd\tx", 4), "abcd    x");
+        assert_eq!(expand("ab\tcdefg\tx", 4), "ab  cdefg   x");
+        assert_eq!(expand("\t\tx", 2), "    x");
+        assert_eq!(expand("ab\tx", 0), "ab\tx");
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        let is_match =
+            |glob: &str, path: &str| Regex::new(&glob_to_regex(glob)).unwrap().is_match(path);
+        assert!(is_match("*.lock", "Cargo.lock"));
+        assert!(is_match("*.lock", "web/yarn.lock"));
+        assert!(!is_match("*.lock", "Cargo.lock.bak"));
+        assert!(is_match("*.min.js", "static/app.min.js"));
+        assert!(!is_match("*.min.js", "static/app-min.js"));
+        assert!(is_match("src/?.rs", "src/a.rs"));
+        assert!(!is_match("src/?.rs", "src/ab.rs"));
+        assert!(is_match("[ab].txt", "b.txt"));
+        assert!(!is_match("[!ab].txt", "b.txt"));
+        assert!(is_match("[!ab].txt", "c.txt"));
+        assert!(is_match("a[.txt", "a[.txt"));
+        assert!(is_match("[é].txt", "é.txt"));
+        assert!(is_match("café-*.md", "café-menu.md"));
+        assert!(!is_match("[!é]?.txt", "éa.txt"));
+    }
+
+    #[test]
+    fn test_expand_tabs_to_tab_stops_wide_characters() {
+        assert_eq!(expand("日本\tx", 8), "日本    x");
+        assert_eq!(expand("é\tx", 4), "é   x");
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("abcdef", 6, "…"), "abcdef");
+        assert_eq!(truncate_to_width("abcdef", 3, "…"), "abc…");
+        assert_eq!(truncate_to_width("日本語", 3, "…"), "日…");
+        assert_eq!(
+            truncate_to_width("\x1b[1;31mabc\x1b[0mdef\x1b[m", 4, "…"),
+            "\x1b[1;31mabc\x1b[0md…\x1b[m"
+        );
+        assert_eq!(
+            truncate_to_width("\x1b]8;;file:///a\x1b\\abc\x1b]8;;\x1b\\", 1, "…"),
+            "\x1b]8;;file:///a\x1b\\a…\x1b]8;;\x1b\\"
+        );
+    }
+}